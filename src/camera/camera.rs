@@ -27,4 +27,21 @@ pub trait Camera {
     fn get_data_mut(
         &mut self
     ) -> &mut CameraData;
+
+    fn is_orthographic(
+        &self
+    ) -> bool;
+
+    fn get_zoom(
+        &self
+    ) -> f32 {
+        1.0
+    }
+
+    // cameras with a zoom also update their projection
+    fn set_zoom(
+        &mut self,
+        _zoom: f32
+    ) {
+    }
 }
\ No newline at end of file
@@ -40,7 +40,7 @@ impl OrthographicCamera {
         cam
     }
 
-    fn update_projection_matrix(
+    pub fn update_projection_matrix(
         &mut self
     ) {
         let dx = (self.right - self.left) / (2.0 * self.zoom);
@@ -82,8 +82,9 @@ impl Updatable for OrthographicCamera {
     fn update_matrix(
         &mut self,
     ) {
-        self.cam.obj.update_matrix();
-        self.cam.world_matrix_inverse = self.cam.obj.matrix.invert();
+        // forced, as the scene update skips the camera while it is borrowed for rendering
+        self.cam.obj.update_matrix_world(true);
+        self.cam.world_matrix_inverse = self.cam.obj.world_matrix.invert();
     }
 }
 
@@ -99,6 +100,26 @@ impl Camera for OrthographicCamera {
     ) -> &mut CameraData {
         &mut self.cam
     }
+
+    fn is_orthographic(
+        &self
+    ) -> bool {
+        true
+    }
+
+    fn get_zoom(
+        &self
+    ) -> f32 {
+        self.zoom
+    }
+
+    fn set_zoom(
+        &mut self,
+        zoom: f32
+    ) {
+        self.zoom = zoom;
+        self.update_projection_matrix();
+    }
 }
 
 impl ObjectCamera for OrthographicCamera {
@@ -53,7 +53,7 @@ impl PerspectiveCamera {
         self.film_gauge / self.aspect.max(1.0)
 	}
 
-    fn update_projection_matrix(
+    pub fn update_projection_matrix(
         &mut self
     ) {
         let near = self.near;
@@ -96,8 +96,9 @@ impl Updatable for PerspectiveCamera {
     fn update_matrix(
         &mut self,
     ) {
-        self.cam.obj.update_matrix();
-        self.cam.world_matrix_inverse = self.cam.obj.matrix.invert();
+        // forced, as the scene update skips the camera while it is borrowed for rendering
+        self.cam.obj.update_matrix_world(true);
+        self.cam.world_matrix_inverse = self.cam.obj.world_matrix.invert();
     }
 }
 
@@ -113,6 +114,26 @@ impl Camera for PerspectiveCamera {
     ) -> &mut CameraData {
         &mut self.cam
     }
+
+    fn is_orthographic(
+        &self
+    ) -> bool {
+        false
+    }
+
+    fn get_zoom(
+        &self
+    ) -> f32 {
+        self.zoom
+    }
+
+    fn set_zoom(
+        &mut self,
+        zoom: f32
+    ) {
+        self.zoom = zoom;
+        self.update_projection_matrix();
+    }
 }
 
 impl ObjectCamera for PerspectiveCamera {
@@ -0,0 +1,514 @@
+use crate::math::{Vector3, Capsule, Triangle, Octree, UP};
+
+const EPS: f32 = 1e-5;
+
+// moves a capsule through an octree with gravity, y up. it walks on surfaces up to max_slope,
+// slides along steeper ones, climbs steps up to step_height and keeps to the ground going down.
+// the same inputs and time steps always give the same results
+pub struct CharacterController {
+    pub collider: Capsule,
+    pub velocity: Vector3,
+    // units per second squared
+    pub gravity: f32,
+    // units per second
+    pub jump_speed: f32,
+    pub move_speed: f32,
+    // how fast the horizontal velocity follows the input, per second; off the ground it's scaled by air_control
+    pub acceleration: f32,
+    pub air_control: f32,
+    // radians from up
+    pub max_slope: f32,
+    pub step_height: f32,
+    // collision passes per step
+    pub max_iterations: usize,
+    // longer updates are split in steps of at most this many seconds
+    pub max_step: f32,
+    move_input: Vector3,
+    jump_requested: bool,
+    ground_normal: Option<Vector3>,
+}
+
+impl CharacterController {
+    // the capsule's start is the center of its lower sphere
+    pub fn new(
+        collider: Capsule
+    ) -> Self {
+        Self {
+            collider,
+            velocity: Vector3::zero(),
+            gravity: 30.0,
+            jump_speed: 10.0,
+            move_speed: 6.0,
+            acceleration: 10.0,
+            air_control: 0.2,
+            max_slope: 45f32.to_radians(),
+            step_height: 0.3,
+            max_iterations: 4,
+            max_step: 1.0 / 120.0,
+            move_input: Vector3::zero(),
+            jump_requested: false,
+            ground_normal: None,
+        }
+    }
+
+    // in world space; y is ignored and longer directions are shortened to 1
+    pub fn set_move_input(
+        &mut self,
+        direction: Vector3
+    ) {
+        let input = Vector3::new(direction.x, 0.0, direction.z);
+        let len = input.length();
+        self.move_input = if len > 1.0 {input.div_scalar(len)} else {input};
+    }
+
+    // done by the next update if the character is on the ground
+    pub fn jump(
+        &mut self
+    ) {
+        self.jump_requested = true;
+    }
+
+    pub fn is_grounded(
+        &self
+    ) -> bool {
+        self.ground_normal.is_some()
+    }
+
+    pub fn get_ground_normal(
+        &self
+    ) -> Option<Vector3> {
+        self.ground_normal
+    }
+
+    // radians from up
+    pub fn get_slope_angle(
+        &self
+    ) -> Option<f32> {
+        self.ground_normal
+            .map(|normal| normal.y.clamp(-1.0, 1.0).acos())
+    }
+
+    // dt is in seconds
+    pub fn update(
+        &mut self,
+        octree: &Octree,
+        dt: f32
+    ) {
+        if dt <= 0.0 {
+            return;
+        }
+
+        let steps = (dt / self.max_step).ceil().max(1.0) as usize;
+        for _ in 0..steps {
+            self.step(octree, dt / steps as f32);
+        }
+
+        self.jump_requested = false;
+    }
+
+    fn step(
+        &mut self,
+        octree: &Octree,
+        dt: f32
+    ) {
+        let grounded = self.is_grounded();
+
+        // the horizontal velocity eases towards the input's
+        let control = if grounded {1.0} else {self.air_control};
+        let t = 1.0 - (-self.acceleration * control * dt).exp();
+        let target = self.move_input.mul_scalar(self.move_speed);
+        self.velocity.x += (target.x - self.velocity.x) * t;
+        self.velocity.z += (target.z - self.velocity.z) * t;
+
+        let jumped = grounded && self.jump_requested;
+        if jumped {
+            self.velocity.y = self.jump_speed;
+            self.jump_requested = false;
+        }
+        self.velocity.y -= self.gravity * dt;
+
+        // only walking climbs steps and keeps to the ground
+        let walking = grounded && !jumped && self.step_height > 0.0;
+
+        let motion = self.velocity.mul_scalar(dt);
+        let mut velocity = self.velocity;
+        let (mut collider, mut ground) = self.resolve(
+            octree,
+            self.collider.translate(&motion),
+            &mut velocity,
+            walking
+        );
+
+        // going down slopes and steps without leaving the ground
+        if walking && ground.is_none() {
+            let mut snap_velocity = velocity;
+            let (snapped, snap_ground) = self.resolve(
+                octree,
+                collider.translate(&UP.mul_scalar(-self.step_height)),
+                &mut snap_velocity,
+                true
+            );
+            if snap_ground.is_some() {
+                collider = snapped;
+                ground = snap_ground;
+                velocity = snap_velocity;
+            }
+        }
+
+        self.collider = collider;
+        self.velocity = velocity;
+        self.ground_normal = ground;
+    }
+
+    // pushes the collider out of the triangles until it's free or the passes run out,
+    // removing the velocity that goes into what was hit. with steps, low obstacles lift it instead.
+    // also returns the most upward walkable normal touched
+    fn resolve(
+        &self,
+        octree: &Octree,
+        mut collider: Capsule,
+        velocity: &mut Vector3,
+        steps: bool
+    ) -> (Capsule, Option<Vector3>) {
+        let min_ground_y = self.max_slope.cos();
+        let mut ground: Option<Vector3> = None;
+
+        for _ in 0..self.max_iterations {
+            let mut pushed = false;
+
+            for index in octree.get_capsule_triangles(&collider) {
+                let triangle = &octree.triangles[index as usize];
+                let (normal, _, depth) = match collider.intersecting_triangle(triangle) {
+                    Some(hit) => hit,
+                    None => continue
+                };
+
+                if normal.y >= min_ground_y {
+                    if ground.is_none_or(|ground| normal.y > ground.y) {
+                        ground = Some(normal);
+                    }
+
+                    // lifting instead of pushing along the normal keeps it from sliding down slopes
+                    collider = collider.translate(&UP.mul_scalar(depth / normal.y));
+                    velocity.y = velocity.y.max(0.0);
+                }
+                else if let Some(lifted) = self.step_onto(octree, &collider, triangle, steps) {
+                    collider = lifted;
+                    ground = ground.or(Some(UP));
+                    velocity.y = velocity.y.max(0.0);
+                }
+                else {
+                    collider = collider.translate(&normal.mul_scalar(depth));
+                    let into = velocity.dot(&normal);
+                    if into < 0.0 {
+                        *velocity = velocity.sub(&normal.mul_scalar(into));
+                    }
+                }
+
+                pushed |= depth > EPS;
+            }
+
+            if !pushed {
+                break;
+            }
+        }
+
+        (collider, ground)
+    }
+
+    // the collider lifted onto the triangle's top, when that is at most a step above the feet
+    // and nothing steep is in the way up there
+    fn step_onto(
+        &self,
+        octree: &Octree,
+        collider: &Capsule,
+        triangle: &Triangle,
+        steps: bool
+    ) -> Option<Capsule> {
+        if !steps {
+            return None;
+        }
+
+        let feet = collider.start.y - collider.radius;
+        let rise = triangle.a.y.max(triangle.b.y).max(triangle.c.y) - feet;
+        if rise <= 0.0 || rise > self.step_height {
+            return None;
+        }
+
+        let lifted = collider.translate(&UP.mul_scalar(rise));
+        if self.is_blocked(octree, &lifted) {
+            None
+        }
+        else {
+            Some(lifted)
+        }
+    }
+
+    fn is_blocked(
+        &self,
+        octree: &Octree,
+        collider: &Capsule
+    ) -> bool {
+        let min_ground_y = self.max_slope.cos();
+
+        octree.get_capsule_triangles(collider)
+            .into_iter()
+            .filter_map(|index| collider.intersecting_triangle(&octree.triangles[index as usize]))
+            .any(|(normal, _, depth)| normal.y < min_ground_y && depth > EPS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    // the quad a b c d, facing where (c - b) x (a - b) points
+    fn quad(
+        octree: &mut Octree,
+        a: Vector3,
+        b: Vector3,
+        c: Vector3,
+        d: Vector3
+    ) {
+        octree.add_triangle(Triangle::new(a, b, c));
+        octree.add_triangle(Triangle::new(a, c, d));
+    }
+
+    // facing up at height y, from x0 to x1 and across z in [-5, 5]
+    fn ground(
+        octree: &mut Octree,
+        x0: f32,
+        x1: f32,
+        y: f32
+    ) {
+        quad(
+            octree,
+            Vector3::new(x0, y, -5.0),
+            Vector3::new(x0, y, 5.0),
+            Vector3::new(x1, y, 5.0),
+            Vector3::new(x1, y, -5.0)
+        );
+    }
+
+    // a floor at y = 0 and a block from x = 1 to 5, whose top is at height and whose side faces -x
+    fn block(
+        height: f32
+    ) -> Octree {
+        let mut octree = Octree::new();
+        ground(&mut octree, -5.0, 1.0, 0.0);
+        ground(&mut octree, 1.0, 5.0, height);
+        quad(
+            &mut octree,
+            Vector3::new(1.0, 0.0, -5.0),
+            Vector3::new(1.0, 0.0, 5.0),
+            Vector3::new(1.0, height, 5.0),
+            Vector3::new(1.0, height, -5.0)
+        );
+        octree.build();
+        octree
+    }
+
+    // a floor at y = 0 up to x = 0, then a slope rising towards +x at the angle, in degrees
+    fn slope(
+        degrees: f32
+    ) -> Octree {
+        let top = 4.0 * degrees.to_radians().tan();
+        let mut octree = Octree::new();
+        ground(&mut octree, -5.0, 0.0, 0.0);
+        quad(
+            &mut octree,
+            Vector3::new(0.0, 0.0, -5.0),
+            Vector3::new(0.0, 0.0, 5.0),
+            Vector3::new(4.0, top, 5.0),
+            Vector3::new(4.0, top, -5.0)
+        );
+        octree.build();
+        octree
+    }
+
+    // the lower sphere's center at x and y, the capsule 1.35 tall
+    fn character(
+        x: f32,
+        y: f32
+    ) -> CharacterController {
+        CharacterController::new(Capsule::new(
+            Vector3::new(x, y, 0.0),
+            Vector3::new(x, y + 0.65, 0.0),
+            0.35
+        ))
+    }
+
+    fn run(
+        controller: &mut CharacterController,
+        octree: &Octree,
+        frames: usize
+    ) {
+        for _ in 0..frames {
+            controller.update(octree, DT);
+        }
+    }
+
+    fn assert_near(
+        a: f32,
+        b: f32,
+        eps: f32
+    ) {
+        assert!((a - b).abs() < eps, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn lands_on_the_floor() {
+        let octree = block(0.5);
+        let mut controller = character(-2.0, 1.0);
+        assert!(!controller.is_grounded());
+
+        run(&mut controller, &octree, 60);
+
+        assert!(controller.is_grounded());
+        assert_near(controller.collider.start.y, 0.35, 1e-3);
+        assert_near(controller.get_slope_angle().unwrap(), 0.0, 1e-3);
+        assert_near(controller.velocity.y, 0.0, 1e-3);
+    }
+
+    #[test]
+    fn stands_on_a_walkable_slope() {
+        let octree = slope(30.0);
+        let mut controller = character(2.0, 3.0);
+
+        run(&mut controller, &octree, 60);
+        let x = controller.collider.start.x;
+        run(&mut controller, &octree, 60);
+
+        assert!(controller.is_grounded());
+        assert_near(controller.get_slope_angle().unwrap(), 30f32.to_radians(), 1e-3);
+        // it doesn't slide down
+        assert_near(controller.collider.start.x, x, 1e-3);
+    }
+
+    #[test]
+    fn slides_down_a_steep_slope() {
+        let octree = slope(60.0);
+        let mut controller = character(2.0, 5.0);
+
+        run(&mut controller, &octree, 15);
+        assert!(!controller.is_grounded());
+
+        run(&mut controller, &octree, 120);
+        assert!(controller.is_grounded());
+        assert_near(controller.get_slope_angle().unwrap(), 0.0, 1e-3);
+        assert!(controller.collider.start.x < 0.0);
+    }
+
+    #[test]
+    fn does_not_walk_up_a_steep_slope() {
+        let octree = slope(60.0);
+        let mut controller = character(-2.0, 0.35);
+        controller.set_move_input(Vector3::new(1.0, 0.0, 0.0));
+
+        run(&mut controller, &octree, 120);
+
+        assert!(controller.collider.start.x < 0.0);
+        assert!(controller.collider.start.y < 0.5);
+    }
+
+    #[test]
+    fn climbs_a_low_step() {
+        let octree = block(0.2);
+        let mut controller = character(-1.0, 0.35);
+        controller.set_move_input(Vector3::new(1.0, 0.0, 0.0));
+
+        run(&mut controller, &octree, 60);
+
+        assert!(controller.collider.start.x > 2.0);
+        assert_near(controller.collider.start.y, 0.55, 1e-3);
+        assert!(controller.is_grounded());
+    }
+
+    #[test]
+    fn stops_at_a_high_ledge() {
+        let octree = block(0.5);
+        let mut controller = character(-1.0, 0.35);
+        controller.set_move_input(Vector3::new(1.0, 0.0, 0.0));
+
+        run(&mut controller, &octree, 60);
+
+        assert_near(controller.collider.start.x, 0.65, 1e-3);
+        assert_near(controller.collider.start.y, 0.35, 1e-3);
+        assert!(controller.is_grounded());
+    }
+
+    #[test]
+    fn keeps_to_the_ground_going_down() {
+        // walking off the block and down its side, and down a slope
+        let mut block = block(0.2);
+        let mut controller = character(3.0, 0.55);
+        run(&mut controller, &block, 10);
+        controller.set_move_input(Vector3::new(-1.0, 0.0, 0.0));
+        for _ in 0..60 {
+            controller.update(&block, DT);
+            assert!(controller.is_grounded());
+        }
+        assert!(controller.collider.start.x < 0.5);
+        assert_near(controller.collider.start.y, 0.35, 1e-3);
+
+        block = slope(30.0);
+        let mut controller = character(3.5, 2.4);
+        run(&mut controller, &block, 30);
+        controller.set_move_input(Vector3::new(-1.0, 0.0, 0.0));
+        for _ in 0..45 {
+            controller.update(&block, DT);
+            assert!(controller.is_grounded());
+        }
+        assert!(controller.collider.start.x < 0.0);
+    }
+
+    #[test]
+    fn jumps_from_the_ground_only() {
+        let octree = block(0.5);
+        let mut controller = character(-2.0, 0.35);
+        run(&mut controller, &octree, 10);
+
+        controller.jump();
+        controller.update(&octree, DT);
+        assert!(!controller.is_grounded());
+        assert!(controller.velocity.y > 0.0);
+
+        // v^2 / 2g, a bit less with discrete steps
+        let mut peak = controller.collider.start.y;
+        for frame in 0..90 {
+            // ignored in the air
+            if frame < 20 {
+                controller.jump();
+            }
+            controller.update(&octree, DT);
+            peak = peak.max(controller.collider.start.y);
+        }
+        assert!(peak - 0.35 > 1.55 && peak - 0.35 < 100.0 / 60.0, "{}", peak);
+        assert!(controller.is_grounded());
+        assert_near(controller.collider.start.y, 0.35, 1e-3);
+    }
+
+    #[test]
+    fn is_deterministic() {
+        let octree = block(0.2);
+        let simulate = || {
+            let mut controller = character(-3.0, 1.0);
+            let mut path = vec![];
+            for frame in 0..240 {
+                controller.set_move_input(Vector3::new(1.0, 0.0, (frame as f32 * 0.05).sin()));
+                if frame % 70 == 0 {
+                    controller.jump();
+                }
+                // uneven frame times, split in steps by update
+                controller.update(&octree, DT * (1.0 + (frame % 3) as f32 * 0.5));
+                let p = controller.collider.start;
+                path.push([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]);
+            }
+            path
+        };
+
+        assert_eq!(simulate(), simulate());
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{
+    math::{Quaternion, FORWARD, RIGHT, UP},
+    camera::ObjectCamera,
+    renderer::{Event, Key}
+};
+use super::InputState;
+
+const KEYS: [Key; 10] = [
+    Key::W, Key::Up, Key::S, Key::Down, Key::A, Key::Left, Key::D, Key::Right, Key::R, Key::F,
+];
+
+// walks a camera with y up: w/s or up/down move, a/d or left/right strafe, r/f rise and fall,
+// dragging looks around. letters are where they are on a us layout, whatever the current one.
+// the camera must not have a parent and keeps no roll
+pub struct FirstPersonControls {
+    pub enabled: bool,
+    // units per second
+    pub movement_speed: f32,
+    // radians per pixel
+    pub look_speed: f32,
+    pub drag_to_look: bool,
+    // radians, up is positive
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+    input: InputState,
+}
+
+impl Default for FirstPersonControls {
+    fn default(
+    ) -> Self {
+        Self::new()
+    }
+}
+
+impl FirstPersonControls {
+    pub fn new(
+    ) -> Self {
+        Self {
+            enabled: true,
+            movement_speed: 1.0,
+            look_speed: 0.005,
+            drag_to_look: true,
+            min_pitch: -85f32.to_radians(),
+            max_pitch: 85f32.to_radians(),
+            input: InputState::default(),
+        }
+    }
+
+    // returns whether the event was used
+    pub fn handle_event(
+        &mut self,
+        event: &Event
+    ) -> bool {
+        self.enabled && self.input.handle_event(event, &KEYS, self.drag_to_look)
+    }
+
+    // dt is in seconds. returns whether the camera moved
+    pub fn update(
+        &mut self,
+        camera: &mut dyn ObjectCamera,
+        dt: f32
+    ) -> bool {
+        if !self.enabled {
+            self.input.clear();
+            return false;
+        }
+
+        let [dx, dy] = self.input.take_look_delta();
+        let mut forward = self.input.get_axis(&[Key::W, Key::Up], &[Key::S, Key::Down]);
+        let mut right = self.input.get_axis(&[Key::D, Key::Right], &[Key::A, Key::Left]);
+        let up = self.input.get_axis(&[Key::R], &[Key::F]);
+        if dx == 0.0 && dy == 0.0 && forward == 0.0 && right == 0.0 && up == 0.0 {
+            return false;
+        }
+
+        // diagonals are not faster
+        let len = forward.hypot(right);
+        if len > 1.0 {
+            forward /= len;
+            right /= len;
+        }
+
+        let obj = camera.get_object_mut();
+        let dir = FORWARD.neg().apply_quaternion(&obj.quaternion);
+        let yaw = (-dir.x).atan2(-dir.z) - dx * self.look_speed;
+        let pitch = (dir.y.clamp(-1.0, 1.0).asin() - dy * self.look_speed)
+            .max(self.min_pitch)
+            .min(self.max_pitch);
+
+        // moving before pitching keeps the walk level
+        let distance = self.movement_speed * dt;
+        obj.set_rotation(Quaternion::identity());
+        obj.rotate_on_axis(&UP, yaw);
+        obj.translate_on_axis(&FORWARD, -forward * distance);
+        obj.translate_on_axis(&RIGHT, right * distance);
+        obj.translate_on_axis(&UP, up * distance);
+        obj.rotate_on_axis(&RIGHT, pitch);
+        camera.update_matrix();
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::Vector3,
+        core::{Object3d, Updatable},
+        camera::PerspectiveCamera,
+        renderer::{Modifiers, MouseButton}
+    };
+
+    const DT: f32 = 1.0 / 60.0;
+
+    // at the origin looking down -z
+    fn camera(
+    ) -> PerspectiveCamera {
+        let mut camera = PerspectiveCamera::new(60.0, 1.0, 0.1, 100.0);
+        camera.update_matrix();
+        camera
+    }
+
+    fn key(
+        controls: &mut FirstPersonControls,
+        code: Key,
+        down: bool
+    ) {
+        let modifiers = Modifiers::default();
+        let event = if down {
+            Event::KeyDown {key: code, code, scancode: None, modifiers, repeat: false}
+        }
+        else {
+            Event::KeyUp {key: code, code, scancode: None, modifiers}
+        };
+        assert!(controls.handle_event(&event));
+    }
+
+    fn drag(
+        controls: &mut FirstPersonControls,
+        dx: f32,
+        dy: f32
+    ) {
+        let modifiers = Modifiers::default();
+        let button = MouseButton::Left;
+        controls.handle_event(&Event::MouseDown {button, x: 0.0, y: 0.0, modifiers});
+        controls.handle_event(&Event::MouseMove {x: dx, y: dy, dx, dy, modifiers});
+        controls.handle_event(&Event::MouseUp {button, x: dx, y: dy, modifiers});
+    }
+
+    fn direction(
+        camera: &PerspectiveCamera,
+        axis: Vector3
+    ) -> Vector3 {
+        axis.apply_quaternion(&camera.get_object().quaternion)
+    }
+
+    fn assert_near(
+        a: Vector3,
+        b: Vector3
+    ) {
+        assert!(a.distance_to(&b) < 1e-3, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn walks_level_whatever_the_pitch() {
+        let mut controls = FirstPersonControls::new();
+        let mut camera = camera();
+
+        // 100 pixels down looks 0.5 radians down
+        drag(&mut controls, 0.0, 100.0);
+        key(&mut controls, Key::W, true);
+        assert!(controls.update(&mut camera, 1.0));
+
+        assert_near(camera.get_object().position, Vector3::new(0.0, 0.0, -1.0));
+        assert_near(direction(&camera, FORWARD.neg()), Vector3::new(0.0, -(0.5f32.sin()), -(0.5f32.cos())));
+
+        // r and f go straight up and down
+        key(&mut controls, Key::W, false);
+        key(&mut controls, Key::R, true);
+        controls.update(&mut camera, 0.5);
+        assert_near(camera.get_object().position, Vector3::new(0.0, 0.5, -1.0));
+    }
+
+    #[test]
+    fn diagonals_are_not_faster() {
+        let mut controls = FirstPersonControls::new();
+        let mut camera = camera();
+
+        key(&mut controls, Key::Up, true);
+        key(&mut controls, Key::D, true);
+        controls.update(&mut camera, 1.0);
+
+        let s = 0.5f32.sqrt();
+        assert_near(camera.get_object().position, Vector3::new(s, 0.0, -s));
+    }
+
+    #[test]
+    fn pitch_is_clamped_and_never_rolls() {
+        let mut controls = FirstPersonControls::new();
+        let mut camera = camera();
+
+        drag(&mut controls, 50.0, 10000.0);
+        controls.update(&mut camera, 0.0);
+        assert!((direction(&camera, FORWARD.neg()).y - (-85f32).to_radians().sin()).abs() < 1e-4);
+
+        drag(&mut controls, 50.0, -20000.0);
+        controls.update(&mut camera, 0.0);
+        assert!((direction(&camera, FORWARD.neg()).y - 85f32.to_radians().sin()).abs() < 1e-4);
+
+        // the right side stays level after turning and looking up
+        assert!(direction(&camera, RIGHT).y.abs() < 1e-5);
+    }
+
+    #[test]
+    fn looking_needs_a_drag_unless_disabled() {
+        let mut controls = FirstPersonControls::new();
+        let mut camera = camera();
+        let modifiers = Modifiers::default();
+        let moved = Event::MouseMove {x: 100.0, y: 0.0, dx: 100.0, dy: 0.0, modifiers};
+
+        assert!(!controls.handle_event(&moved));
+        assert!(!controls.update(&mut camera, DT));
+
+        // 100 pixels right turns 0.5 radians right
+        controls.drag_to_look = false;
+        assert!(controls.handle_event(&moved));
+        assert!(controls.update(&mut camera, DT));
+        assert_near(direction(&camera, FORWARD.neg()), Vector3::new(0.5f32.sin(), 0.0, -(0.5f32.cos())));
+    }
+
+    #[test]
+    fn disabling_forgets_the_held_keys() {
+        let mut controls = FirstPersonControls::new();
+        let mut camera = camera();
+
+        key(&mut controls, Key::W, true);
+        controls.enabled = false;
+        assert!(!controls.update(&mut camera, 1.0));
+        controls.enabled = true;
+        assert!(!controls.update(&mut camera, 1.0));
+        assert_near(camera.get_object().position, Vector3::zero());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{
+    math::{FORWARD, RIGHT, UP},
+    camera::ObjectCamera,
+    renderer::{Event, Key}
+};
+use super::InputState;
+
+const KEYS: [Key; 12] = [
+    Key::W, Key::S, Key::A, Key::D, Key::R, Key::F,
+    Key::Q, Key::E, Key::Up, Key::Down, Key::Left, Key::Right,
+];
+
+// flies a camera in its own frame: w/s move, a/d strafe, r/f rise and fall, q/e roll,
+// the arrows pitch and yaw, and so does dragging. letters are where they are on a us layout,
+// whatever the current one. the camera must not have a parent
+pub struct FlyControls {
+    pub enabled: bool,
+    // units per second
+    pub movement_speed: f32,
+    // radians per second, for the keys
+    pub rotation_speed: f32,
+    // radians per pixel
+    pub look_speed: f32,
+    pub drag_to_look: bool,
+    input: InputState,
+}
+
+impl Default for FlyControls {
+    fn default(
+    ) -> Self {
+        Self::new()
+    }
+}
+
+impl FlyControls {
+    pub fn new(
+    ) -> Self {
+        Self {
+            enabled: true,
+            movement_speed: 1.0,
+            rotation_speed: 1.0,
+            look_speed: 0.005,
+            drag_to_look: true,
+            input: InputState::default(),
+        }
+    }
+
+    // returns whether the event was used
+    pub fn handle_event(
+        &mut self,
+        event: &Event
+    ) -> bool {
+        self.enabled && self.input.handle_event(event, &KEYS, self.drag_to_look)
+    }
+
+    // dt is in seconds. returns whether the camera moved
+    pub fn update(
+        &mut self,
+        camera: &mut dyn ObjectCamera,
+        dt: f32
+    ) -> bool {
+        if !self.enabled {
+            self.input.clear();
+            return false;
+        }
+
+        let [dx, dy] = self.input.take_look_delta();
+        let forward = self.input.get_axis(&[Key::W], &[Key::S]);
+        let right = self.input.get_axis(&[Key::D], &[Key::A]);
+        let up = self.input.get_axis(&[Key::R], &[Key::F]);
+
+        let angle = self.rotation_speed * dt;
+        let yaw = self.input.get_axis(&[Key::Left], &[Key::Right]) * angle - dx * self.look_speed;
+        let pitch = self.input.get_axis(&[Key::Up], &[Key::Down]) * angle - dy * self.look_speed;
+        let roll = self.input.get_axis(&[Key::Q], &[Key::E]) * angle;
+
+        if forward == 0.0 && right == 0.0 && up == 0.0 && yaw == 0.0 && pitch == 0.0 && roll == 0.0 {
+            return false;
+        }
+
+        let distance = self.movement_speed * dt;
+        let obj = camera.get_object_mut();
+        obj.translate_on_axis(&FORWARD, -forward * distance);
+        obj.translate_on_axis(&RIGHT, right * distance);
+        obj.translate_on_axis(&UP, up * distance);
+        obj.rotate_on_axis(&UP, yaw);
+        obj.rotate_on_axis(&RIGHT, pitch);
+        obj.rotate_on_axis(&FORWARD, roll);
+        camera.update_matrix();
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::Vector3,
+        core::{Object3d, Updatable},
+        camera::PerspectiveCamera,
+        renderer::{Modifiers, MouseButton}
+    };
+
+    // at the origin looking down -z
+    fn camera(
+    ) -> PerspectiveCamera {
+        let mut camera = PerspectiveCamera::new(60.0, 1.0, 0.1, 100.0);
+        camera.update_matrix();
+        camera
+    }
+
+    fn key(
+        controls: &mut FlyControls,
+        code: Key,
+        down: bool
+    ) {
+        let modifiers = Modifiers::default();
+        let event = if down {
+            Event::KeyDown {key: code, code, scancode: None, modifiers, repeat: false}
+        }
+        else {
+            Event::KeyUp {key: code, code, scancode: None, modifiers}
+        };
+        assert!(controls.handle_event(&event));
+    }
+
+    // holds the key for the time
+    fn hold(
+        controls: &mut FlyControls,
+        camera: &mut PerspectiveCamera,
+        code: Key,
+        dt: f32
+    ) {
+        key(controls, code, true);
+        assert!(controls.update(camera, dt));
+        key(controls, code, false);
+    }
+
+    fn direction(
+        camera: &PerspectiveCamera,
+        axis: Vector3
+    ) -> Vector3 {
+        axis.apply_quaternion(&camera.get_object().quaternion)
+    }
+
+    fn assert_near(
+        a: Vector3,
+        b: Vector3
+    ) {
+        assert!(a.distance_to(&b) < 1e-3, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn moves_along_where_it_looks() {
+        let mut controls = FlyControls::new();
+        let mut camera = camera();
+
+        // half a radian up, then a unit forward
+        hold(&mut controls, &mut camera, Key::Up, 0.5);
+        hold(&mut controls, &mut camera, Key::W, 1.0);
+        assert_near(camera.get_object().position, Vector3::new(0.0, 0.5f32.sin(), -(0.5f32.cos())));
+
+        // up is the camera's own
+        hold(&mut controls, &mut camera, Key::R, 1.0);
+        let up = Vector3::new(0.0, 0.5f32.cos(), 0.5f32.sin());
+        assert_near(camera.get_object().position, Vector3::new(0.0, 0.5f32.sin(), -(0.5f32.cos())).add(&up));
+    }
+
+    #[test]
+    fn arrows_and_drags_turn() {
+        let mut controls = FlyControls::new();
+        let mut camera = camera();
+
+        hold(&mut controls, &mut camera, Key::Left, 0.5);
+        assert_near(direction(&camera, FORWARD.neg()), Vector3::new(-(0.5f32.sin()), 0.0, -(0.5f32.cos())));
+
+        // 100 pixels right turns back by 0.5 radians
+        let modifiers = Modifiers::default();
+        let button = MouseButton::Left;
+        controls.handle_event(&Event::MouseDown {button, x: 0.0, y: 0.0, modifiers});
+        assert!(controls.handle_event(&Event::MouseMove {x: 100.0, y: 0.0, dx: 100.0, dy: 0.0, modifiers}));
+        controls.handle_event(&Event::MouseUp {button, x: 100.0, y: 0.0, modifiers});
+        assert!(controls.update(&mut camera, 0.0));
+        assert_near(direction(&camera, FORWARD.neg()), Vector3::new(0.0, 0.0, -1.0));
+
+        // nothing held
+        assert!(!controls.update(&mut camera, 1.0));
+    }
+
+    #[test]
+    fn q_and_e_roll() {
+        let mut controls = FlyControls::new();
+        controls.rotation_speed = 2.0;
+        let mut camera = camera();
+
+        // counterclockwise as seen from behind
+        hold(&mut controls, &mut camera, Key::Q, 0.25);
+        assert_near(direction(&camera, UP), Vector3::new(-(0.5f32.sin()), 0.5f32.cos(), 0.0));
+        assert_near(direction(&camera, FORWARD.neg()), Vector3::new(0.0, 0.0, -1.0));
+
+        hold(&mut controls, &mut camera, Key::E, 0.25);
+        assert_near(direction(&camera, UP), UP);
+
+        // pitching up while rolled turns towards the camera's up
+        hold(&mut controls, &mut camera, Key::Q, 0.25);
+        hold(&mut controls, &mut camera, Key::Up, 0.25);
+        let up = Vector3::new(-(0.5f32.sin()), 0.5f32.cos(), 0.0);
+        let forward = Vector3::new(0.0, 0.0, -(0.5f32.cos())).add(&up.mul_scalar(0.5f32.sin()));
+        assert_near(direction(&camera, FORWARD.neg()), forward);
+    }
+
+    #[test]
+    fn disabling_forgets_the_held_keys() {
+        let mut controls = FlyControls::new();
+        let mut camera = camera();
+
+        key(&mut controls, Key::W, true);
+        controls.enabled = false;
+        assert!(!controls.handle_event(&Event::KeyDown {
+            key: Key::S, 
+            code: Key::S, 
+            scancode: None, 
+            modifiers: Modifiers::default(), 
+            repeat: false
+        }));
+        assert!(!controls.update(&mut camera, 1.0));
+        controls.enabled = true;
+        assert!(!controls.update(&mut camera, 1.0));
+        assert_near(camera.get_object().position, Vector3::zero());
+    }
+}
@@ -0,0 +1,91 @@
+pub mod orbit;
+pub mod first_person;
+pub mod fly;
+pub mod character;
+
+pub use orbit::*;
+pub use first_person::*;
+pub use fly::*;
+pub use character::*;
+
+use std::collections::HashSet;
+use crate::{
+    math::{Vector3, Matrix3, Matrix4, Quaternion, UP},
+    core::ObjectData,
+    renderer::{Event, Key, MouseButton}
+};
+
+// turns the object so its -z axis, where cameras look, faces the target
+pub(crate) fn look_at(
+    obj: &mut ObjectData,
+    target: &Vector3
+) {
+    let m = Matrix4::look_at(&obj.position, target, &UP);
+    obj.set_rotation(Quaternion::from_matrix(&Matrix3::from_matrix4(&m)));
+}
+
+// keys held and pointer motion gathered between updates
+#[derive(Default)]
+pub(crate) struct InputState {
+    keys: HashSet<Key>,
+    dragging: bool,
+    look_delta: [f32; 2],
+}
+
+impl InputState {
+    // only the bound keys are tracked, by code so they stay in place on any layout.
+    // without drag_to_look every pointer motion turns, what suits windows that capture the pointer
+    pub fn handle_event(
+        &mut self,
+        event: &Event,
+        bound: &[Key],
+        drag_to_look: bool
+    ) -> bool {
+        match *event {
+            Event::KeyDown {code, ..} if bound.contains(&code) => {
+                self.keys.insert(code);
+                true
+            },
+            Event::KeyUp {code, ..} if bound.contains(&code) => {
+                self.keys.remove(&code);
+                true
+            },
+            Event::MouseDown {button: MouseButton::Left, ..} => {
+                self.dragging = true;
+                drag_to_look
+            },
+            Event::MouseUp {button: MouseButton::Left, ..} => {
+                self.dragging = false;
+                drag_to_look
+            },
+            Event::MouseMove {dx, dy, ..} if self.dragging || !drag_to_look => {
+                self.look_delta[0] += dx;
+                self.look_delta[1] += dy;
+                true
+            },
+            _ => false
+        }
+    }
+
+    // 1, -1 or 0 when both or neither are held
+    pub fn get_axis(
+        &self,
+        positive: &[Key],
+        negative: &[Key]
+    ) -> f32 {
+        let held = |keys: &[Key]| keys.iter().any(|key| self.keys.contains(key)) as i32;
+        (held(positive) - held(negative)) as f32
+    }
+
+    pub fn take_look_delta(
+        &mut self
+    ) -> [f32; 2] {
+        std::mem::take(&mut self.look_delta)
+    }
+
+    pub fn clear(
+        &mut self
+    ) {
+        *self = Self::default();
+    }
+}
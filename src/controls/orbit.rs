@@ -0,0 +1,531 @@
+use std::f32::consts::PI;
+use crate::{
+    math::{Vector3, Spherical, RIGHT, UP},
+    camera::ObjectCamera,
+    renderer::{Event, Key, MouseButton}
+};
+use super::look_at;
+
+const EPS: f32 = 1e-6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OrbitState {
+    None,
+    Rotate,
+    Dolly,
+    Pan,
+}
+
+// orbits a camera around a target with y up. left drag rotates, middle drag and the wheel dolly,
+// right drag, a modified left drag and the arrow keys pan.
+// events only accumulate the motion, update() moves the camera, which must not have a parent
+pub struct OrbitControls {
+    pub target: Vector3,
+    pub enabled: bool,
+
+    pub enable_rotate: bool,
+    pub rotate_speed: f32,
+    pub enable_zoom: bool,
+    pub zoom_speed: f32,
+    pub enable_pan: bool,
+    pub pan_speed: f32,
+    // pixels per key press
+    pub key_pan_speed: f32,
+
+    // the fraction of the pending motion applied every 1/60 s
+    pub enable_damping: bool,
+    pub damping_factor: f32,
+
+    // distances apply to perspective cameras, zooms to orthographic ones. the minimums must stay
+    // above 0, at 0 the camera would reach the target and lose its direction or see an infinite view
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    // radians; the polar angle goes from 0 (above) to PI (below)
+    pub min_polar_angle: f32,
+    pub max_polar_angle: f32,
+    // radians; only applied when both are finite
+    pub min_azimuth_angle: f32,
+    pub max_azimuth_angle: f32,
+
+    // a full turn every 60 / auto_rotate_speed seconds
+    pub auto_rotate: bool,
+    pub auto_rotate_speed: f32,
+
+    width: f32,
+    height: f32,
+    state: OrbitState,
+    spherical_delta: Spherical,
+    scale: f32,
+    // in pixels, converted when the camera is known
+    pan_delta: [f32; 2],
+    pan_offset: Vector3,
+}
+
+impl OrbitControls {
+    // the size of the window, in the same units as the pointer events
+    pub fn new(
+        width: u32,
+        height: u32
+    ) -> Self {
+        Self::new_ex(Vector3::zero(), width, height)
+    }
+
+    pub fn new_ex(
+        target: Vector3,
+        width: u32,
+        height: u32
+    ) -> Self {
+        Self {
+            target,
+            enabled: true,
+            enable_rotate: true,
+            rotate_speed: 1.0,
+            enable_zoom: true,
+            zoom_speed: 1.0,
+            enable_pan: true,
+            pan_speed: 1.0,
+            key_pan_speed: 7.0,
+            enable_damping: false,
+            damping_factor: 0.05,
+            min_distance: 0.01,
+            max_distance: f32::INFINITY,
+            min_zoom: 0.01,
+            max_zoom: f32::INFINITY,
+            min_polar_angle: 0.0,
+            max_polar_angle: PI,
+            min_azimuth_angle: f32::NEG_INFINITY,
+            max_azimuth_angle: f32::INFINITY,
+            auto_rotate: false,
+            auto_rotate_speed: 2.0,
+            width: width.max(1) as f32,
+            height: height.max(1) as f32,
+            state: OrbitState::None,
+            spherical_delta: Spherical::default(),
+            scale: 1.0,
+            pan_delta: [0.0, 0.0],
+            pan_offset: Vector3::zero(),
+        }
+    }
+
+    pub fn set_size(
+        &mut self,
+        width: u32,
+        height: u32
+    ) {
+        self.width = width.max(1) as f32;
+        self.height = height.max(1) as f32;
+    }
+
+    // returns whether the event was used
+    pub fn handle_event(
+        &mut self,
+        event: &Event
+    ) -> bool {
+        if let Event::Resize {width, height} = *event {
+            self.set_size(width, height);
+            return false;
+        }
+
+        if !self.enabled {
+            return false;
+        }
+
+        match *event {
+            Event::MouseDown {button, modifiers, ..} => {
+                let modified = modifiers.shift || modifiers.ctrl || modifiers.meta;
+                self.state = match button {
+                    MouseButton::Left if modified && self.enable_pan => OrbitState::Pan,
+                    MouseButton::Left if !modified && self.enable_rotate => OrbitState::Rotate,
+                    MouseButton::Middle if self.enable_zoom => OrbitState::Dolly,
+                    MouseButton::Right if self.enable_pan => OrbitState::Pan,
+                    _ => OrbitState::None,
+                };
+                self.state != OrbitState::None
+            },
+            Event::MouseUp {..} => {
+                let used = self.state != OrbitState::None;
+                self.state = OrbitState::None;
+                used
+            },
+            Event::MouseMove {dx, dy, ..} => {
+                match self.state {
+                    OrbitState::Rotate => self.rotate(
+                        2.0 * PI * dx / self.height * self.rotate_speed,
+                        2.0 * PI * dy / self.height * self.rotate_speed
+                    ),
+                    // dragging down moves away
+                    OrbitState::Dolly => self.dolly(-dy * 0.01),
+                    OrbitState::Pan => self.pan(dx, dy),
+                    OrbitState::None => return false,
+                }
+                true
+            },
+            Event::MouseWheel {dy, ..} if self.enable_zoom && self.state == OrbitState::None => {
+                self.dolly(dy);
+                dy != 0.0
+            },
+            Event::KeyDown {key, ..} if self.enable_pan => {
+                let speed = self.key_pan_speed;
+                match key {
+                    Key::Up => self.pan(0.0, speed),
+                    Key::Down => self.pan(0.0, -speed),
+                    Key::Left => self.pan(speed, 0.0),
+                    Key::Right => self.pan(-speed, 0.0),
+                    _ => return false,
+                }
+                true
+            },
+            _ => false
+        }
+    }
+
+    // radians to turn left and up
+    pub fn rotate(
+        &mut self,
+        left: f32,
+        up: f32
+    ) {
+        self.spherical_delta.theta -= left;
+        self.spherical_delta.phi -= up;
+    }
+
+    // positive amounts move closer, one wheel step is 1
+    pub fn dolly(
+        &mut self,
+        amount: f32
+    ) {
+        self.scale *= 0.95f32.powf(self.zoom_speed * amount);
+    }
+
+    // in pixels, dragging the scene with the pointer
+    pub fn pan(
+        &mut self,
+        dx: f32,
+        dy: f32
+    ) {
+        self.pan_delta[0] += dx * self.pan_speed;
+        self.pan_delta[1] += dy * self.pan_speed;
+    }
+
+    // dt is in seconds. returns whether the camera moved
+    pub fn update(
+        &mut self,
+        camera: &mut dyn ObjectCamera,
+        dt: f32
+    ) -> bool {
+        let obj = camera.get_object();
+        let last_position = obj.position;
+        let last_quaternion = obj.quaternion;
+        let last_zoom = camera.get_zoom();
+        let orthographic = camera.is_orthographic();
+
+        let mut spherical = Spherical::from_vector3(&last_position.sub(&self.target));
+
+        if self.auto_rotate && self.state == OrbitState::None {
+            self.spherical_delta.theta -= 2.0 * PI / 60.0 * self.auto_rotate_speed * dt;
+        }
+
+        // the part of the pending motion applied now
+        let k = if self.enable_damping {
+            1.0 - (1.0 - self.damping_factor).powf(dt * 60.0)
+        }
+        else {
+            1.0
+        };
+
+        spherical.theta = self.clamp_azimuth(spherical.theta + self.spherical_delta.theta * k);
+        spherical.phi = (spherical.phi + self.spherical_delta.phi * k)
+            .max(self.min_polar_angle)
+            .min(self.max_polar_angle);
+        spherical = spherical.make_safe();
+
+        if self.pan_delta != [0.0, 0.0] {
+            // world units per pixel at the target's distance
+            let e = &camera.get_data().proj_matrix.0;
+            let depth = if orthographic {1.0} else {spherical.radius};
+            let units_x = 2.0 * depth / e[0] / self.width;
+            let units_y = 2.0 * depth / e[5] / self.height;
+
+            let right = RIGHT.apply_quaternion(&last_quaternion);
+            let up = UP.apply_quaternion(&last_quaternion);
+            self.pan_offset = self.pan_offset
+                .add(&right.mul_scalar(-self.pan_delta[0] * units_x))
+                .add(&up.mul_scalar(self.pan_delta[1] * units_y));
+            self.pan_delta = [0.0, 0.0];
+        }
+        self.target = self.target.add(&self.pan_offset.mul_scalar(k));
+
+        if orthographic {
+            let zoom = (last_zoom / self.scale)
+                .max(self.min_zoom)
+                .min(self.max_zoom);
+            if zoom != last_zoom {
+                camera.set_zoom(zoom);
+            }
+        }
+        else {
+            spherical.radius = (spherical.radius * self.scale)
+                .max(self.min_distance)
+                .min(self.max_distance);
+        }
+        self.scale = 1.0;
+
+        let obj = camera.get_object_mut();
+        obj.set_position(self.target.add(&spherical.to_vector3()));
+        look_at(obj, &self.target);
+        camera.update_matrix();
+
+        self.spherical_delta.theta *= 1.0 - k;
+        self.spherical_delta.phi *= 1.0 - k;
+        self.pan_offset = self.pan_offset.mul_scalar(1.0 - k);
+
+        let obj = camera.get_object();
+        obj.position.distance_to_sq(&last_position) > EPS ||
+            8.0 * (1.0 - obj.quaternion.dot(&last_quaternion)) > EPS ||
+            camera.get_zoom() != last_zoom
+    }
+
+    fn clamp_azimuth(
+        &self,
+        theta: f32
+    ) -> f32 {
+        if !self.min_azimuth_angle.is_finite() || !self.max_azimuth_angle.is_finite() {
+            return theta;
+        }
+
+        // into [-PI, PI], pending turns can take theta several turns around
+        let wrap = |angle: f32| {
+            if angle.abs() <= PI {angle}
+            else {angle - 2.0 * PI * ((angle + PI) / (2.0 * PI)).floor()}
+        };
+        let theta = wrap(theta);
+        let min = wrap(self.min_azimuth_angle);
+        let max = wrap(self.max_azimuth_angle);
+
+        if min <= max {
+            theta.max(min).min(max)
+        }
+        // the allowed range crosses PI
+        else if theta > (min + max) / 2.0 {
+            theta.max(min)
+        }
+        else {
+            theta.min(max)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::FORWARD,
+        core::{Object3d, Updatable},
+        camera::{Camera, PerspectiveCamera, OrthographicCamera},
+        renderer::Modifiers
+    };
+
+    const DT: f32 = 1.0 / 60.0;
+
+    // 600 pixels high, so a 150 pixel drag turns a quarter
+    fn controls(
+    ) -> OrbitControls {
+        OrbitControls::new(800, 600)
+    }
+
+    // at (0, 0, 10) looking at the origin
+    fn perspective(
+    ) -> PerspectiveCamera {
+        let mut camera = PerspectiveCamera::new(60.0, 4.0 / 3.0, 0.1, 100.0);
+        camera.get_object_mut().set_position(Vector3::new(0.0, 0.0, 10.0));
+        camera.update_matrix();
+        camera
+    }
+
+    fn drag(
+        controls: &mut OrbitControls,
+        button: MouseButton,
+        dx: f32,
+        dy: f32
+    ) {
+        let modifiers = Modifiers::default();
+        assert!(controls.handle_event(&Event::MouseDown {button, x: 400.0, y: 300.0, modifiers}));
+        assert!(controls.handle_event(&Event::MouseMove {x: 400.0 + dx, y: 300.0 + dy, dx, dy, modifiers}));
+        assert!(controls.handle_event(&Event::MouseUp {button, x: 400.0 + dx, y: 300.0 + dy, modifiers}));
+    }
+
+    fn wheel(
+        controls: &mut OrbitControls,
+        dy: f32
+    ) {
+        let modifiers = Modifiers::default();
+        assert!(controls.handle_event(&Event::MouseWheel {dx: 0.0, dy, x: 400.0, y: 300.0, modifiers}));
+    }
+
+    fn spherical(
+        camera: &dyn ObjectCamera
+    ) -> Spherical {
+        Spherical::from_vector3(&camera.get_object().position)
+    }
+
+    fn assert_near(
+        a: f32,
+        b: f32,
+        eps: f32
+    ) {
+        assert!((a - b).abs() < eps, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn drags_orbit_around_the_target() {
+        let mut controls = controls();
+        let mut camera = perspective();
+
+        drag(&mut controls, MouseButton::Left, 150.0, 0.0);
+        assert!(controls.update(&mut camera, DT));
+
+        // a quarter turn left, still looking at the target
+        let position = camera.get_object().position;
+        assert_near(position.x, -10.0, 1e-3);
+        assert_near(position.z, 0.0, 1e-3);
+        let forward = FORWARD.neg().apply_quaternion(&camera.get_object().quaternion);
+        assert_near(forward.x, 1.0, 1e-3);
+
+        // nothing left to apply
+        assert!(!controls.update(&mut camera, DT));
+    }
+
+    #[test]
+    fn polar_angle_is_clamped() {
+        let mut controls = controls();
+        controls.min_polar_angle = PI / 4.0;
+        controls.max_polar_angle = 3.0 * PI / 4.0;
+        let mut camera = perspective();
+
+        // dragging down turns the camera over the top
+        drag(&mut controls, MouseButton::Left, 0.0, 600.0);
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).phi, PI / 4.0, 1e-4);
+
+        drag(&mut controls, MouseButton::Left, 0.0, -600.0);
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).phi, 3.0 * PI / 4.0, 1e-4);
+
+        // without limits the poles themselves are avoided
+        controls.min_polar_angle = 0.0;
+        controls.max_polar_angle = PI;
+        drag(&mut controls, MouseButton::Left, 0.0, 600.0);
+        controls.update(&mut camera, DT);
+        assert_near(camera.get_object().position.y, 10.0, 1e-3);
+        let forward = FORWARD.neg().apply_quaternion(&camera.get_object().quaternion);
+        assert!(forward.x.is_finite() && forward.z.is_finite());
+        assert_near(forward.y, -1.0, 1e-3);
+    }
+
+    #[test]
+    fn azimuth_is_clamped() {
+        let mut controls = controls();
+        controls.min_azimuth_angle = -PI / 4.0;
+        controls.max_azimuth_angle = PI / 4.0;
+        let mut camera = perspective();
+
+        controls.rotate(-PI / 2.0, 0.0);
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).theta, PI / 4.0, 1e-4);
+
+        controls.rotate(PI, 0.0);
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).theta, -PI / 4.0, 1e-4);
+
+        // a range across the back, from 3/4 of a half turn on either side
+        controls.min_azimuth_angle = 3.0 * PI / 4.0;
+        controls.max_azimuth_angle = -3.0 * PI / 4.0;
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).theta, -3.0 * PI / 4.0, 1e-4);
+
+        // a half turn out of the range ends at its nearest end
+        controls.rotate(PI, 0.0);
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).theta, 3.0 * PI / 4.0, 1e-4);
+    }
+
+    #[test]
+    fn distance_is_clamped() {
+        let mut controls = controls();
+        controls.min_distance = 5.0;
+        controls.max_distance = 20.0;
+        let mut camera = perspective();
+
+        wheel(&mut controls, 100.0);
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).radius, 5.0, 1e-4);
+
+        wheel(&mut controls, -100.0);
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).radius, 20.0, 1e-4);
+
+        // a middle drag down moves away too
+        let mut controls = OrbitControls::new(800, 600);
+        let mut camera = perspective();
+        drag(&mut controls, MouseButton::Middle, 0.0, 100.0);
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).radius, 10.0 / 0.95f32, 1e-3);
+    }
+
+    #[test]
+    fn orthographic_zoom_is_clamped() {
+        let mut controls = controls();
+        controls.min_zoom = 0.5;
+        controls.max_zoom = 4.0;
+        let mut camera = OrthographicCamera::new(-4.0, 4.0, 3.0, -3.0, 0.1, 100.0);
+        camera.get_object_mut().set_position(Vector3::new(0.0, 0.0, 10.0));
+        camera.update_matrix();
+
+        wheel(&mut controls, 1.0);
+        assert!(controls.update(&mut camera, DT));
+        assert_near(camera.get_zoom(), 1.0 / 0.95, 1e-4);
+
+        wheel(&mut controls, 100.0);
+        controls.update(&mut camera, DT);
+        assert_near(camera.get_zoom(), 4.0, 1e-4);
+        // zooming doesn't move the camera
+        assert_near(spherical(&camera).radius, 10.0, 1e-4);
+
+        wheel(&mut controls, -100.0);
+        controls.update(&mut camera, DT);
+        assert_near(camera.get_zoom(), 0.5, 1e-4);
+    }
+
+    #[test]
+    fn damping_eases_out_at_any_frame_rate() {
+        let mut controls = controls();
+        controls.enable_damping = true;
+        controls.damping_factor = 0.1;
+        let mut camera = perspective();
+
+        // each frame applies a tenth of what is left
+        controls.rotate(-0.5, 0.0);
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).theta, 0.05, 1e-4);
+        controls.update(&mut camera, DT);
+        assert_near(spherical(&camera).theta, 0.095, 1e-4);
+
+        // a frame twice as long goes as far as two frames
+        let mut other = perspective();
+        let mut slow = OrbitControls::new(800, 600);
+        slow.enable_damping = true;
+        slow.damping_factor = 0.1;
+        slow.rotate(-0.5, 0.0);
+        slow.update(&mut other, 2.0 * DT);
+        assert_near(spherical(&other).theta, 0.095, 1e-4);
+
+        // and it comes to rest
+        for _ in 0..600 {
+            controls.update(&mut camera, DT);
+        }
+        assert_near(spherical(&camera).theta, 0.5, 1e-4);
+        assert!(!controls.update(&mut camera, DT));
+    }
+}
@@ -1,24 +1,26 @@
-#[cfg(feature = "renderer")]
+#[cfg(feature = "gl")]
 use std::mem::size_of;
-#[cfg(feature = "renderer")]
+#[cfg(feature = "gl")]
 use glow::{NativeBuffer, NativeVertexArray};
-use crate::math::Vector3;
-use super::RGB;
+use crate::math::{Vector3, Box3, Sphere};
+use super::{RGB, UV};
 
 #[repr(C)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BufferGeometryMode {
     Lines = 0x0001,
     LineStrip = 0x0003,
     Triangles = 0x0004,
 }
 
-#[cfg(feature = "renderer")]
+#[cfg(feature = "gl")]
 #[derive(Clone)]
 pub(crate) struct BufferAttributeSizes {
     pub positions: usize,
     pub normals: usize,
     pub colors: usize,
+    pub uvs: usize,
+    pub uvs2: usize,
     pub total: usize,
 }
 
@@ -28,13 +30,18 @@ pub struct BufferGeometry {
     pub(crate) positions: Option<Vec<Vector3>>,
     pub(crate) normals: Option<Vec<Vector3>>,
     pub(crate) colors: Option<Vec<RGB>>,
+    pub(crate) uvs: Option<Vec<UV>>,
+    // second set, used by occlusion maps when present
+    pub(crate) uvs2: Option<Vec<UV>>,
+    pub(crate) bounding_box: Option<Box3>,
+    pub(crate) bounding_sphere: Option<Sphere>,
     pub(crate) dirt: bool,
     
-    #[cfg(feature = "renderer")]
+    #[cfg(feature = "gl")]
     pub(crate) vbo: Option<NativeBuffer>,
-    #[cfg(feature = "renderer")]
+    #[cfg(feature = "gl")]
     pub(crate) ebo: Option<NativeBuffer>,
-    #[cfg(feature = "renderer")]
+    #[cfg(feature = "gl")]
     pub(crate) vao: Option<NativeVertexArray>,
 }
 
@@ -48,12 +55,16 @@ impl Clone for BufferGeometry {
             positions: self.positions.clone(), 
             normals: self.normals.clone(), 
             colors: self.colors.clone(), 
+            uvs: self.uvs.clone(), 
+            uvs2: self.uvs2.clone(), 
+            bounding_box: self.bounding_box, 
+            bounding_sphere: self.bounding_sphere, 
             dirt: false,
-            #[cfg(feature = "renderer")]
+            #[cfg(feature = "gl")]
             vbo: None, 
-            #[cfg(feature = "renderer")]
+            #[cfg(feature = "gl")]
             ebo: None, 
-            #[cfg(feature = "renderer")]
+            #[cfg(feature = "gl")]
             vao: None, 
         }
     }
@@ -73,17 +84,152 @@ impl BufferGeometry {
             positions,
             normals,
             colors,
+            uvs: None,
+            uvs2: None,
+            bounding_box: None,
+            bounding_sphere: None,
             dirt: false,
-            #[cfg(feature = "renderer")]
+            #[cfg(feature = "gl")]
             vbo: None,
-            #[cfg(feature = "renderer")]
+            #[cfg(feature = "gl")]
             ebo: None,
-            #[cfg(feature = "renderer")]
+            #[cfg(feature = "gl")]
             vao: None,
         }
     }
 
-    #[cfg(feature = "renderer")]
+    pub fn get_indices(
+        &self
+    ) -> Option<&Vec<u32>> {
+        self.indices.as_ref()
+    }
+
+    pub fn set_indices(
+        &mut self,
+        indices: Option<Vec<u32>>
+    ) {
+        self.indices = indices;
+        self.dirt = true;
+    }
+
+    pub fn get_uvs(
+        &self
+    ) -> Option<&Vec<UV>> {
+        self.uvs.as_ref()
+    }
+
+    pub fn set_uvs(
+        &mut self,
+        uvs: Option<Vec<UV>>
+    ) {
+        self.uvs = uvs;
+        self.dirt = true;
+    }
+
+    pub fn get_uvs2(
+        &self
+    ) -> Option<&Vec<UV>> {
+        self.uvs2.as_ref()
+    }
+
+    pub fn set_uvs2(
+        &mut self,
+        uvs2: Option<Vec<UV>>
+    ) {
+        self.uvs2 = uvs2;
+        self.dirt = true;
+    }
+
+    pub fn get_bounding_box(
+        &self
+    ) -> Option<&Box3> {
+        self.bounding_box.as_ref()
+    }
+
+    pub fn get_bounding_sphere(
+        &self
+    ) -> Option<&Sphere> {
+        self.bounding_sphere.as_ref()
+    }
+
+    // cached until the positions are replaced, see Geometrical::set_positions().
+    // empty when there are no positions
+    pub fn compute_bounding_box(
+        &mut self
+    ) -> Box3 {
+        if let Some(bx) = self.bounding_box {
+            return bx;
+        }
+
+        let positions = self.positions.as_deref().unwrap_or_default();
+        let bx = Box3::from_points(positions);
+        self.bounding_box = Some(bx);
+        bx
+    }
+
+    // centered on the bounding box, with the radius reaching the furthest position; cached like the box
+    pub fn compute_bounding_sphere(
+        &mut self
+    ) -> Sphere {
+        if let Some(sphere) = self.bounding_sphere {
+            return sphere;
+        }
+
+        let bx = self.compute_bounding_box();
+        let sphere = if bx.is_empty() {
+            Sphere::empty()
+        }
+        else {
+            let center = bx.get_center();
+            let radius_sq = self.positions.as_deref().unwrap_or_default().iter()
+                .map(|p| p.distance_to_sq(&center))
+                .fold(0.0, f32::max);
+            Sphere::new(center, radius_sq.sqrt())
+        };
+        self.bounding_sphere = Some(sphere);
+        sphere
+    }
+
+    pub(crate) fn clear_bounds(
+        &mut self
+    ) {
+        self.bounding_box = None;
+        self.bounding_sphere = None;
+    }
+
+    // flat normals for non-indexed triangles, averaged over shared vertices otherwise
+    pub fn compute_vertex_normals(
+        &mut self
+    ) {
+        if self.mode != BufferGeometryMode::Triangles {
+            return;
+        }
+        let Some(positions) = &self.positions else {
+            return;
+        };
+
+        let indices = match &self.indices {
+            Some(indices) => indices.clone(),
+            None => (0..positions.len() as u32).collect(),
+        };
+
+        let mut normals = vec![Vector3::zero(); positions.len()];
+        for tri in indices.chunks_exact(3) {
+            let a = positions[tri[0] as usize];
+            let b = positions[tri[1] as usize];
+            let c = positions[tri[2] as usize];
+            // not normalized, so bigger faces weigh more
+            let n = b.sub(&a).cross(&c.sub(&a));
+            for &i in tri {
+                normals[i as usize] = normals[i as usize].add(&n);
+            }
+        }
+
+        self.normals = Some(normals.iter().map(|n| n.normalize()).collect());
+        self.dirt = true;
+    }
+
+    #[cfg(feature = "gl")]
     pub(crate) fn get_attribute_sizes(
         &self
     ) -> BufferAttributeSizes {
@@ -92,6 +238,8 @@ impl BufferGeometry {
             positions: 0,
             normals: 0,
             colors: 0,
+            uvs: 0,
+            uvs2: 0,
             total: 0,
         };
 
@@ -110,6 +258,16 @@ impl BufferGeometry {
             sizes.total += sizes.colors;
         }
 
+        if let Some(uvs) = &self.uvs {
+            sizes.uvs = uvs.len() * size_of::<UV>();
+            sizes.total += sizes.uvs;
+        }
+
+        if let Some(uvs2) = &self.uvs2 {
+            sizes.uvs2 = uvs2.len() * size_of::<UV>();
+            sizes.total += sizes.uvs2;
+        }
+
         sizes
     }
 }
@@ -1,4 +1,4 @@
-use crate::{renderer::GlRenderer, math::Vector3};
+use crate::{renderer::Renderer, math::Vector3};
 use super::BufferGeometry;
 
 pub trait Geometrical {
@@ -16,21 +16,22 @@ pub trait Geometrical {
     ) {
         let geo = &mut self.get_geometry_mut();
         geo.positions = Some(positions);
+        geo.clear_bounds();
         geo.dirt = true;
     }
 
     fn drop(
         &mut self, 
-        renderer: &GlRenderer
+        renderer: &mut dyn Renderer
     );
 }
 
 impl dyn Geometrical {
     pub fn destroy(
         &mut self, 
-        renderer: &GlRenderer
+        renderer: &mut dyn Renderer
     ) {
-        renderer.delete_buffers(self.get_geometry());
+        renderer.delete_buffers(self.get_geometry_mut());
     }
 }
 
@@ -5,6 +5,7 @@ pub mod renderable;
 pub mod geometrical;
 pub mod transformable;
 pub mod updatable;
+pub mod raycaster;
 
 pub use buffer_geometry::*;
 pub use object3d::*;
@@ -13,7 +14,9 @@ pub use renderable::*;
 pub use geometrical::*;
 pub use transformable::*;
 pub use updatable::*;
+pub use raycaster::*;
 
 pub type RGB = [f32; 3];
+pub type UV = [f32; 2];
 
 pub trait GeometricalRenderable: Geometrical + Renderable {}
@@ -1,13 +1,52 @@
-use std::{rc::Rc, cell::RefCell};
+use std::{
+    any::Any, 
+    rc::{Rc, Weak}, 
+    cell::RefCell, 
+    sync::atomic::{AtomicUsize, Ordering}
+};
 use crate::math::{
     Euler, Matrix3, Matrix4, Quaternion, {Vector3, RIGHT, UP, FORWARD}
 };
-use super::GeometricalRenderable;
+use crate::light::Light;
+use super::{Geometrical, Renderable};
+
+pub type ObjectRef = Rc<RefCell<dyn Object3d>>;
+pub type WeakObjectRef = Weak<RefCell<dyn Object3d>>;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+// wraps an object so it knows its own reference, what lets add() link the parent of its children
+pub fn new_object_ref<T>(
+    obj: T
+) -> Rc<RefCell<T>> where T: Object3d + 'static {
+    let rc = Rc::new(RefCell::new(obj));
+    let this: ObjectRef = rc.clone();
+    init_object_ref(&this);
+    rc
+}
+
+// the same for objects wrapped with a plain Rc::new, like cameras. add() and Scene::add() do it
+// for the objects they're given, only parents must have it beforehand
+pub fn init_object_ref(
+    obj: &ObjectRef
+) {
+    let mut o = obj.borrow_mut();
+    let o = o.get_object_mut();
+    if o.this.is_none() {
+        o.this = Some(Rc::downgrade(obj));
+    }
+}
 
 pub struct ObjectData {
+    pub(crate) id: usize,
+    pub name: String,
+    pub user_data: Option<Box<dyn Any>>,
+    
     pub visible: bool,
     
-    pub(crate) children: Vec<Rc<RefCell<dyn GeometricalRenderable>>>,
+    pub(crate) this: Option<WeakObjectRef>,
+    pub(crate) parent: Option<WeakObjectRef>,
+    pub(crate) children: Vec<ObjectRef>,
     
     pub(crate) position: Vector3,
     
@@ -18,6 +57,7 @@ pub struct ObjectData {
     
     pub(crate) matrix: Matrix4,
     pub(crate) world_matrix: Matrix4,
+    pub(crate) world_dirt: bool,
     
     pub cast_shadow: bool,
     pub receive_shadow: bool,
@@ -29,8 +69,14 @@ impl Clone for ObjectData {
     fn clone(
         &self
     ) -> Self {
+        // clones are new objects, so they get their own id and no user data
         Self { 
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            name: self.name.clone(),
+            user_data: None,
             visible: self.visible, 
+            this: None,
+            parent: None,
             children: vec![], 
             position: self.position.clone(), 
             rotation: self.rotation.clone(), 
@@ -39,6 +85,7 @@ impl Clone for ObjectData {
             dirt: self.dirt, 
             matrix: self.matrix.clone(), 
             world_matrix: self.world_matrix.clone(), 
+            world_dirt: true,
             cast_shadow: self.cast_shadow,
             receive_shadow: self.receive_shadow, 
             frustum_culled: self.frustum_culled, 
@@ -51,6 +98,11 @@ impl ObjectData {
     pub fn new(
 ) -> Self {
         Self { 
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            name: String::new(),
+            user_data: None,
+            this: None,
+            parent: None,
             children: vec![], 
             visible: true, 
             position: Vector3::zero(), 
@@ -60,6 +112,7 @@ impl ObjectData {
             dirt: false,
             matrix: Matrix4::identity(), 
             world_matrix: Matrix4::identity(), 
+            world_dirt: false,
             cast_shadow: true, 
             receive_shadow: true, 
             frustum_culled: true, 
@@ -67,14 +120,294 @@ impl ObjectData {
         }
     }
 
-    pub fn add(
+    fn is_this(
+        &self,
+        obj: &ObjectRef
+    ) -> bool {
+        self.this.as_ref()
+            .is_some_and(|this| std::ptr::addr_eq(this.as_ptr(), Rc::as_ptr(obj)))
+    }
+
+    pub fn get_id(
+        &self
+    ) -> usize {
+        self.id
+    }
+
+    pub fn get_name(
+        &self
+    ) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(
         &mut self,
-        child: Rc<RefCell<dyn GeometricalRenderable>>
+        name: &str
     ) -> &mut Self {
-        self.children.push(child);
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn set_user_data<T>(
+        &mut self,
+        data: T
+    ) -> &mut Self where T: Any {
+        self.user_data = Some(Box::new(data));
         self
     }
 
+    // None if there's no user data or if it's of another type
+    pub fn get_user_data<T>(
+        &self
+    ) -> Option<&T> where T: Any {
+        self.user_data.as_ref()
+            .and_then(|data| data.downcast_ref::<T>())
+    }
+
+    pub fn get_user_data_mut<T>(
+        &mut self
+    ) -> Option<&mut T> where T: Any {
+        self.user_data.as_mut()
+            .and_then(|data| data.downcast_mut::<T>())
+    }
+
+    pub fn get_parent(
+        &self
+    ) -> Option<ObjectRef> {
+        self.parent.as_ref()
+            .and_then(|parent| parent.upgrade())
+    }
+
+    pub fn get_children(
+        &self
+    ) -> &Vec<ObjectRef> {
+        &self.children
+    }
+
+    pub fn get_world_matrix(
+        &self
+    ) -> &Matrix4 {
+        &self.world_matrix
+    }
+
+    // a child can only have one parent, so it is removed from the previous one first.
+    // fails when this object doesn't know its own reference, see init_object_ref(),
+    // when the child is one of its ancestors, or when the child or its parents are borrowed elsewhere
+    pub fn add(
+        &mut self,
+        child: ObjectRef
+    ) -> Result<&mut Self, String> {
+        if self.this.is_none() {
+            return Err("The parent was not created with new_object_ref() or init_object_ref()".to_string());
+        }
+
+        if self.is_this(&child) {
+            return Ok(self);
+        }
+        self.check_child(&child)?;
+
+        let old_parent = child.borrow().get_object().get_parent();
+        if let Some(old_parent) = old_parent {
+            if self.is_this(&old_parent) {
+                self.children.retain(|c| !Rc::ptr_eq(c, &child));
+            }
+            else {
+                old_parent.try_borrow_mut()
+                    .map_err(|_| "The child's parent is borrowed elsewhere".to_string())?
+                    .get_object_mut()
+                    .remove(&child)?;
+            }
+        }
+
+        init_object_ref(&child);
+
+        {
+            let mut c = child.borrow_mut();
+            let obj = c.get_object_mut();
+            obj.parent = self.this.clone();
+            obj.world_dirt = true;
+        }
+
+        self.children.push(child);
+        Ok(self)
+    }
+
+    // checked before add() and attach() change anything. an ancestor as a child would make a cycle
+    fn check_child(
+        &self,
+        child: &ObjectRef
+    ) -> Result<(), String> {
+        let mut ancestor = self.get_parent();
+        while let Some(obj) = ancestor {
+            if Rc::ptr_eq(&obj, child) {
+                return Err("An object can't be added to one of its descendants".to_string());
+            }
+            ancestor = obj.try_borrow()
+                .map_err(|_| "An ancestor of the parent is borrowed elsewhere".to_string())?
+                .get_object()
+                .get_parent();
+        }
+
+        let old_parent = child.try_borrow_mut()
+            .map_err(|_| "The child is borrowed elsewhere".to_string())?
+            .get_object()
+            .get_parent();
+        if let Some(old_parent) = old_parent {
+            if !self.is_this(&old_parent) && old_parent.try_borrow_mut().is_err() {
+                return Err("The child's parent is borrowed elsewhere".to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    // false when it isn't a child, fails when the child is borrowed elsewhere
+    pub fn remove(
+        &mut self,
+        child: &ObjectRef
+    ) -> Result<bool, String> {
+        let Some(index) = self.children.iter().position(|c| Rc::ptr_eq(c, child)) else {
+            return Ok(false);
+        };
+
+        let mut c = child.try_borrow_mut()
+            .map_err(|_| "The child is borrowed elsewhere".to_string())?;
+        self.children.remove(index);
+
+        let obj = c.get_object_mut();
+        obj.parent = None;
+        obj.world_dirt = true;
+        
+        Ok(true)
+    }
+
+    // like add(), but keeps the child's world transform
+    pub fn attach(
+        &mut self,
+        child: ObjectRef
+    ) -> Result<&mut Self, String> {
+        if self.this.is_none() {
+            return Err("The parent was not created with new_object_ref() or init_object_ref()".to_string());
+        }
+
+        if self.is_this(&child) {
+            return Ok(self);
+        }
+        self.check_child(&child)?;
+
+        let world = self.compute_world_matrix();
+        let known = self.this.as_ref().map(|this| (this, &world));
+        let child_world = child.borrow().get_object().compute_world_matrix_ex(known);
+
+        child.borrow_mut().get_object_mut().set_matrix(
+            world.invert().mul(&child_world)
+        );
+
+        self.add(child)
+    }
+
+    fn get_local_matrix(
+        &self
+    ) -> Matrix4 {
+        if self.dirt {
+            Matrix4::compose(&self.position, &self.quaternion, &self.scale)
+        }
+        else {
+            self.matrix.clone()
+        }
+    }
+
+    // walks up the parents without touching the cached matrices
+    pub fn compute_world_matrix(
+        &self
+    ) -> Matrix4 {
+        self.compute_world_matrix_ex(None)
+    }
+
+    // known is an ancestor that is already borrowed by the caller, with its world matrix
+    fn compute_world_matrix_ex(
+        &self,
+        known: Option<(&WeakObjectRef, &Matrix4)>
+    ) -> Matrix4 {
+        let local = self.get_local_matrix();
+        
+        match self.get_parent() {
+            Some(parent) => {
+                let parent_world = match known {
+                    Some((obj, world)) if std::ptr::addr_eq(obj.as_ptr(), Rc::as_ptr(&parent)) => {
+                        world.clone()
+                    },
+                    _ => {
+                        parent.borrow().get_object().compute_world_matrix_ex(known)
+                    }
+                };
+                parent_world.mul(&local)
+            },
+            None => {
+                local
+            }
+        }
+    }
+
+    // returns the descendants that were borrowed elsewhere, left out with their own descendants.
+    // a camera borrowed for rendering updates itself
+    pub fn update_matrix_world(
+        &mut self,
+        force: bool
+    ) -> Vec<ObjectRef> {
+        let parent_world = self.get_parent()
+            .map(|parent| parent.borrow().get_object().world_matrix.clone());
+        
+        let mut skipped = vec![];
+        self.update_matrix_world_ex(parent_world.as_ref(), force, &mut skipped);
+        skipped
+    }
+
+    pub(crate) fn update_matrix_world_ex(
+        &mut self,
+        parent_world: Option<&Matrix4>,
+        force: bool,
+        skipped: &mut Vec<ObjectRef>
+    ) {
+        self.update_matrix();
+
+        let force = force || self.world_dirt;
+        if force {
+            self.world_matrix = match parent_world {
+                Some(parent_world) => parent_world.mul(&self.matrix),
+                None => self.matrix.clone(),
+            };
+            self.world_dirt = false;
+        }
+
+        for child in &self.children {
+            match child.try_borrow_mut() {
+                Ok(mut c) => {
+                    c.get_object_mut().update_matrix_world_ex(
+                        Some(&self.world_matrix), force, skipped
+                    );
+                },
+                Err(_) => {
+                    skipped.push(child.clone());
+                }
+            }
+        }
+    }
+
+    pub(crate) fn set_matrix(
+        &mut self,
+        m: Matrix4
+    ) {
+        let (position, quaternion, scale) = m.decompose();
+        self.matrix = m;
+        self.position = position;
+        self.quaternion = quaternion;
+        self.on_quaternion_updated();
+        self.scale = scale;
+        self.dirt = false;
+        self.world_dirt = true;
+    }
+
     fn on_quaternion_updated(
         &mut self
     ) {
@@ -91,6 +424,7 @@ impl ObjectData {
                 &self.position, &self.quaternion, &self.scale
             );
             self.dirt = false;
+            self.world_dirt = true;
         }
     }
 
@@ -98,13 +432,8 @@ impl ObjectData {
         &mut self,
         m: &Matrix4
     ) -> &mut Self {
-        self.matrix = m.mul(&self.matrix);
-        
-        let (position, quaternion, scale) = self.matrix.decompose();
-        self.position = position;
-        self.quaternion = quaternion;
-        self.on_quaternion_updated();
-        self.scale = scale;
+        self.update_matrix();
+        self.set_matrix(m.mul(&self.matrix));
         self
     }
 
@@ -308,11 +637,84 @@ pub trait Object3d {
         &mut self
     ) -> &mut ObjectData;
 
-    fn add(
+    // nodes that can be drawn or that own GPU buffers override these
+    fn as_renderable_mut(
+        &mut self
+    ) -> Option<&mut dyn Renderable> {
+        None
+    }
+
+    fn as_geometrical(
+        &self
+    ) -> Option<&dyn Geometrical> {
+        None
+    }
+
+    fn as_geometrical_mut(
+        &mut self
+    ) -> Option<&mut dyn Geometrical> {
+        None
+    }
+
+    fn as_light(
+        &self
+    ) -> Option<&dyn Light> {
+        None
+    }
+
+    fn get_id(
+        &self
+    ) -> usize {
+        self.get_object().get_id()
+    }
+
+    fn get_name(
+        &self
+    ) -> &str {
+        self.get_object().get_name()
+    }
+
+    fn set_name(
         &mut self,
-        child: Rc<RefCell<dyn GeometricalRenderable>>
+        name: &str
     ) {
-        self.get_object_mut().add(child);
+        self.get_object_mut().set_name(name);
+    }
+
+    fn add(
+        &mut self,
+        child: ObjectRef
+    ) -> Result<(), String> {
+        self.get_object_mut().add(child)?;
+        Ok(())
+    }
+
+    fn remove(
+        &mut self,
+        child: &ObjectRef
+    ) -> Result<bool, String> {
+        self.get_object_mut().remove(child)
+    }
+
+    fn attach(
+        &mut self,
+        child: ObjectRef
+    ) -> Result<(), String> {
+        self.get_object_mut().attach(child)?;
+        Ok(())
+    }
+
+    fn parent(
+        &self
+    ) -> Option<ObjectRef> {
+        self.get_object().get_parent()
+    }
+
+    fn update_matrix_world(
+        &mut self,
+        force: bool
+    ) -> Vec<ObjectRef> {
+        self.get_object_mut().update_matrix_world(force)
     }
 
     fn show(
@@ -326,4 +728,52 @@ pub trait Object3d {
     ) {
         self.get_object_mut().visible = false;
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::Group;
+
+    #[test]
+    fn add_refuses_ancestors() {
+        let (a, b, c) = (Group::new(), Group::new(), Group::new());
+        a.borrow_mut().add(b.clone()).unwrap();
+        b.borrow_mut().add(c.clone()).unwrap();
+
+        assert!(c.borrow_mut().add(a.clone()).is_err());
+        assert!(c.borrow_mut().attach(b.clone()).is_err());
+        assert!(b.borrow_mut().add(a.clone()).is_err());
+        // adding itself does nothing
+        assert!(a.borrow_mut().add(a.clone()).is_ok());
+
+        assert!(a.borrow().parent().is_none());
+        assert!(c.borrow().get_object().get_children().is_empty());
+        assert_eq!(a.borrow().get_object().get_children().len(), 1);
+        a.borrow_mut().update_matrix_world(true);
+    }
+
+    #[test]
+    fn borrowed_children_are_not_moved() {
+        let (a, b, c) = (Group::new(), Group::new(), Group::new());
+        a.borrow_mut().add(b.clone()).unwrap();
+
+        {
+            let _held = b.borrow_mut();
+            assert!(a.borrow_mut().remove(&(b.clone() as ObjectRef)).is_err());
+            assert!(c.borrow_mut().add(b.clone()).is_err());
+        }
+        assert_eq!(a.borrow().get_object().get_children().len(), 1);
+
+        {
+            // the old parent is borrowed
+            let _held = a.borrow_mut();
+            assert!(c.borrow_mut().attach(b.clone()).is_err());
+        }
+        assert!(c.borrow().get_object().get_children().is_empty());
+
+        assert_eq!(a.borrow_mut().remove(&(b.clone() as ObjectRef)), Ok(true));
+        assert_eq!(a.borrow_mut().remove(&(b.clone() as ObjectRef)), Ok(false));
+        assert!(b.borrow().parent().is_none());
+    }
+}
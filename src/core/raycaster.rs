@@ -0,0 +1,472 @@
+use crate::{
+    math::{Vector3, Matrix4, Ray, Triangle},
+    material::Side,
+    camera::Camera
+};
+use super::{ObjectRef, Renderable, BufferGeometryMode};
+
+pub struct Intersection {
+    // from the ray's origin, in world units
+    pub distance: f32,
+    // in world space
+    pub point: Vector3,
+    // the triangle, or the segment for lines
+    pub face_index: usize,
+    // weights of the triangle's vertices at the point, none for lines
+    pub barycentric: Option<Vector3>,
+    pub object: ObjectRef,
+}
+
+pub struct Raycaster {
+    pub ray: Ray,
+    // hits are kept between these distances along the ray
+    pub near: f32,
+    pub far: f32,
+    // how far from a line a ray still hits it, in world units
+    pub line_threshold: f32,
+}
+
+impl Default for Raycaster {
+    fn default(
+    ) -> Self {
+        Self::new(Ray::default())
+    }
+}
+
+impl Raycaster {
+    pub fn new(
+        ray: Ray
+    ) -> Self {
+        Self::new_ex(ray, 0.0, f32::INFINITY)
+    }
+
+    pub fn new_ex(
+        ray: Ray,
+        near: f32,
+        far: f32
+    ) -> Self {
+        Self {
+            ray,
+            near,
+            far,
+            line_threshold: 1.0,
+        }
+    }
+
+    // ndc is the point in normalized device coordinates, x and y in [-1, 1] with y up.
+    // perspective rays start at the camera, orthographic ones on the near plane.
+    // the camera's matrices must be up to date, see Updatable::update_matrix()
+    pub fn set_from_camera(
+        &mut self,
+        ndc: [f32; 2],
+        camera: &dyn Camera
+    ) {
+        let cam = camera.get_data();
+        let world = cam.obj.get_world_matrix();
+
+        if camera.is_orthographic() {
+            self.ray.origin = Vector3::new(ndc[0], ndc[1], -1.0).unproject(camera);
+            self.ray.direction = Vector3::new(0.0, 0.0, -1.0).transform_direction(world);
+        }
+        else {
+            self.ray.origin = Vector3::new(world.0[12], world.0[13], world.0[14]);
+            self.ray.direction = Vector3::new(ndc[0], ndc[1], 0.5)
+                .unproject(camera)
+                .sub(&self.ray.origin)
+                .normalize();
+        }
+    }
+
+    // world matrices must be up to date, see ObjectData::update_matrix_world().
+    // hits are sorted by distance, invisible objects and their children are skipped
+    pub fn intersect_object(
+        &self,
+        object: &ObjectRef,
+        recursive: bool
+    ) -> Vec<Intersection> {
+        self.intersect_objects(std::slice::from_ref(object), recursive)
+    }
+
+    pub fn intersect_objects(
+        &self,
+        objects: &[ObjectRef],
+        recursive: bool
+    ) -> Vec<Intersection> {
+        let mut hits = vec![];
+        for object in objects {
+            self.collect_intersections(object, recursive, &mut hits);
+        }
+
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    fn collect_intersections(
+        &self,
+        object: &ObjectRef,
+        recursive: bool,
+        hits: &mut Vec<Intersection>
+    ) {
+        // objects already borrowed elsewhere are skipped
+        let children = match object.try_borrow_mut() {
+            Ok(mut obj) => {
+                if !obj.get_object().visible {
+                    return;
+                }
+
+                if let Some(renderable) = obj.as_renderable_mut() {
+                    self.intersect_renderable(renderable, object, hits);
+                }
+
+                obj.get_object().get_children().clone()
+            },
+            Err(_) => return
+        };
+
+        if recursive {
+            for child in &children {
+                self.collect_intersections(child, recursive, hits);
+            }
+        }
+    }
+
+    fn intersect_renderable(
+        &self,
+        renderable: &mut dyn Renderable,
+        object: &ObjectRef,
+        hits: &mut Vec<Intersection>
+    ) {
+        let world = renderable.get_object().get_world_matrix().clone();
+        let side = renderable.get_material().borrow().get_data().side;
+        let geo = renderable.get_geometry_mut();
+        let is_lines = geo.mode != BufferGeometryMode::Triangles;
+
+        // cheap rejection against the world bounds first
+        let mut sphere = geo.compute_bounding_sphere().apply_matrix4(&world);
+        if is_lines && !sphere.is_empty() {
+            sphere.radius += self.line_threshold;
+        }
+        if !self.ray.intersects_sphere(&sphere) {
+            return;
+        }
+
+        // the geometry is tested in its own space
+        let inverse = world.invert();
+        let ray = Ray::new(
+            self.ray.origin.apply_matrix4(&inverse),
+            self.ray.direction.transform_direction(&inverse)
+        );
+
+        if !is_lines && !ray.intersects_box(&geo.compute_bounding_box()) {
+            return;
+        }
+
+        let positions = match &geo.positions {
+            Some(positions) => positions,
+            None => return
+        };
+        let indices: Vec<usize> = match &geo.indices {
+            Some(indices) => indices.iter().map(|&i| i as usize).collect(),
+            None => (0..positions.len()).collect()
+        };
+
+        let mut push = |distance: f32, point: Vector3, face_index: usize, barycentric: Option<Vector3>| {
+            if distance >= self.near && distance <= self.far {
+                hits.push(Intersection {
+                    distance,
+                    point,
+                    face_index,
+                    barycentric,
+                    object: object.clone(),
+                });
+            }
+        };
+
+        match geo.mode {
+            BufferGeometryMode::Triangles => {
+                for (face_index, face) in indices.chunks_exact(3).enumerate() {
+                    // faces indexing past the positions are skipped
+                    let (Some(a), Some(b), Some(c)) = (positions.get(face[0]), positions.get(face[1]), positions.get(face[2])) else {
+                        continue;
+                    };
+                    let tri = Triangle::new(*a, *b, *c);
+                    let hit = match side {
+                        Side::Front => ray.intersecting_triangle(&tri, true),
+                        Side::Back => ray.intersecting_triangle(&Triangle::new(tri.a, tri.c, tri.b), true),
+                        Side::Double => ray.intersecting_triangle(&tri, false),
+                    };
+
+                    if let Some(local) = hit {
+                        let point = local.apply_matrix4(&world);
+                        push(point.distance_to(&self.ray.origin), point, face_index, tri.get_barycoord(&local));
+                    }
+                }
+            },
+            BufferGeometryMode::Lines | BufferGeometryMode::LineStrip => {
+                let step = if geo.mode == BufferGeometryMode::Lines {2} else {1};
+                let threshold = self.line_threshold / get_average_scale(&world);
+                let threshold_sq = threshold * threshold;
+
+                for i in (0..indices.len().saturating_sub(1)).step_by(step) {
+                    let (Some(start), Some(end)) = (positions.get(indices[i]), positions.get(indices[i + 1])) else {
+                        continue;
+                    };
+                    let (dist_sq, on_ray, on_segment) = ray.distance_sq_to_segment(start, end);
+                    if dist_sq > threshold_sq {
+                        continue;
+                    }
+
+                    let distance = on_ray.apply_matrix4(&world).distance_to(&self.ray.origin);
+                    push(distance, on_segment.apply_matrix4(&world), i / step, None);
+                }
+            }
+        }
+    }
+}
+
+fn get_average_scale(
+    m: &Matrix4
+) -> f32 {
+    let e = &m.0;
+    let scale = Vector3::new(e[0], e[1], e[2]).length() +
+        Vector3::new(e[4], e[5], e[6]).length() +
+        Vector3::new(e[8], e[9], e[10]).length();
+    
+    scale / 3.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use std::{rc::Rc, cell::RefCell};
+    use crate::{
+        math::Quaternion,
+        camera::{PerspectiveCamera, OrthographicCamera},
+        core::{Object3d, Transformable, Updatable, BufferGeometry},
+        geometry::{Triangles, Lines, LineStrip},
+        object::{Mesh, Group}
+    };
+    use super::*;
+
+    fn assert_near(
+        a: Vector3,
+        b: Vector3
+    ) {
+        assert!(a.distance_to(&b) < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    // at (1, 2, 10), turned to look down -x
+    fn place<C: Transformable + Updatable>(
+        camera: &mut C
+    ) {
+        camera.set_position(Vector3::new(1.0, 2.0, 10.0));
+        camera.set_rotation(Quaternion::from_axis_and_angle(&Vector3::new(0.0, 1.0, 0.0), PI / 2.0));
+        camera.update_matrix();
+    }
+
+    #[test]
+    fn perspective_rays_leave_the_camera() {
+        // the near plane is 4 wide and 2 high at a distance of 1
+        let mut camera = PerspectiveCamera::new(90.0, 2.0, 1.0, 100.0);
+        place(&mut camera);
+        let mut raycaster = Raycaster::default();
+
+        raycaster.set_from_camera([0.0, 0.0], &camera);
+        assert_near(raycaster.ray.origin, Vector3::new(1.0, 2.0, 10.0));
+        assert_near(raycaster.ray.direction, Vector3::new(-1.0, 0.0, 0.0));
+
+        // the camera's right is -z
+        raycaster.set_from_camera([1.0, 0.0], &camera);
+        assert_near(raycaster.ray.origin, Vector3::new(1.0, 2.0, 10.0));
+        assert_near(raycaster.ray.direction, Vector3::new(-1.0, 0.0, -2.0).normalize());
+
+        raycaster.set_from_camera([-1.0, 1.0], &camera);
+        assert_near(raycaster.ray.direction, Vector3::new(-1.0, 1.0, 2.0).normalize());
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let mut camera = OrthographicCamera::new(-4.0, 4.0, 3.0, -3.0, 1.0, 50.0);
+        place(&mut camera);
+        let mut raycaster = Raycaster::default();
+
+        raycaster.set_from_camera([0.0, 0.0], &camera);
+        assert_near(raycaster.ray.origin, Vector3::new(0.0, 2.0, 10.0));
+        assert_near(raycaster.ray.direction, Vector3::new(-1.0, 0.0, 0.0));
+
+        // starting on the near plane, half way to the right edge and on the bottom one
+        raycaster.set_from_camera([0.5, -1.0], &camera);
+        assert_near(raycaster.ray.origin, Vector3::new(0.0, -1.0, 8.0));
+        assert_near(raycaster.ray.direction, Vector3::new(-1.0, 0.0, 0.0));
+    }
+
+    // a triangle facing +z, from (-1, -1) to (1, -1) and (0, 1) at the given depth
+    fn triangle(
+        z: f32
+    ) -> Rc<RefCell<Mesh>> {
+        let geo = Triangles::new(vec![Triangle::new(
+            Vector3::new(-1.0, -1.0, 0.0), 
+            Vector3::new(1.0, -1.0, 0.0), 
+            Vector3::new(0.0, 1.0, 0.0)
+        )], [1.0, 1.0, 1.0]);
+        let mesh = Mesh::new(&geo);
+        mesh.borrow_mut().get_object_mut().set_position(Vector3::new(0.0, 0.0, z));
+        mesh.borrow_mut().update_matrix_world(true);
+        mesh
+    }
+
+    // looking down -z from z = 10, through (x, y)
+    fn down(
+        x: f32,
+        y: f32
+    ) -> Raycaster {
+        Raycaster::new(Ray::new(Vector3::new(x, y, 10.0), Vector3::new(0.0, 0.0, -1.0)))
+    }
+
+    fn is(
+        hit: &Intersection,
+        mesh: &Rc<RefCell<Mesh>>
+    ) -> bool {
+        std::ptr::addr_eq(Rc::as_ptr(&hit.object), Rc::as_ptr(mesh))
+    }
+
+    #[test]
+    fn hits_are_sorted_by_distance() {
+        let (back, middle, front) = (triangle(-5.0), triangle(0.0), triangle(3.0));
+        let objects: [ObjectRef; 3] = [middle.clone(), back.clone(), front.clone()];
+
+        let hits = down(0.0, 0.0).intersect_objects(&objects, false);
+        assert_eq!(hits.len(), 3);
+        assert!(is(&hits[0], &front) && is(&hits[1], &middle) && is(&hits[2], &back));
+        for (hit, z) in hits.iter().zip([3.0, 0.0, -5.0]) {
+            assert!((hit.distance - (10.0 - z)).abs() < 1e-4);
+            assert_near(hit.point, Vector3::new(0.0, 0.0, z));
+            assert_eq!(hit.face_index, 0);
+        }
+
+        assert!(down(5.0, 0.0).intersect_objects(&objects, false).is_empty());
+    }
+
+    #[test]
+    fn children_are_only_tested_when_recursive() {
+        let group = Group::new();
+        let mesh = triangle(0.0);
+        group.borrow_mut().add(mesh.clone()).unwrap();
+        group.borrow_mut().get_object_mut().set_position(Vector3::new(0.0, 0.0, 2.0));
+        group.borrow_mut().update_matrix_world(true);
+        let group: ObjectRef = group;
+
+        let raycaster = down(0.0, 0.0);
+        assert!(raycaster.intersect_object(&group, false).is_empty());
+        let hits = raycaster.intersect_object(&group, true);
+        assert_eq!(hits.len(), 1);
+        assert!((hits[0].distance - 8.0).abs() < 1e-4);
+
+        // hidden parents hide their children
+        group.borrow_mut().get_object_mut().visible = false;
+        assert!(raycaster.intersect_object(&group, true).is_empty());
+    }
+
+    #[test]
+    fn near_and_far_filter_hits() {
+        let objects: [ObjectRef; 3] = [triangle(-5.0), triangle(0.0), triangle(3.0)];
+        let mut raycaster = down(0.0, 0.0);
+        raycaster.near = 8.0;
+        raycaster.far = 12.0;
+
+        let hits = raycaster.intersect_objects(&objects, false);
+        assert_eq!(hits.len(), 1);
+        assert!((hits[0].distance - 10.0).abs() < 1e-4);
+
+        // inclusive
+        raycaster.near = 7.0;
+        raycaster.far = 7.0;
+        assert_eq!(raycaster.intersect_objects(&objects, false).len(), 1);
+    }
+
+    #[test]
+    fn sides_decide_which_faces_are_hit() {
+        let mesh = triangle(0.0);
+        let object: ObjectRef = mesh.clone();
+        let from_front = down(0.0, 0.0);
+        let from_back = Raycaster::new(Ray::new(Vector3::new(0.0, 0.0, -10.0), Vector3::new(0.0, 0.0, 1.0)));
+
+        for (side, front, back) in [(Side::Front, 1, 0), (Side::Back, 0, 1), (Side::Double, 1, 1)] {
+            mesh.borrow().get_material().borrow_mut().get_data_mut().side = side;
+            assert_eq!(from_front.intersect_object(&object, false).len(), front);
+            assert_eq!(from_back.intersect_object(&object, false).len(), back);
+        }
+    }
+
+    #[test]
+    fn hits_carry_barycentric_coordinates() {
+        let object: ObjectRef = triangle(0.0);
+
+        for (x, y, weights) in [
+            (0.0, 0.0, Vector3::new(0.25, 0.25, 0.5)),
+            (0.5, -0.5, Vector3::new(0.125, 0.625, 0.25)),
+            (-0.5, -0.5, Vector3::new(0.625, 0.125, 0.25)),
+        ] {
+            let hits = down(x, y).intersect_object(&object, false);
+            assert_eq!(hits.len(), 1);
+            assert_near(hits[0].barycentric.unwrap(), weights);
+        }
+    }
+
+    #[test]
+    fn lines_are_hit_within_the_threshold() {
+        // along x at y = 0.5, scaled by 2 so y = 1 in the world
+        let lines = Mesh::new(&Lines::new(
+            vec![Vector3::new(-5.0, 0.5, 0.0), Vector3::new(5.0, 0.5, 0.0)], 
+            [1.0, 1.0, 1.0]
+        ));
+        lines.borrow_mut().get_object_mut().set_scale(Vector3::new(2.0, 2.0, 2.0));
+        lines.borrow_mut().update_matrix_world(true);
+        let object: ObjectRef = lines;
+
+        let mut raycaster = down(0.0, 0.0);
+        raycaster.line_threshold = 0.8;
+        assert!(raycaster.intersect_object(&object, false).is_empty());
+
+        raycaster.line_threshold = 1.2;
+        let hits = raycaster.intersect_object(&object, false);
+        assert_eq!(hits.len(), 1);
+        assert!((hits[0].distance - 10.0).abs() < 1e-4);
+        assert_near(hits[0].point, Vector3::new(0.0, 1.0, 0.0));
+        assert!(hits[0].barycentric.is_none());
+    }
+
+    #[test]
+    fn line_strips_number_their_segments() {
+        let strip: ObjectRef = Mesh::new(&LineStrip::new(
+            vec![Vector3::new(-3.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(3.0, 0.0, 0.0)], 
+            [1.0, 1.0, 1.0]
+        ));
+        strip.borrow_mut().update_matrix_world(true);
+
+        let mut raycaster = down(2.0, 0.0);
+        raycaster.line_threshold = 0.1;
+        let hits = raycaster.intersect_object(&strip, false);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].face_index, 2);
+    }
+
+    #[test]
+    fn faces_past_the_positions_are_skipped() {
+        let geo = Triangles {
+            geo: BufferGeometry::new(
+                BufferGeometryMode::Triangles, 
+                Some(vec![0, 1, 9, 0, 1, 2]), 
+                Some(vec![Vector3::new(-1.0, -1.0, 0.0), Vector3::new(1.0, -1.0, 0.0), Vector3::new(0.0, 1.0, 0.0)]), 
+                None, 
+                None
+            ),
+        };
+        let mesh: ObjectRef = Mesh::new(&geo);
+        mesh.borrow_mut().update_matrix_world(true);
+
+        let hits = down(0.0, 0.0).intersect_object(&mesh, false);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].face_index, 1);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    core::{BufferGeometry, Geometrical, BufferGeometryMode}, 
+    core::{BufferGeometry, Geometrical, BufferGeometryMode, UV}, 
     math::Vector3
 };
 
@@ -14,6 +14,14 @@ enum Coords {
     XYZ
 }
 
+// the faces built so far
+#[derive(Default)]
+struct Planes {
+    indices: Vec<u32>,
+    positions: Vec<Vector3>,
+    uvs: Vec<UV>,
+}
+
 impl Box3 {
     pub fn new_ex(
         width: f32,
@@ -24,70 +32,51 @@ impl Box3 {
         depth_segs: usize
     ) -> Self {
 
-        let mut num_vertices = 0;
-        let mut indices = vec![];
-        let mut positions = vec![];
+        let mut planes = Planes::default();
 
-        num_vertices += Self::build_plane(
-            &mut indices, 
-            &mut positions,
+        planes.build_plane(
             Coords::ZYX, 
-            -1.0, -1.0, 
-            depth, height, width, 
-            depth_segs, height_segs,
-            num_vertices
+            [-1.0, -1.0], 
+            [depth, height, width], 
+            [depth_segs, height_segs]
         );
 
-        num_vertices += Self::build_plane(
-            &mut indices, 
-            &mut positions,
+        planes.build_plane(
             Coords::ZYX, 
-            1.0, -1.0, 
-            depth, height, -width, 
-            depth_segs, height_segs,
-            num_vertices
+            [1.0, -1.0], 
+            [depth, height, -width], 
+            [depth_segs, height_segs]
         );
 
-        num_vertices += Self::build_plane(
-            &mut indices, 
-            &mut positions,
+        planes.build_plane(
             Coords::XZY, 
-            1.0, 1.0, 
-            width, depth, height, 
-            width_segs, depth_segs,
-            num_vertices
+            [1.0, 1.0], 
+            [width, depth, height], 
+            [width_segs, depth_segs]
         );
 
-        num_vertices += Self::build_plane(
-            &mut indices, 
-            &mut positions,
+        planes.build_plane(
             Coords::XZY, 
-            1.0, -1.0, 
-            width, depth, -height, 
-            width_segs, depth_segs,
-            num_vertices
+            [1.0, -1.0], 
+            [width, depth, -height], 
+            [width_segs, depth_segs]
         );
 
-        num_vertices += Self::build_plane(
-            &mut indices, 
-            &mut positions,
+        planes.build_plane(
             Coords::XYZ, 
-            1.0, -1.0, 
-            width, height, depth, 
-            width_segs, height_segs,
-            num_vertices
+            [1.0, -1.0], 
+            [width, height, depth], 
+            [width_segs, height_segs]
         );
 
-        num_vertices += Self::build_plane(
-            &mut indices, 
-            &mut positions,
+        planes.build_plane(
             Coords::XYZ, 
-            -1.0, -1.0, 
-            width, height, -depth, 
-            width_segs, height_segs,
-            num_vertices
+            [-1.0, -1.0], 
+            [width, height, -depth], 
+            [width_segs, height_segs]
         );
 
+        let num_vertices = planes.positions.len();
         let mut colors = vec![];
         let mut color = 0.1;
         let inc = 0.9 / num_vertices as f32;
@@ -96,14 +85,18 @@ impl Box3 {
             colors.push([color, 0.0, 0.0]);
         }
         
+        let mut geo = BufferGeometry::new(
+            BufferGeometryMode::Triangles, 
+            Some(planes.indices), 
+            Some(planes.positions), 
+            None,
+            Some(colors),
+        );
+        geo.set_uvs(Some(planes.uvs));
+        geo.compute_vertex_normals();
+
         Self {
-            geo: BufferGeometry::new(
-                BufferGeometryMode::Triangles, 
-                Some(indices), 
-                Some(positions), 
-                None,
-                Some(colors),
-            )
+            geo
         }
     }
 
@@ -114,20 +107,23 @@ impl Box3 {
     ) -> Self {
         Self::new_ex(width, height, depth, 1, 1, 1)
     }
+}
 
+impl Planes {
+    // dir flips the u and v axes, size is the width, height and depth along them,
+    // grid the segments along u and v
     fn build_plane(
-        indices: &mut Vec<u32>, 
-        positions: &mut Vec<Vector3>, 
+        &mut self,
         coords: Coords, 
-        udir: f32, 
-        vdir: f32, 
-        width: f32, 
-        height: f32, 
-        depth: f32, 
-        grid_x: usize, 
-        grid_y: usize,
-        num_vertices: usize
-    ) -> usize {
+        dir: [f32; 2], 
+        size: [f32; 3], 
+        grid: [usize; 2]
+    ) {
+        let [udir, vdir] = dir;
+        let [width, height, depth] = size;
+        let [grid_x, grid_y] = grid;
+        let num_vertices = self.positions.len();
+
         let segment_width = width / grid_x as f32;
         let segment_height = height / grid_y as f32;
 
@@ -138,8 +134,6 @@ impl Box3 {
         let grid_x1 = grid_x + 1;
         let grid_y1 = grid_y + 1;
 
-        let mut vertex_counter = 0;
-
         let mut vector = Vector3::default();
 
         for iy in 0..grid_y1 {
@@ -165,9 +159,8 @@ impl Box3 {
                     },
                 }
 
-                positions.push(vector);
-
-                vertex_counter += 1;
+                self.positions.push(vector);
+                self.uvs.push([ix as f32 / grid_x as f32, 1.0 - iy as f32 / grid_y as f32]);
             }
         }
 
@@ -178,12 +171,10 @@ impl Box3 {
                 let c = (num_vertices + (ix + 1) + grid_x1 * (iy + 1)) as u32;
                 let d = (num_vertices + (ix + 1) + grid_x1 * iy) as u32;
 
-                indices.extend_from_slice(&[a, b, d]);
-                indices.extend_from_slice(&[b, c, d]);
+                self.indices.extend_from_slice(&[a, b, d]);
+                self.indices.extend_from_slice(&[b, c, d]);
             }
         }
-
-        vertex_counter
     }
 }
 
@@ -202,7 +193,7 @@ impl Geometrical for Box3 {
 
     fn drop(
         &mut self, 
-        renderer: &crate::renderer::GlRenderer
+        renderer: &mut dyn crate::renderer::Renderer
     ) {
         (self as &mut dyn Geometrical).destroy(renderer)
     }
@@ -231,7 +231,7 @@ impl Geometrical for Cylinder {
 
     fn drop(
         &mut self, 
-        renderer: &crate::renderer::GlRenderer
+        renderer: &mut dyn crate::renderer::Renderer
     ) {
         (self as &mut dyn Geometrical).destroy(renderer)
     }
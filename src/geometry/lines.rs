@@ -41,7 +41,7 @@ impl Geometrical for Lines {
 
     fn drop(
         &mut self, 
-        renderer: &crate::renderer::GlRenderer
+        renderer: &mut dyn crate::renderer::Renderer
     ) {
         (self as &mut dyn Geometrical).destroy(renderer)
     }
@@ -50,7 +50,7 @@ impl Geometrical for Triangles {
 
     fn drop(
         &mut self, 
-        renderer: &crate::renderer::GlRenderer
+        renderer: &mut dyn crate::renderer::Renderer
     ) {
         (self as &mut dyn Geometrical).destroy(renderer)
     }
@@ -0,0 +1,157 @@
+use std::fs;
+use super::{
+    encode_png, 
+    is_png, 
+    decode_png, 
+    is_jpeg, 
+    decode_jpeg, 
+    is_ppm, 
+    decode_ppm, 
+    is_tga, 
+    decode_tga
+};
+
+// RGBA8 pixels, rows ordered from top to bottom
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    // panics when the size doesn't fit in memory
+    pub fn new(
+        width: u32,
+        height: u32
+    ) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; Self::get_data_size(width, height).unwrap()],
+        }
+    }
+
+    pub fn from_rgba(
+        width: u32,
+        height: u32,
+        data: Vec<u8>
+    ) -> Result<Self, String> {
+        let expected = Self::get_data_size(width, height)?;
+        if data.len() != expected {
+            return Err(format!(
+                "Expected {} bytes for a {}x{} RGBA image, got {}", 
+                expected, width, height, data.len()
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    // in bytes, an error when it doesn't fit in usize
+    pub(crate) fn get_data_size(
+        width: u32,
+        height: u32
+    ) -> Result<usize, String> {
+        (width as usize).checked_mul(height as usize)
+            .and_then(|n| n.checked_mul(4))
+            .ok_or(format!("Image size {}x{} is too large", width, height))
+    }
+
+    // format is sniffed from the content: png, jpeg, pnm or tga
+    pub fn load_from_bytes(
+        bytes: &[u8]
+    ) -> Result<Self, String> {
+        if is_png(bytes) {
+            decode_png(bytes)
+        }
+        else if is_jpeg(bytes) {
+            decode_jpeg(bytes)
+        }
+        else if is_ppm(bytes) {
+            decode_ppm(bytes)
+        }
+        else if is_tga(bytes) {
+            decode_tga(bytes)
+        }
+        else {
+            Err("Unknown image format".to_string())
+        }
+    }
+
+    pub fn load(
+        path: &str
+    ) -> Result<Self, String> {
+        let bytes = fs::read(path)
+            .map_err(|e| format!("{}: {}", path, e))?;
+        Self::load_from_bytes(&bytes)
+    }
+
+    pub fn get_pixel(
+        &self,
+        x: u32,
+        y: u32
+    ) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]
+    }
+
+    pub fn set_pixel(
+        &mut self,
+        x: u32,
+        y: u32,
+        rgba: [u8; 4]
+    ) {
+        let i = ((y * self.width + x) * 4) as usize;
+        self.data[i..i + 4].copy_from_slice(&rgba);
+    }
+
+    pub fn flip_y(
+        &mut self
+    ) {
+        let row = (self.width * 4) as usize;
+        let h = self.height as usize;
+        for y in 0..h / 2 {
+            let (top, bottom) = self.data.split_at_mut((h - 1 - y) * row);
+            top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+        }
+    }
+
+    pub fn to_png(
+        &self
+    ) -> Result<Vec<u8>, String> {
+        encode_png(self)
+    }
+
+    pub fn save_png(
+        &self,
+        path: &str
+    ) -> Result<(), String> {
+        fs::write(path, self.to_png()?)
+            .map_err(|e| e.to_string())
+    }
+
+    // number of pixels where any channel differs by more than tolerance,
+    // for comparing renders against golden images
+    pub fn count_differences(
+        &self,
+        other: &Image,
+        tolerance: u8
+    ) -> Result<usize, String> {
+        if self.width != other.width || self.height != other.height {
+            return Err(format!(
+                "Image sizes differ: {}x{} and {}x{}", 
+                self.width, self.height, other.width, other.height
+            ));
+        }
+
+        Ok(self.data.chunks_exact(4)
+            .zip(other.data.chunks_exact(4))
+            .filter(|(a, b)| a.iter().zip(b.iter()).any(|(a, b)| a.abs_diff(*b) > tolerance))
+            .count())
+    }
+}
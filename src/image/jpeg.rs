@@ -0,0 +1,672 @@
+use std::{f32::consts::PI, sync::OnceLock};
+use super::Image;
+
+// position in the 8x8 block of the n-th coefficient of the stream
+const ZIGZAG: [usize; 64] = [
+     0,  1,  8, 16,  9,  2,  3, 10, 
+    17, 24, 32, 25, 18, 11,  4,  5, 
+    12, 19, 26, 33, 40, 48, 41, 34, 
+    27, 20, 13,  6,  7, 14, 21, 28, 
+    35, 42, 49, 56, 57, 50, 43, 36, 
+    29, 22, 15, 23, 30, 37, 44, 51, 
+    58, 59, 52, 45, 38, 31, 39, 46, 
+    53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+pub fn is_jpeg(
+    bytes: &[u8]
+) -> bool {
+    bytes.starts_with(&[0xff, 0xd8, 0xff])
+}
+
+#[derive(Clone, Default)]
+struct HuffmanTable {
+    // per code length: the largest code, and the index of the first symbol
+    max_code: [i32; 17],
+    offset: [i32; 17],
+    symbols: Vec<u8>,
+}
+
+impl HuffmanTable {
+    fn new(
+        counts: &[u8],
+        symbols: &[u8]
+    ) -> Self {
+        let mut table = Self {
+            max_code: [-1; 17],
+            offset: [0; 17],
+            symbols: symbols.to_vec(),
+        };
+
+        let mut code = 0i32;
+        let mut index = 0i32;
+        for len in 1..=16 {
+            let count = counts[len - 1] as i32;
+            if count > 0 {
+                table.offset[len] = index - code;
+                code += count;
+                index += count;
+                table.max_code[len] = code - 1;
+            }
+            code <<= 1;
+        }
+
+        table
+    }
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quant: usize,
+    dc_table: usize,
+    ac_table: usize,
+    dc_pred: i32,
+    // decoded samples, padded to whole mcus
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+struct Frame {
+    width: usize,
+    height: usize,
+    components: Vec<Component>,
+    h_max: usize,
+    v_max: usize,
+    mcus_x: usize,
+    mcus_y: usize,
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+    bits: u32,
+}
+
+impl<'a> BitReader<'a> {
+    // most significant bit first, skipping stuffed zeros; stops feeding at markers
+    fn read_bit(
+        &mut self
+    ) -> u32 {
+        if self.bits == 0 {
+            let byte = match self.data.get(self.pos) {
+                Some(0xff) => {
+                    match self.data.get(self.pos + 1) {
+                        Some(0x00) => {
+                            self.pos += 2;
+                            0xff
+                        },
+                        _ => 0,
+                    }
+                },
+                Some(byte) => {
+                    self.pos += 1;
+                    *byte
+                },
+                None => 0,
+            };
+            self.bit = byte as u32;
+            self.bits = 8;
+        }
+
+        self.bits -= 1;
+        (self.bit >> self.bits) & 1
+    }
+
+    fn read(
+        &mut self,
+        count: u32
+    ) -> i32 {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.read_bit() as i32;
+        }
+        value
+    }
+
+    // sizes come from the huffman tables and are at most 16 in valid files
+    fn receive_extend(
+        &mut self,
+        size: u32
+    ) -> Result<i32, String> {
+        if size == 0 {
+            return Ok(0);
+        }
+        if size > 16 {
+            return Err("Invalid JPEG coefficient size".to_string());
+        }
+        let value = self.read(size);
+        if value < 1 << (size - 1) {
+            Ok(value - (1 << size) + 1)
+        }
+        else {
+            Ok(value)
+        }
+    }
+
+    fn decode(
+        &mut self,
+        table: &HuffmanTable
+    ) -> Result<u8, String> {
+        let mut code = 0i32;
+        for len in 1..=16 {
+            code = (code << 1) | self.read_bit() as i32;
+            if code <= table.max_code[len] {
+                return table.symbols.get((table.offset[len] + code) as usize)
+                    .copied()
+                    .ok_or("Invalid JPEG huffman table".to_string());
+            }
+        }
+        Err("Invalid JPEG huffman code".to_string())
+    }
+
+    // skips to the marker following the entropy coded data
+    fn reset(
+        &mut self
+    ) {
+        self.bits = 0;
+        while self.pos + 1 < self.data.len() && 
+            (self.data[self.pos] != 0xff || matches!(self.data[self.pos + 1], 0x00 | 0xff)) {
+            self.pos += 1;
+        }
+    }
+}
+
+// baseline and extended sequential huffman, grayscale or YCbCr with any subsampling
+pub fn decode_jpeg(
+    bytes: &[u8]
+) -> Result<Image, String> {
+    if !is_jpeg(bytes) {
+        return Err("Not a JPEG file".to_string());
+    }
+
+    let mut quant = [[0u16; 64]; 4];
+    let mut dc_tables = vec![HuffmanTable::default(); 4];
+    let mut ac_tables = vec![HuffmanTable::default(); 4];
+    let mut restart_interval = 0;
+    let mut frame: Option<Frame> = None;
+    let mut adobe_transform = None;
+
+    let mut pos = 2;
+    loop {
+        // markers can be preceded by any number of fill bytes
+        while bytes.get(pos) == Some(&0xff) && bytes.get(pos + 1) == Some(&0xff) {
+            pos += 1;
+        }
+        if pos + 2 > bytes.len() || bytes[pos] != 0xff {
+            return Err("Invalid JPEG marker".to_string());
+        }
+        let marker = bytes[pos + 1];
+        if marker == 0xd9 {
+            break;
+        }
+        if pos + 4 > bytes.len() {
+            return Err("Truncated JPEG segment".to_string());
+        }
+        
+        let len = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let data = bytes.get(pos + 4..pos + 2 + len)
+            .ok_or("Truncated JPEG segment".to_string())?;
+        pos += 2 + len;
+
+        match marker {
+            0xc0 | 0xc1 => {
+                frame = Some(read_frame(data)?);
+            },
+            0xc2 | 0xc3 | 0xc5..=0xc7 | 0xc9..=0xcb | 0xcd..=0xcf => {
+                return Err("Only baseline and sequential JPEGs are supported".to_string());
+            },
+            0xc4 => {
+                let mut i = 0;
+                while i + 17 <= data.len() {
+                    let (class, id) = (data[i] >> 4, (data[i] & 3) as usize);
+                    let counts = &data[i + 1..i + 17];
+                    let total = counts.iter().map(|c| *c as usize).sum::<usize>();
+                    let symbols = data.get(i + 17..i + 17 + total)
+                        .ok_or("Truncated JPEG huffman table".to_string())?;
+                    let table = HuffmanTable::new(counts, symbols);
+                    if class == 0 {
+                        dc_tables[id] = table;
+                    }
+                    else {
+                        ac_tables[id] = table;
+                    }
+                    i += 17 + total;
+                }
+            },
+            0xdb => {
+                let mut i = 0;
+                while i < data.len() {
+                    let (wide, id) = (data[i] >> 4 != 0, (data[i] & 3) as usize);
+                    let size = if wide {128} else {64};
+                    let values = data.get(i + 1..i + 1 + size)
+                        .ok_or("Truncated JPEG quantization table".to_string())?;
+                    for k in 0..64 {
+                        quant[id][k] = if wide {
+                            u16::from_be_bytes([values[k * 2], values[k * 2 + 1]])
+                        }
+                        else {
+                            values[k] as u16
+                        };
+                    }
+                    i += 1 + size;
+                }
+            },
+            0xdd => {
+                let interval = data.get(..2)
+                    .ok_or("Truncated JPEG restart interval".to_string())?;
+                restart_interval = u16::from_be_bytes([interval[0], interval[1]]) as usize;
+            },
+            0xee if data.len() >= 12 && data.starts_with(b"Adobe") => {
+                adobe_transform = Some(data[11]);
+            },
+            0xda => {
+                let frame = frame.as_mut()
+                    .ok_or("JPEG scan before the frame header".to_string())?;
+                pos = decode_scan(bytes, pos, data, frame, &quant, &dc_tables, &ac_tables, restart_interval)?;
+            },
+            _ => {
+            },
+        }
+    }
+
+    let frame = frame.ok_or("Missing JPEG frame".to_string())?;
+    to_image(&frame, adobe_transform)
+}
+
+fn read_frame(
+    data: &[u8]
+) -> Result<Frame, String> {
+    if data.len() < 6 || data[0] != 8 {
+        return Err("Only 8-bit JPEGs are supported".to_string());
+    }
+    let height = u16::from_be_bytes([data[1], data[2]]) as usize;
+    let width = u16::from_be_bytes([data[3], data[4]]) as usize;
+    let count = data[5] as usize;
+    if width == 0 || height == 0 {
+        return Err("Invalid JPEG size".to_string());
+    }
+
+    let mut components = vec![];
+    for c in data[6..].chunks_exact(3).take(count) {
+        let (h, v) = ((c[1] >> 4) as usize, (c[1] & 15) as usize);
+        if !(1..=4).contains(&h) || !(1..=4).contains(&v) {
+            return Err("Invalid JPEG sampling factors".to_string());
+        }
+        components.push(Component {
+            id: c[0],
+            h,
+            v,
+            quant: (c[2] & 3) as usize,
+            dc_table: 0,
+            ac_table: 0,
+            dc_pred: 0,
+            width: 0,
+            height: 0,
+            pixels: vec![],
+        });
+    }
+    if components.len() != count {
+        return Err("Truncated JPEG frame header".to_string());
+    }
+
+    let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+    let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+    let mcus_x = width.div_ceil(8 * h_max);
+    let mcus_y = height.div_ceil(8 * v_max);
+
+    // the samples are allocated by the first scan of each component, once the data is known to cover them
+    for c in &mut components {
+        c.width = mcus_x * c.h * 8;
+        c.height = mcus_y * c.v * 8;
+    }
+
+    Ok(Frame {
+        width,
+        height,
+        components,
+        h_max,
+        v_max,
+        mcus_x,
+        mcus_y,
+    })
+}
+
+// returns the position after the entropy coded data
+#[allow(clippy::too_many_arguments)]
+fn decode_scan(
+    bytes: &[u8],
+    start: usize,
+    header: &[u8],
+    frame: &mut Frame,
+    quant: &[[u16; 64]; 4],
+    dc_tables: &[HuffmanTable],
+    ac_tables: &[HuffmanTable],
+    restart_interval: usize
+) -> Result<usize, String> {
+    let count = *header.first()
+        .ok_or("Truncated JPEG scan header".to_string())? as usize;
+    let selectors = header.get(1..1 + count * 2)
+        .ok_or("Truncated JPEG scan header".to_string())?;
+    let mut scan = vec![];
+    for s in selectors.chunks_exact(2) {
+        let index = frame.components.iter().position(|c| c.id == s[0])
+            .ok_or("Unknown JPEG scan component".to_string())?;
+        frame.components[index].dc_table = (s[1] >> 4) as usize & 3;
+        frame.components[index].ac_table = (s[1] & 15) as usize & 3;
+        frame.components[index].dc_pred = 0;
+        scan.push(index);
+    }
+
+    // a single component scan isn't interleaved, its mcu is one block
+    let (mcus_x, mcus_y) = if scan.len() == 1 {
+        let c = &frame.components[scan[0]];
+        (
+            (frame.width * c.h).div_ceil(frame.h_max).div_ceil(8),
+            (frame.height * c.v).div_ceil(frame.v_max).div_ceil(8)
+        )
+    }
+    else {
+        (frame.mcus_x, frame.mcus_y)
+    };
+
+    // every block takes at least two bits, a dc and an end of block code
+    let blocks = scan.iter()
+        .map(|&i| if scan.len() == 1 {1} else {frame.components[i].h * frame.components[i].v})
+        .sum::<usize>() * mcus_x * mcus_y;
+    if bytes.len().saturating_sub(start) < blocks.div_ceil(4) {
+        return Err("Truncated JPEG scan data".to_string());
+    }
+    for &i in &scan {
+        let c = &mut frame.components[i];
+        if c.pixels.is_empty() {
+            c.pixels = vec![0; c.width * c.height];
+        }
+    }
+
+    let mut reader = BitReader {
+        data: bytes,
+        pos: start,
+        bit: 0,
+        bits: 0,
+    };
+    let mut block = [0f32; 64];
+
+    for mcu in 0..mcus_x * mcus_y {
+        if restart_interval > 0 && mcu > 0 && mcu % restart_interval == 0 {
+            reader.reset();
+            // RSTn
+            if matches!(bytes.get(reader.pos + 1), Some(0xd0..=0xd7)) {
+                reader.pos += 2;
+            }
+            for &i in &scan {
+                frame.components[i].dc_pred = 0;
+            }
+        }
+
+        let (mx, my) = (mcu % mcus_x, mcu / mcus_x);
+        for &i in &scan {
+            let c = &mut frame.components[i];
+            let (bh, bv) = if scan.len() == 1 {(1, 1)} else {(c.h, c.v)};
+            for by in 0..bv {
+                for bx in 0..bh {
+                    decode_block(
+                        &mut reader, 
+                        &dc_tables[c.dc_table], 
+                        &ac_tables[c.ac_table], 
+                        &quant[c.quant], 
+                        &mut c.dc_pred, 
+                        &mut block
+                    )?;
+                    let x = (mx * bh + bx) * 8;
+                    let y = (my * bv + by) * 8;
+                    if x < c.width && y < c.height {
+                        idct(&block, &mut c.pixels, c.width, x, y);
+                    }
+                }
+            }
+        }
+    }
+
+    reader.reset();
+    Ok(reader.pos)
+}
+
+fn decode_block(
+    reader: &mut BitReader,
+    dc_table: &HuffmanTable,
+    ac_table: &HuffmanTable,
+    quant: &[u16; 64],
+    dc_pred: &mut i32,
+    block: &mut [f32; 64]
+) -> Result<(), String> {
+    block.fill(0.0);
+
+    // 16-bit quantization tables and crafted differences would overflow integer products
+    let size = reader.decode(dc_table)?;
+    *dc_pred = dc_pred.wrapping_add(reader.receive_extend(size as u32)?);
+    block[0] = *dc_pred as f32 * quant[0] as f32;
+
+    let mut k = 1;
+    while k < 64 {
+        let rs = reader.decode(ac_table)?;
+        let (run, size) = ((rs >> 4) as usize, (rs & 15) as u32);
+        if size == 0 {
+            if run != 15 {
+                break;
+            }
+            k += 16;
+            continue;
+        }
+        k += run;
+        if k > 63 {
+            return Err("Invalid JPEG coefficient index".to_string());
+        }
+        block[ZIGZAG[k]] = reader.receive_extend(size)? as f32 * quant[k] as f32;
+        k += 1;
+    }
+
+    Ok(())
+}
+
+// separable float inverse dct, writing level shifted samples
+fn idct(
+    block: &[f32; 64],
+    out: &mut [u8],
+    stride: usize,
+    x0: usize,
+    y0: usize
+) {
+    // built on first use, shared by every block
+    static COS: OnceLock<[[f32; 8]; 8]> = OnceLock::new();
+    let cos = COS.get_or_init(|| {
+        let mut cos = [[0f32; 8]; 8];
+        for (x, row) in cos.iter_mut().enumerate() {
+            for (u, c) in row.iter_mut().enumerate() {
+                let scale = if u == 0 {1.0 / 2f32.sqrt()} else {1.0};
+                *c = scale * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+            }
+        }
+        cos
+    });
+
+    let mut tmp = [0f32; 64];
+    for v in 0..8 {
+        for x in 0..8 {
+            tmp[v * 8 + x] = (0..8).map(|u| cos[x][u] * block[v * 8 + u]).sum::<f32>() / 2.0;
+        }
+    }
+
+    for y in 0..8 {
+        for x in 0..8 {
+            let value = (0..8).map(|v| cos[y][v] * tmp[v * 8 + x]).sum::<f32>() / 2.0;
+            out[(y0 + y) * stride + x0 + x] = (value + 128.0).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+fn to_image(
+    frame: &Frame,
+    adobe_transform: Option<u8>
+) -> Result<Image, String> {
+    if frame.components.iter().any(|c| c.pixels.is_empty()) {
+        return Err("Missing JPEG scan data".to_string());
+    }
+
+    let mut img = Image::new(frame.width as u32, frame.height as u32);
+    
+    // nearest neighbour upsampling of subsampled components
+    let sample = |c: &Component, x: usize, y: usize| -> f32 {
+        let sx = x * c.h / frame.h_max;
+        let sy = y * c.v / frame.v_max;
+        c.pixels[sy * c.width + sx] as f32
+    };
+
+    for y in 0..frame.height {
+        for x in 0..frame.width {
+            let rgb = match frame.components.as_slice() {
+                [gray] => {
+                    let g = sample(gray, x, y);
+                    [g, g, g]
+                },
+                [a, b, c] if adobe_transform == Some(0) => {
+                    [sample(a, x, y), sample(b, x, y), sample(c, x, y)]
+                },
+                [luma, cb, cr] => {
+                    let l = sample(luma, x, y);
+                    let cb = sample(cb, x, y) - 128.0;
+                    let cr = sample(cr, x, y) - 128.0;
+                    [
+                        l + 1.402 * cr,
+                        l - 0.344136 * cb - 0.714136 * cr,
+                        l + 1.772 * cb,
+                    ]
+                },
+                _ => {
+                    return Err(format!("Unsupported JPEG with {} components", frame.components.len()));
+                },
+            };
+            let [r, g, b] = rgb.map(|c| c.round().clamp(0.0, 255.0) as u8);
+            img.set_pixel(x as u32, y as u32, [r, g, b, 255]);
+        }
+    }
+
+    Ok(img)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a baseline grayscale jpeg where every block only has a dc difference of dc_bits.
+    // both huffman tables hold a single one bit code, for the dc size and the end of block
+    fn flat_jpeg(
+        width: u16,
+        height: u16,
+        quant: u16,
+        dc_size: u8,
+        dc_bits: u32
+    ) -> Vec<u8> {
+        let mut out = vec![0xff, 0xd8];
+        if quant > 255 {
+            out.extend_from_slice(&[0xff, 0xdb, 0, 131, 0x10]);
+            for _ in 0..64 {
+                out.extend_from_slice(&quant.to_be_bytes());
+            }
+        }
+        else {
+            out.extend_from_slice(&[0xff, 0xdb, 0, 67, 0x00]);
+            out.extend_from_slice(&[quant as u8; 64]);
+        }
+
+        out.extend_from_slice(&[0xff, 0xc0, 0, 11, 8]);
+        out.extend_from_slice(&height.to_be_bytes());
+        out.extend_from_slice(&width.to_be_bytes());
+        out.extend_from_slice(&[1, 1, 0x11, 0]);
+
+        for (class, symbol) in [(0x00, dc_size), (0x10, 0x00)] {
+            out.extend_from_slice(&[0xff, 0xc4, 0, 20, class, 1]);
+            out.extend_from_slice(&[0; 15]);
+            out.push(symbol);
+        }
+        out.extend_from_slice(&[0xff, 0xda, 0, 8, 1, 1, 0x00, 0, 63, 0]);
+
+        let blocks = (width as usize).div_ceil(8) * (height as usize).div_ceil(8);
+        let mut bits = vec![];
+        for _ in 0..blocks {
+            bits.push(0);
+            bits.extend((0..dc_size as u32).rev().map(|i| (dc_bits >> i) & 1));
+            bits.push(0);
+        }
+        // padded with ones, with a zero stuffed after every 0xff
+        bits.resize(bits.len().div_ceil(8) * 8, 1);
+        for byte in bits.chunks_exact(8) {
+            let byte = byte.iter().fold(0u8, |acc, b| (acc << 1) | *b as u8);
+            out.push(byte);
+            if byte == 0xff {
+                out.push(0);
+            }
+        }
+
+        out.extend_from_slice(&[0xff, 0xd9]);
+        out
+    }
+
+    #[test]
+    fn decodes_baseline_blocks() {
+        let bytes = flat_jpeg(16, 8, 1, 4, 15);
+        assert!(is_jpeg(&bytes));
+        let img = decode_jpeg(&bytes).unwrap();
+        assert_eq!((img.width, img.height), (16, 8));
+        // dc predictions add up, a dc of d shifts the whole block by d / 8
+        assert_eq!(img.get_pixel(0, 0), [130, 130, 130, 255]);
+        assert_eq!(img.get_pixel(7, 7), [130, 130, 130, 255]);
+        assert_eq!(img.get_pixel(8, 0), [132, 132, 132, 255]);
+        assert_eq!(img.get_pixel(15, 7), [132, 132, 132, 255]);
+    }
+
+    #[test]
+    fn large_coefficients_saturate() {
+        // 16-bit quantization and the largest dc differences
+        let img = decode_jpeg(&flat_jpeg(24, 8, 65535, 16, 0xffff)).unwrap();
+        assert_eq!(img.get_pixel(23, 7), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = flat_jpeg(16, 16, 1, 4, 15);
+        for len in 0..bytes.len() {
+            assert!(decode_jpeg(&bytes[..len]).is_err(), "prefix of {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_files() {
+        // frame sizes far beyond the scan data don't allocate
+        let mut huge = flat_jpeg(16, 8, 1, 4, 15);
+        huge[76..80].copy_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(decode_jpeg(&huge), Err("Truncated JPEG scan data".to_string()));
+
+        // no code starts with a one bit
+        let mut code = flat_jpeg(16, 8, 1, 4, 15);
+        let data = code.len() - 4;
+        code[data] = 0xc0;
+        assert_eq!(decode_jpeg(&code), Err("Invalid JPEG huffman code".to_string()));
+
+        let mut progressive = flat_jpeg(16, 8, 1, 4, 15);
+        progressive[72] = 0xc2;
+        assert_eq!(decode_jpeg(&progressive), Err("Only baseline and sequential JPEGs are supported".to_string()));
+
+        let mut precision = flat_jpeg(16, 8, 1, 4, 15);
+        precision[75] = 12;
+        assert_eq!(decode_jpeg(&precision), Err("Only 8-bit JPEGs are supported".to_string()));
+
+        let mut missing_scan = flat_jpeg(16, 8, 1, 4, 15);
+        missing_scan.truncate(missing_scan.len() - 2 - 2 - 10);
+        missing_scan.extend_from_slice(&[0xff, 0xd9]);
+        assert_eq!(decode_jpeg(&missing_scan), Err("Missing JPEG scan data".to_string()));
+    }
+}
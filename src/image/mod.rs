@@ -0,0 +1,12 @@
+pub mod image;
+pub mod zlib;
+pub mod png;
+pub mod jpeg;
+pub mod tga;
+pub mod ppm;
+
+pub use image::*;
+pub use png::*;
+pub use jpeg::*;
+pub use tga::*;
+pub use ppm::*;
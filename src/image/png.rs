@@ -0,0 +1,447 @@
+use super::{Image, zlib};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+pub fn crc32(
+    data: &[u8]
+) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb88320 ^ (crc >> 1)
+            } 
+            else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn write_chunk(
+    out: &mut Vec<u8>,
+    ty: &[u8; 4],
+    data: &[u8]
+) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    
+    let start = out.len();
+    out.extend_from_slice(ty);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// 8-bit RGBA, no filtering, stored (uncompressed) zlib stream.
+// fails when the data doesn't match the size
+pub fn encode_png(
+    img: &Image
+) -> Result<Vec<u8>, String> {
+    let expected = Image::get_data_size(img.width, img.height)?;
+    if img.data.len() != expected {
+        return Err(format!(
+            "Expected {} bytes for a {}x{} RGBA image, got {}", 
+            expected, img.width, img.height, img.data.len()
+        ));
+    }
+
+    let row = img.width as usize * 4;
+    let mut raw = Vec::with_capacity((row + 1) * img.height as usize);
+    for y in 0..img.height as usize {
+        raw.push(0);
+        raw.extend_from_slice(&img.data[y * row..(y + 1) * row]);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&img.width.to_be_bytes());
+    header.extend_from_slice(&img.height.to_be_bytes());
+    // bit depth, color type (RGBA), compression, filter, interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut out = SIGNATURE.to_vec();
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &zlib::compress_stored(&raw));
+    write_chunk(&mut out, b"IEND", &[]);
+    
+    Ok(out)
+}
+
+pub fn is_png(
+    bytes: &[u8]
+) -> bool {
+    bytes.starts_with(&SIGNATURE)
+}
+
+struct Header {
+    width: u32,
+    height: u32,
+    depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn channels(
+        &self
+    ) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            // gray and palette indices
+            _ => 1,
+        }
+    }
+
+    // bytes per row of a pass, without the filter byte
+    fn stride(
+        &self,
+        width: u32
+    ) -> usize {
+        (width as usize * self.channels() * self.depth as usize).div_ceil(8)
+    }
+}
+
+// every color type, bit depth and interlace method; 16-bit samples keep their high byte
+pub fn decode_png(
+    bytes: &[u8]
+) -> Result<Image, String> {
+    if !is_png(bytes) {
+        return Err("Not a PNG file".to_string());
+    }
+
+    let mut header = None;
+    let mut palette: Vec<[u8; 4]> = vec![];
+    let mut transparent: Option<Vec<u16>> = None;
+    let mut idat = vec![];
+
+    let mut pos = SIGNATURE.len();
+    while pos + 12 <= bytes.len() {
+        let len = u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let ty = &bytes[pos + 4..pos + 8];
+        let data = bytes.get(pos + 8..pos + 8 + len)
+            .ok_or("Truncated PNG chunk".to_string())?;
+        pos += 12 + len;
+
+        match ty {
+            b"IHDR" => {
+                if len < 13 {
+                    return Err("Invalid PNG header".to_string());
+                }
+                let h = Header {
+                    width: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+                    height: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+                    depth: data[8],
+                    color_type: data[9],
+                    interlaced: data[12] == 1,
+                };
+                let valid = match h.color_type {
+                    0 => matches!(h.depth, 1 | 2 | 4 | 8 | 16),
+                    3 => matches!(h.depth, 1 | 2 | 4 | 8),
+                    2 | 4 | 6 => matches!(h.depth, 8 | 16),
+                    _ => false,
+                };
+                if !valid {
+                    return Err(format!(
+                        "Invalid PNG color type {} with bit depth {}", h.color_type, h.depth
+                    ));
+                }
+                header = Some(h);
+            },
+            b"PLTE" => {
+                palette = data.chunks_exact(3)
+                    .map(|c| [c[0], c[1], c[2], 255])
+                    .collect();
+            },
+            b"tRNS" => {
+                match header.as_ref().map(|h| h.color_type) {
+                    Some(3) => {
+                        for (entry, alpha) in palette.iter_mut().zip(data) {
+                            entry[3] = *alpha;
+                        }
+                    },
+                    _ => {
+                        transparent = Some(data.chunks_exact(2)
+                            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                            .collect());
+                    },
+                }
+            },
+            b"IDAT" => {
+                idat.extend_from_slice(data);
+            },
+            b"IEND" => {
+                break;
+            },
+            _ => {
+            },
+        }
+    }
+
+    let header = header.ok_or("Missing PNG header".to_string())?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err("Missing PNG palette".to_string());
+    }
+
+    // Adam7 passes as (x, y, dx, dy); a single pass otherwise
+    let passes: &[(u32, u32, u32, u32)] = if header.interlaced {
+        &[(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)]
+    }
+    else {
+        &[(0, 0, 1, 1)]
+    };
+    // the size of a pass in pixels
+    let pass_size = |x0: u32, y0: u32, dx: u32, dy: u32| (
+        ((header.width as u64 + (dx - 1 - x0) as u64) / dx as u64) as u32,
+        ((header.height as u64 + (dy - 1 - y0) as u64) / dy as u64) as u32
+    );
+
+    // all the image data must be there before the pixels are allocated
+    let mut total = 0usize;
+    for &(x0, y0, dx, dy) in passes {
+        let (w, h) = pass_size(x0, y0, dx, dy);
+        if w > 0 && h > 0 {
+            total = (header.stride(w) + 1).checked_mul(h as usize)
+                .and_then(|size| total.checked_add(size))
+                .ok_or("PNG image too large".to_string())?;
+        }
+    }
+    let raw = zlib::decompress(&idat, total)?;
+    if raw.len() < total {
+        return Err("Truncated PNG image data".to_string());
+    }
+
+    Image::get_data_size(header.width, header.height)?;
+    let mut img = Image::new(header.width, header.height);
+
+    let mut offset = 0;
+    for &(x0, y0, dx, dy) in passes {
+        let (w, h) = pass_size(x0, y0, dx, dy);
+        if w == 0 || h == 0 {
+            continue;
+        }
+
+        let stride = header.stride(w);
+        let size = (stride + 1) * h as usize;
+        let data = raw.get(offset..offset + size)
+            .ok_or("Truncated PNG image data".to_string())?;
+        offset += size;
+
+        let rows = unfilter(data, stride, h as usize, header.channels() * header.depth as usize)?;
+
+        for (y, row) in rows.chunks_exact(stride).enumerate() {
+            for x in 0..w as usize {
+                let rgba = texel(&header, row, x, &palette, transparent.as_deref());
+                img.set_pixel(x0 + x as u32 * dx, y0 + y as u32 * dy, rgba);
+            }
+        }
+    }
+
+    Ok(img)
+}
+
+fn unfilter(
+    data: &[u8],
+    stride: usize,
+    height: usize,
+    bits_per_pixel: usize
+) -> Result<Vec<u8>, String> {
+    let bpp = bits_per_pixel.div_ceil(8);
+    let mut out = vec![0u8; stride * height];
+
+    for y in 0..height {
+        let filter = data[y * (stride + 1)];
+        let src = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (prev, cur) = out.split_at_mut(y * stride);
+        let prev = if y > 0 {&prev[(y - 1) * stride..]} else {&[][..]};
+        let cur = &mut cur[..stride];
+
+        for x in 0..stride {
+            let a = if x >= bpp {cur[x - bpp]} else {0};
+            let b = prev.get(x).copied().unwrap_or(0);
+            let c = if x >= bpp {prev.get(x - bpp).copied().unwrap_or(0)} else {0};
+            
+            cur[x] = src[x].wrapping_add(match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(format!("Invalid PNG filter {}", filter)),
+            });
+        }
+    }
+
+    Ok(out)
+}
+
+fn paeth(
+    a: u8,
+    b: u8,
+    c: u8
+) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } 
+    else if pb <= pc {
+        b
+    } 
+    else {
+        c
+    }
+}
+
+fn texel(
+    header: &Header,
+    row: &[u8],
+    x: usize,
+    palette: &[[u8; 4]],
+    transparent: Option<&[u16]>
+) -> [u8; 4] {
+    let depth = header.depth as usize;
+    let channels = header.channels();
+    
+    // raw sample values, at the image's bit depth
+    let sample = |i: usize| -> u16 {
+        let bit = (x * channels + i) * depth;
+        match depth {
+            16 => u16::from_be_bytes([row[bit / 8], row[bit / 8 + 1]]),
+            8 => row[bit / 8] as u16,
+            _ => ((row[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1) as u8) as u16,
+        }
+    };
+    // to 8 bits
+    let scale = |v: u16| -> u8 {
+        match depth {
+            16 => (v >> 8) as u8,
+            8 => v as u8,
+            _ => (v * 255 / ((1 << depth) - 1)) as u8,
+        }
+    };
+
+    match header.color_type {
+        0 => {
+            let v = sample(0);
+            let alpha = if transparent.is_some_and(|t| t.first() == Some(&v)) {0} else {255};
+            let g = scale(v);
+            [g, g, g, alpha]
+        },
+        2 => {
+            let (r, g, b) = (sample(0), sample(1), sample(2));
+            let alpha = if transparent.is_some_and(|t| t == [r, g, b]) {0} else {255};
+            [scale(r), scale(g), scale(b), alpha]
+        },
+        3 => {
+            palette.get(sample(0) as usize).copied().unwrap_or([0, 0, 0, 255])
+        },
+        4 => {
+            let g = scale(sample(0));
+            [g, g, g, scale(sample(1))]
+        },
+        _ => {
+            [scale(sample(0)), scale(sample(1)), scale(sample(2)), scale(sample(3))]
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a png with the given IHDR fields and already filtered rows
+    fn png(
+        width: u32,
+        height: u32,
+        depth: u8,
+        color_type: u8,
+        raw: &[u8]
+    ) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[depth, color_type, 0, 0, 0]);
+
+        let mut out = SIGNATURE.to_vec();
+        write_chunk(&mut out, b"IHDR", &header);
+        write_chunk(&mut out, b"IDAT", &zlib::compress_stored(raw));
+        write_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    fn gradient(
+        width: u32,
+        height: u32
+    ) -> Image {
+        let data = (0..width * height * 4).map(|i| (i * 37 % 256) as u8).collect();
+        Image::from_rgba(width, height, data).unwrap()
+    }
+
+    #[test]
+    fn encoded_images_decode_to_the_same_pixels() {
+        let img = gradient(5, 3);
+        let bytes = encode_png(&img).unwrap();
+        assert!(is_png(&bytes));
+        assert_eq!(decode_png(&bytes).unwrap(), img);
+        assert_eq!(Image::load_from_bytes(&bytes).unwrap(), img);
+    }
+
+    #[test]
+    fn encode_fails_on_mismatched_data() {
+        let img = Image {
+            width: 2,
+            height: 2,
+            data: vec![0; 15],
+        };
+        assert!(encode_png(&img).is_err());
+        assert!(img.to_png().is_err());
+    }
+
+    #[test]
+    fn undoes_the_row_filters() {
+        // sub filter: each byte adds the one a texel to its left
+        let bytes = png(2, 1, 8, 2, &[1, 10, 20, 30, 5, 5, 5]);
+        let img = decode_png(&bytes).unwrap();
+        assert_eq!(img.get_pixel(0, 0), [10, 20, 30, 255]);
+        assert_eq!(img.get_pixel(1, 0), [15, 25, 35, 255]);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = encode_png(&gradient(4, 4)).unwrap();
+        // everything up to the end of the IDAT data is needed, crcs aren't checked
+        for len in 0..bytes.len() - 12 - 4 {
+            assert!(decode_png(&bytes[..len]).is_err(), "prefix of {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_files() {
+        let mut bytes = encode_png(&gradient(4, 4)).unwrap();
+        // a pixel byte in the stored IDAT stream no longer matches the checksum
+        let last = bytes.len() - 12 - 4 - 4 - 1;
+        bytes[last] ^= 1;
+        assert_eq!(decode_png(&bytes), Err("Bad zlib checksum".to_string()));
+
+        assert_eq!(decode_png(&png(1, 1, 8, 6, &[5, 0, 0, 0, 0])), Err("Invalid PNG filter 5".to_string()));
+        assert!(decode_png(&png(1, 1, 8, 5, &[0, 0, 0])).is_err());
+        assert!(decode_png(&png(1, 1, 16, 3, &[0, 0, 0])).is_err());
+        assert_eq!(decode_png(&png(1, 1, 8, 3, &[0, 0])), Err("Missing PNG palette".to_string()));
+        assert_eq!(decode_png(&png(2, 2, 8, 6, &[0; 8])), Err("Truncated PNG image data".to_string()));
+    }
+
+    #[test]
+    fn image_data_is_capped_to_the_header_size() {
+        // 4x4 pixels behind a 1x1 header
+        let bytes = encode_png(&gradient(4, 4)).unwrap();
+        let mut small = bytes.clone();
+        small[16..24].copy_from_slice(&[0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(decode_png(&small), Err("Inflated data larger than 5 bytes".to_string()));
+    }
+}
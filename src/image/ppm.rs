@@ -0,0 +1,206 @@
+use super::Image;
+
+pub fn is_ppm(
+    bytes: &[u8]
+) -> bool {
+    bytes.len() >= 2 && bytes[0] == b'P' && (b'1'..=b'6').contains(&bytes[1])
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    // skips whitespace and comments
+    fn skip(
+        &mut self
+    ) {
+        while let Some(&b) = self.bytes.get(self.pos) {
+            if b == b'#' {
+                while self.bytes.get(self.pos).is_some_and(|b| *b != b'\n') {
+                    self.pos += 1;
+                }
+            }
+            else if b.is_ascii_whitespace() {
+                self.pos += 1;
+            }
+            else {
+                break;
+            }
+        }
+    }
+
+    fn number(
+        &mut self
+    ) -> Result<u32, String> {
+        self.skip();
+        let start = self.pos;
+        while self.bytes.get(self.pos).is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or("Invalid PNM number".to_string())
+    }
+
+    // plain bitmaps may pack their digits without separators
+    fn bit(
+        &mut self
+    ) -> Result<u32, String> {
+        self.skip();
+        match self.bytes.get(self.pos) {
+            Some(b @ (b'0' | b'1')) => {
+                self.pos += 1;
+                Ok((b - b'0') as u32)
+            },
+            _ => Err("Invalid PBM data".to_string()),
+        }
+    }
+}
+
+// netpbm bitmaps, graymaps and pixmaps, plain (P1-P3) or raw (P4-P6)
+pub fn decode_ppm(
+    bytes: &[u8]
+) -> Result<Image, String> {
+    if !is_ppm(bytes) {
+        return Err("Not a PNM file".to_string());
+    }
+
+    let kind = bytes[1] - b'0';
+    let mut reader = Reader {
+        bytes,
+        pos: 2,
+    };
+    let width = reader.number()?;
+    let height = reader.number()?;
+    let max = if kind == 1 || kind == 4 {1} else {reader.number()?};
+    if width == 0 || height == 0 || max == 0 || max > 65535 {
+        return Err("Invalid PNM header".to_string());
+    }
+
+    let channels = if kind == 3 || kind == 6 {3} else {1};
+    let count = (width as usize).checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(channels))
+        .ok_or("PNM image too large".to_string())?;
+
+    // every sample takes at least a byte, but in raw bitmaps that hold 8
+    let min_len = match kind {
+        4 => (width as usize).div_ceil(8).saturating_mul(height as usize),
+        5 | 6 if max > 255 => count.saturating_mul(2),
+        _ => count,
+    };
+    if bytes.len().saturating_sub(reader.pos) < min_len {
+        return Err("Truncated PNM data".to_string());
+    }
+    let mut samples = Vec::with_capacity(count);
+
+    match kind {
+        1 => {
+            for _ in 0..count {
+                samples.push(reader.bit()?);
+            }
+        },
+        2 | 3 => {
+            for _ in 0..count {
+                samples.push(reader.number()?);
+            }
+        },
+        _ => {
+            // a single whitespace separates the header from the data
+            let data = bytes.get(reader.pos + 1..).ok_or("Truncated PNM data".to_string())?;
+            if kind == 4 {
+                let stride = (width as usize).div_ceil(8);
+                for y in 0..height as usize {
+                    for x in 0..width as usize {
+                        let byte = *data.get(y * stride + x / 8).ok_or("Truncated PBM data".to_string())?;
+                        samples.push(((byte >> (7 - x % 8)) & 1) as u32);
+                    }
+                }
+            }
+            else if max < 256 {
+                samples.extend(data.iter().take(count).map(|b| *b as u32));
+            }
+            else {
+                samples.extend(data.chunks_exact(2).take(count).map(|b| u16::from_be_bytes([b[0], b[1]]) as u32));
+            }
+            if samples.len() < count {
+                return Err("Truncated PNM data".to_string());
+            }
+        },
+    }
+
+    let mut img = Image::new(width, height);
+    for (i, texel) in samples.chunks_exact(channels).enumerate() {
+        // bitmaps use 1 for black
+        let scale = |v: u32| if kind == 1 || kind == 4 {
+            if v == 0 {255} else {0}
+        }
+        else {
+            (v.min(max) * 255 / max) as u8
+        };
+        let color = if channels == 3 {
+            [scale(texel[0]), scale(texel[1]), scale(texel[2]), 255]
+        }
+        else {
+            let g = scale(texel[0]);
+            [g, g, g, 255]
+        };
+        img.data[i * 4..i * 4 + 4].copy_from_slice(&color);
+    }
+
+    Ok(img)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_plain_and_raw_pixmaps() {
+        let plain = b"P3\n# a comment\n2 1\n255\n1 2 3  4 5 6\n";
+        let mut raw = b"P6 2 1 255\n".to_vec();
+        raw.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        for bytes in [&plain[..], &raw[..]] {
+            assert!(is_ppm(bytes));
+            let img = decode_ppm(bytes).unwrap();
+            assert_eq!(img.data, [1, 2, 3, 255, 4, 5, 6, 255]);
+        }
+    }
+
+    #[test]
+    fn decodes_bitmaps_and_scales_graymaps() {
+        // 1 is black, rows of raw bitmaps are padded to a byte
+        let plain = decode_ppm(b"P1 3 1 101").unwrap();
+        let raw = decode_ppm(&[b'P', b'4', b' ', b'3', b' ', b'1', b'\n', 0b1010_0000]).unwrap();
+        for img in [plain, raw] {
+            assert_eq!(img.data, [0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255]);
+        }
+
+        let gray = decode_ppm(b"P2 2 1 4 0 2").unwrap();
+        assert_eq!(gray.data, [0, 0, 0, 255, 127, 127, 127, 255]);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let mut raw = b"P6 2 2 65535\n".to_vec();
+        raw.extend_from_slice(&[9; 24]);
+        let plain = b"P3 2 1 255 1 2 3 4 5 6".to_vec();
+        for bytes in [raw, plain] {
+            assert!(decode_ppm(&bytes).is_ok());
+            for len in 0..bytes.len() {
+                assert!(decode_ppm(&bytes[..len]).is_err(), "prefix of {} bytes", len);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_headers() {
+        assert_eq!(decode_ppm(b"P7 1 1 255\n0"), Err("Not a PNM file".to_string()));
+        assert_eq!(decode_ppm(b"P6 0 1 255\n"), Err("Invalid PNM header".to_string()));
+        assert_eq!(decode_ppm(b"P5 1 1 0\n\0"), Err("Invalid PNM header".to_string()));
+        assert_eq!(decode_ppm(b"P5 x 1 255\n\0"), Err("Invalid PNM number".to_string()));
+        assert_eq!(decode_ppm(b"P1 2 1 0 2"), Err("Invalid PBM data".to_string()));
+    }
+}
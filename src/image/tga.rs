@@ -0,0 +1,229 @@
+use super::Image;
+
+// tga has no magic number, so this only checks the header is plausible
+pub fn is_tga(
+    bytes: &[u8]
+) -> bool {
+    bytes.len() >= 18 && 
+        matches!(bytes[1], 0 | 1) && 
+        matches!(bytes[2], 1 | 2 | 3 | 9 | 10 | 11) && 
+        matches!(bytes[16], 8 | 15 | 16 | 24 | 32)
+}
+
+// color mapped, true color and grayscale, raw or run length encoded
+pub fn decode_tga(
+    bytes: &[u8]
+) -> Result<Image, String> {
+    if bytes.len() < 18 {
+        return Err("Truncated TGA header".to_string());
+    }
+
+    let id_len = bytes[0] as usize;
+    let has_map = bytes[1] == 1;
+    let kind = bytes[2];
+    let map_first = u16::from_le_bytes([bytes[3], bytes[4]]) as usize;
+    let map_len = u16::from_le_bytes([bytes[5], bytes[6]]) as usize;
+    let map_depth = bytes[7] as usize;
+    let width = u16::from_le_bytes([bytes[12], bytes[13]]) as usize;
+    let height = u16::from_le_bytes([bytes[14], bytes[15]]) as usize;
+    let depth = bytes[16] as usize;
+    let descriptor = bytes[17];
+
+    let (mapped, gray, rle) = match kind {
+        1 => (true, false, false),
+        2 => (false, false, false),
+        3 => (false, true, false),
+        9 => (true, false, true),
+        10 => (false, false, true),
+        11 => (false, true, true),
+        _ => return Err(format!("Unsupported TGA image type {}", kind)),
+    };
+    if mapped && !has_map {
+        return Err("Color mapped TGA without a color map".to_string());
+    }
+    if width == 0 || height == 0 {
+        return Err("Invalid TGA size".to_string());
+    }
+    if !matches!(depth, 8 | 15 | 16 | 24 | 32) {
+        return Err(format!("Unsupported TGA pixel depth {}", depth));
+    }
+    if has_map && !matches!(map_depth, 15 | 16 | 24 | 32) {
+        return Err(format!("Unsupported TGA color map depth {}", map_depth));
+    }
+
+    let mut pos = 18 + id_len;
+
+    let mut palette = vec![];
+    if has_map {
+        let entry = map_depth.div_ceil(8);
+        let data = bytes.get(pos..pos + map_len * entry)
+            .ok_or("Truncated TGA color map".to_string())?;
+        for texel in data.chunks_exact(entry) {
+            palette.push(read_color(texel, map_depth, false)?);
+        }
+        pos += map_len * entry;
+    }
+
+    let size = depth.div_ceil(8);
+    let count = width * height;
+    let raw_len = count.checked_mul(size)
+        .ok_or("TGA image too large".to_string())?;
+
+    // a run length packet holds at most 128 texels
+    let min_len = if rle {count.div_ceil(128) * (1 + size)} else {raw_len};
+    if bytes.len().saturating_sub(pos) < min_len {
+        return Err("Truncated TGA data".to_string());
+    }
+    let mut raw = Vec::with_capacity(raw_len);
+    if rle {
+        while raw.len() < raw_len {
+            let packet = *bytes.get(pos).ok_or("Truncated TGA data".to_string())? as usize;
+            let n = (packet & 0x7f) + 1;
+            pos += 1;
+            if packet & 0x80 != 0 {
+                let texel = bytes.get(pos..pos + size).ok_or("Truncated TGA data".to_string())?;
+                for _ in 0..n {
+                    raw.extend_from_slice(texel);
+                }
+                pos += size;
+            }
+            else {
+                raw.extend_from_slice(bytes.get(pos..pos + n * size).ok_or("Truncated TGA data".to_string())?);
+                pos += n * size;
+            }
+        }
+        raw.truncate(raw_len);
+    }
+    else {
+        raw.extend_from_slice(bytes.get(pos..pos + raw_len).ok_or("Truncated TGA data".to_string())?);
+    }
+
+    // bit 4 flips left-right, rows are stored bottom-up unless bit 5 is set
+    let right_to_left = descriptor & 0x10 != 0;
+    let top_down = descriptor & 0x20 != 0;
+
+    let mut img = Image::new(width as u32, height as u32);
+    for (i, texel) in raw.chunks_exact(size).enumerate() {
+        let color = if mapped {
+            let index = texel.iter().rev().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+            *index.checked_sub(map_first)
+                .and_then(|i| palette.get(i))
+                .ok_or("TGA color index out of range".to_string())?
+        }
+        else {
+            read_color(texel, depth, gray)?
+        };
+
+        let (x, y) = (i % width, i / width);
+        let x = if right_to_left {width - 1 - x} else {x};
+        let y = if top_down {y} else {height - 1 - y};
+        img.set_pixel(x as u32, y as u32, color);
+    }
+
+    Ok(img)
+}
+
+fn read_color(
+    texel: &[u8],
+    depth: usize,
+    gray: bool
+) -> Result<[u8; 4], String> {
+    match (depth, gray) {
+        (8, true) => Ok([texel[0], texel[0], texel[0], 255]),
+        (16, true) => Ok([texel[0], texel[0], texel[0], texel[1]]),
+        (15 | 16, false) => {
+            let v = u16::from_le_bytes([texel[0], texel[1]]);
+            let scale = |c: u16| ((c & 31) * 255 / 31) as u8;
+            // the attribute bit is often left unset, so treat 16-bit as opaque
+            Ok([scale(v >> 10), scale(v >> 5), scale(v), 255])
+        },
+        (24, false) => Ok([texel[2], texel[1], texel[0], 255]),
+        (32, false) => Ok([texel[2], texel[1], texel[0], texel[3]]),
+        _ => Err(format!("Unsupported TGA pixel depth {}", depth)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(
+        kind: u8,
+        depth: u8,
+        map_len: u16,
+        map_depth: u8,
+        width: u16,
+        height: u16,
+        descriptor: u8
+    ) -> Vec<u8> {
+        let mut out = vec![0, (map_len > 0) as u8, kind, 0, 0];
+        out.extend_from_slice(&map_len.to_le_bytes());
+        out.push(map_depth);
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&width.to_le_bytes());
+        out.extend_from_slice(&height.to_le_bytes());
+        out.extend_from_slice(&[depth, descriptor]);
+        out
+    }
+
+    #[test]
+    fn decodes_true_color_rows_bottom_up() {
+        let mut bytes = header(2, 24, 0, 0, 2, 2, 0);
+        // BGR, the bottom row first
+        bytes.extend_from_slice(&[3, 2, 1, 6, 5, 4, 9, 8, 7, 12, 11, 10]);
+        assert!(is_tga(&bytes));
+        let img = decode_tga(&bytes).unwrap();
+        assert_eq!(img.get_pixel(0, 1), [1, 2, 3, 255]);
+        assert_eq!(img.get_pixel(1, 1), [4, 5, 6, 255]);
+        assert_eq!(img.get_pixel(0, 0), [7, 8, 9, 255]);
+        assert_eq!(img.get_pixel(1, 0), [10, 11, 12, 255]);
+    }
+
+    #[test]
+    fn decodes_run_length_color_mapped_images() {
+        let mut bytes = header(9, 8, 2, 32, 4, 1, 0x20);
+        bytes.extend_from_slice(&[30, 20, 10, 255, 60, 50, 40, 128]);
+        // a run of two texels, then two raw ones
+        bytes.extend_from_slice(&[0x81, 1, 0x01, 0, 1]);
+        let img = decode_tga(&bytes).unwrap();
+        assert_eq!(img.data, [
+            40, 50, 60, 128, 
+            40, 50, 60, 128, 
+            10, 20, 30, 255, 
+            40, 50, 60, 128
+        ]);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let mut raw = header(2, 32, 0, 0, 3, 2, 0);
+        raw.extend_from_slice(&[7; 24]);
+        let mut rle = header(10, 24, 0, 0, 3, 2, 0);
+        rle.extend_from_slice(&[0x82, 1, 2, 3, 0x02, 4, 5, 6, 7, 8, 9, 1, 2, 3]);
+        for bytes in [raw, rle] {
+            assert!(decode_tga(&bytes).is_ok());
+            for len in 0..bytes.len() {
+                assert!(decode_tga(&bytes[..len]).is_err(), "prefix of {} bytes", len);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_headers_and_indices() {
+        let mut depth = header(2, 0, 0, 0, 1, 1, 0);
+        depth.extend_from_slice(&[0; 4]);
+        assert_eq!(decode_tga(&depth), Err("Unsupported TGA pixel depth 0".to_string()));
+
+        let mut map_depth = header(1, 8, 1, 0, 1, 1, 0);
+        map_depth.extend_from_slice(&[0; 4]);
+        assert_eq!(decode_tga(&map_depth), Err("Unsupported TGA color map depth 0".to_string()));
+
+        let mut index = header(1, 8, 1, 24, 1, 1, 0);
+        index.extend_from_slice(&[0, 0, 0, 5]);
+        assert_eq!(decode_tga(&index), Err("TGA color index out of range".to_string()));
+
+        let mut kind = header(4, 8, 0, 0, 1, 1, 0);
+        kind.push(0);
+        assert_eq!(decode_tga(&kind), Err("Unsupported TGA image type 4".to_string()));
+    }
+}
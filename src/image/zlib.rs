@@ -0,0 +1,421 @@
+const MAX_STORED_BLOCK: usize = 65535;
+
+pub fn adler32(
+    data: &[u8]
+) -> u32 {
+    let mut a = 1u32;
+    let mut b = 0u32;
+
+    // 5552 is the largest n such that sums don't overflow before the modulo
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+
+    (b << 16) | a
+}
+
+// zlib stream made of uncompressed (stored) deflate blocks
+pub fn compress_stored(
+    data: &[u8]
+) -> Vec<u8> {
+    let blocks = data.len().div_ceil(MAX_STORED_BLOCK).max(1);
+    let mut out = Vec::with_capacity(2 + data.len() + blocks * 5 + 4);
+
+    // CM = 8 (deflate), CINFO = 7 (32K window), FLEVEL = 0, FCHECK makes it a multiple of 31
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        out.push(if last {0x01} else {0x00});
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+
+    out
+}
+
+// inverse of any zlib stream, checking the header and the adler32 trailer.
+// fails once the output grows past max_len
+pub fn decompress(
+    data: &[u8],
+    max_len: usize
+) -> Result<Vec<u8>, String> {
+    if data.len() < 6 {
+        return Err("Truncated zlib stream".to_string());
+    }
+
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0f != 8 || !((cmf as u16) << 8 | flg as u16).is_multiple_of(31) {
+        return Err("Invalid zlib header".to_string());
+    }
+    if flg & 0x20 != 0 {
+        return Err("Preset zlib dictionaries are not supported".to_string());
+    }
+
+    let (out, used) = inflate(&data[2..], max_len)?;
+    
+    let trailer = data.get(2 + used..2 + used + 4)
+        .ok_or("Missing zlib checksum".to_string())?;
+    if u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) != adler32(&out) {
+        return Err("Bad zlib checksum".to_string());
+    }
+
+    Ok(out)
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13
+];
+// order in which the code length code lengths are stored
+const CLEN_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+    bits: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(
+        data: &'a [u8]
+    ) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bit: 0,
+            bits: 0,
+        }
+    }
+
+    // least significant bit first
+    fn read(
+        &mut self,
+        count: u32
+    ) -> Result<u32, String> {
+        while self.bits < count {
+            let byte = *self.data.get(self.pos)
+                .ok_or("Truncated deflate stream".to_string())?;
+            self.pos += 1;
+            self.bit |= (byte as u32) << self.bits;
+            self.bits += 8;
+        }
+
+        let value = self.bit & ((1u64 << count) - 1) as u32;
+        self.bit >>= count;
+        self.bits -= count;
+        Ok(value)
+    }
+
+    fn align(
+        &mut self
+    ) {
+        self.bit = 0;
+        self.bits = 0;
+    }
+}
+
+// canonical huffman code, decoded one bit at a time
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(
+        lengths: &[u8]
+    ) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        Self {
+            counts,
+            symbols,
+        }
+    }
+
+    fn decode(
+        &self,
+        reader: &mut BitReader
+    ) -> Result<u16, String> {
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+        for len in 1..16 {
+            code |= reader.read(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+
+        Err("Invalid huffman code".to_string())
+    }
+}
+
+// raw deflate; returns the data and how many input bytes were consumed.
+// fails once the output grows past max_len
+pub fn inflate(
+    data: &[u8],
+    max_len: usize
+) -> Result<(Vec<u8>, usize), String> {
+    let mut reader = BitReader::new(data);
+    let mut out = vec![];
+
+    loop {
+        let last = reader.read(1)? == 1;
+        match reader.read(2)? {
+            0 => {
+                reader.align();
+                let header = data.get(reader.pos..reader.pos + 4)
+                    .ok_or("Truncated stored block".to_string())?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+                if len != !nlen {
+                    return Err("Corrupted stored block length".to_string());
+                }
+                reader.pos += 4;
+                let block = data.get(reader.pos..reader.pos + len as usize)
+                    .ok_or("Truncated stored block".to_string())?;
+                if out.len() + block.len() > max_len {
+                    return Err(too_long(max_len));
+                }
+                out.extend_from_slice(block);
+                reader.pos += len as usize;
+            },
+            1 => {
+                let (lit, dist) = fixed_tables();
+                inflate_block(&mut reader, &mut out, &lit, &dist, max_len)?;
+            },
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &lit, &dist, max_len)?;
+            },
+            _ => {
+                return Err("Invalid deflate block type".to_string());
+            },
+        }
+
+        if last {
+            break;
+        }
+    }
+
+    Ok((out, reader.pos))
+}
+
+fn too_long(
+    max_len: usize
+) -> String {
+    format!("Inflated data larger than {} bytes", max_len)
+}
+
+fn fixed_tables(
+) -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_tables(
+    reader: &mut BitReader
+) -> Result<(Huffman, Huffman), String> {
+    let hlit = reader.read(5)? as usize + 257;
+    let hdist = reader.read(5)? as usize + 1;
+    let hclen = reader.read(4)? as usize + 4;
+
+    let mut clen_lengths = [0u8; 19];
+    for &i in &CLEN_ORDER[..hclen] {
+        clen_lengths[i] = reader.read(3)? as u8;
+    }
+    let clen = Huffman::new(&clen_lengths);
+
+    let mut lengths = vec![0u8; hlit + hdist];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = clen.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let prev = *lengths[..i].last()
+                    .ok_or("Repeat without a previous length".to_string())?;
+                (prev, 3 + reader.read(2)? as usize)
+            },
+            17 => (0, 3 + reader.read(3)? as usize),
+            _ => (0, 11 + reader.read(7)? as usize),
+        };
+        
+        if i + repeat > lengths.len() {
+            return Err("Too many code lengths".to_string());
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+
+    Ok((Huffman::new(&lengths[..hlit]), Huffman::new(&lengths[hlit..])))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+    max_len: usize
+) -> Result<(), String> {
+    loop {
+        let symbol = lit.decode(reader)? as usize;
+        match symbol {
+            0..=255 => {
+                if out.len() >= max_len {
+                    return Err(too_long(max_len));
+                }
+                out.push(symbol as u8);
+            },
+            256 => {
+                return Ok(());
+            },
+            _ => {
+                let i = symbol - 257;
+                if i >= LENGTH_BASE.len() {
+                    return Err("Invalid length symbol".to_string());
+                }
+                let len = LENGTH_BASE[i] as usize + reader.read(LENGTH_EXTRA[i] as u32)? as usize;
+                
+                let d = dist.decode(reader)? as usize;
+                if d >= DIST_BASE.len() {
+                    return Err("Invalid distance symbol".to_string());
+                }
+                let distance = DIST_BASE[d] as usize + reader.read(DIST_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err("Distance too far back".to_string());
+                }
+                if out.len() + len > max_len {
+                    return Err(too_long(max_len));
+                }
+
+                // byte by byte, as the copy can overlap its own output
+                let start = out.len() - distance;
+                for k in 0..len {
+                    out.push(out[start + k]);
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "hello hello hello hello" at level 9, a fixed huffman block
+    const HELLO: [u8; 16] = [
+        0x78, 0xda, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01, 0x68, 0x03, 0x08, 0xb1
+    ];
+    // 5000 zeros at level 9, a dynamic huffman block
+    const ZEROS: [u8; 28] = [
+        0x78, 0xda, 0xed, 0xc1, 0x31, 0x01, 0x00, 0x00, 0x00, 0xc2, 0xa0, 0xf5, 0x4f, 0x6d, 
+        0x0a, 0x3f, 0xa0, 0x00, 0x00, 0x00, 0x00, 0x80, 0xb7, 0x01, 0x13, 0x88, 0x00, 0x01
+    ];
+
+    #[test]
+    fn stored_streams_round_trip() {
+        // empty, one block and several blocks
+        for len in [0, 1000, MAX_STORED_BLOCK * 2 + 10] {
+            let data: Vec<u8> = (0..len).map(|i| (i * 31 % 251) as u8).collect();
+            let stream = compress_stored(&data);
+            assert_eq!(decompress(&stream, len).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn inflates_huffman_blocks() {
+        assert_eq!(decompress(&HELLO, 100).unwrap(), b"hello hello hello hello");
+        assert_eq!(decompress(&ZEROS, 5000).unwrap(), vec![0; 5000]);
+    }
+
+    #[test]
+    fn caps_the_output_size() {
+        assert_eq!(decompress(&ZEROS, 4999), Err(too_long(4999)));
+        assert_eq!(decompress(&HELLO, 10), Err(too_long(10)));
+        assert_eq!(decompress(&compress_stored(&[1; 100]), 99), Err(too_long(99)));
+    }
+
+    #[test]
+    fn rejects_truncated_streams() {
+        for stream in [&HELLO[..], &ZEROS[..], &compress_stored(&[7; 300])[..]] {
+            for len in 0..stream.len() {
+                assert!(decompress(&stream[..len], 10000).is_err(), "prefix of {} bytes", len);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_streams() {
+        let mut header = HELLO;
+        header[1] ^= 1;
+        assert_eq!(decompress(&header, 100), Err("Invalid zlib header".to_string()));
+
+        let mut checksum = HELLO;
+        checksum[15] ^= 1;
+        assert_eq!(decompress(&checksum, 100), Err("Bad zlib checksum".to_string()));
+
+        let mut block_type = HELLO;
+        block_type[2] |= 0x06;
+        assert_eq!(decompress(&block_type, 100), Err("Invalid deflate block type".to_string()));
+
+        let mut stored = compress_stored(&[7; 300]);
+        stored[5] ^= 1;
+        assert_eq!(decompress(&stored, 1000), Err("Corrupted stored block length".to_string()));
+    }
+}
@@ -9,6 +9,11 @@ pub mod core;
 pub mod object;
 pub mod scene;
 pub mod camera;
+pub mod controls;
 pub mod renderer;
 pub mod loader;
 pub mod geometry;
+pub mod image;
+pub mod material;
+pub mod light;
+pub mod texture;
@@ -0,0 +1,65 @@
+use std::{rc::Rc, cell::RefCell};
+use crate::core::{ObjectData, Object3d, Transformable, RGB, new_object_ref};
+use super::{Light, LightData, LightKind};
+
+// lights all objects equally, from every direction
+#[derive(Clone)]
+pub struct AmbientLight {
+    obj: ObjectData,
+    light: LightData,
+}
+
+impl AmbientLight {
+    pub fn new(
+        color: RGB,
+        intensity: f32
+    ) -> Rc<RefCell<Self>> {
+        new_object_ref(Self {
+            obj: ObjectData::new(),
+            light: LightData::new(color, intensity),
+        })
+    }
+}
+
+impl Object3d for AmbientLight {
+    fn get_object(
+        &self
+    ) -> &ObjectData {
+        &self.obj
+    }
+
+    fn get_object_mut(
+        &mut self
+    ) -> &mut ObjectData {
+        &mut self.obj
+    }
+
+    fn as_light(
+        &self
+    ) -> Option<&dyn Light> {
+        Some(self)
+    }
+}
+
+impl Transformable for AmbientLight {
+}
+
+impl Light for AmbientLight {
+    fn get_light(
+        &self
+    ) -> &LightData {
+        &self.light
+    }
+
+    fn get_light_mut(
+        &mut self
+    ) -> &mut LightData {
+        &mut self.light
+    }
+
+    fn get_kind(
+        &self
+    ) -> LightKind<'_> {
+        LightKind::Ambient(self)
+    }
+}
@@ -0,0 +1,73 @@
+use std::{rc::Rc, cell::RefCell};
+use crate::{
+    core::{ObjectData, Object3d, Transformable, RGB, new_object_ref},
+    math::Vector3
+};
+use super::{Light, LightData, LightKind};
+
+// parallel rays shining from the light's world position towards target, from above by default
+#[derive(Clone)]
+pub struct DirectionalLight {
+    obj: ObjectData,
+    light: LightData,
+    pub target: Vector3,
+}
+
+impl DirectionalLight {
+    pub fn new(
+        color: RGB,
+        intensity: f32
+    ) -> Rc<RefCell<Self>> {
+        let mut obj = ObjectData::new();
+        obj.set_position(Vector3::new(0.0, 1.0, 0.0));
+        
+        new_object_ref(Self {
+            obj,
+            light: LightData::new(color, intensity),
+            target: Vector3::zero(),
+        })
+    }
+}
+
+impl Object3d for DirectionalLight {
+    fn get_object(
+        &self
+    ) -> &ObjectData {
+        &self.obj
+    }
+
+    fn get_object_mut(
+        &mut self
+    ) -> &mut ObjectData {
+        &mut self.obj
+    }
+
+    fn as_light(
+        &self
+    ) -> Option<&dyn Light> {
+        Some(self)
+    }
+}
+
+impl Transformable for DirectionalLight {
+}
+
+impl Light for DirectionalLight {
+    fn get_light(
+        &self
+    ) -> &LightData {
+        &self.light
+    }
+
+    fn get_light_mut(
+        &mut self
+    ) -> &mut LightData {
+        &mut self.light
+    }
+
+    fn get_kind(
+        &self
+    ) -> LightKind<'_> {
+        LightKind::Directional(self)
+    }
+}
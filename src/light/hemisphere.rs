@@ -0,0 +1,75 @@
+use std::{rc::Rc, cell::RefCell};
+use crate::{
+    core::{ObjectData, Object3d, Transformable, RGB, new_object_ref},
+    math::Vector3
+};
+use super::{Light, LightData, LightKind};
+
+// blends from the ground color to the sky color following the light's direction,
+// which is given by its world position (up by default)
+#[derive(Clone)]
+pub struct HemisphereLight {
+    obj: ObjectData,
+    light: LightData,
+    pub ground_color: RGB,
+}
+
+impl HemisphereLight {
+    pub fn new(
+        sky_color: RGB,
+        ground_color: RGB,
+        intensity: f32
+    ) -> Rc<RefCell<Self>> {
+        let mut obj = ObjectData::new();
+        obj.set_position(Vector3::new(0.0, 1.0, 0.0));
+        
+        new_object_ref(Self {
+            obj,
+            light: LightData::new(sky_color, intensity),
+            ground_color,
+        })
+    }
+}
+
+impl Object3d for HemisphereLight {
+    fn get_object(
+        &self
+    ) -> &ObjectData {
+        &self.obj
+    }
+
+    fn get_object_mut(
+        &mut self
+    ) -> &mut ObjectData {
+        &mut self.obj
+    }
+
+    fn as_light(
+        &self
+    ) -> Option<&dyn Light> {
+        Some(self)
+    }
+}
+
+impl Transformable for HemisphereLight {
+}
+
+impl Light for HemisphereLight {
+    fn get_light(
+        &self
+    ) -> &LightData {
+        &self.light
+    }
+
+    fn get_light_mut(
+        &mut self
+    ) -> &mut LightData {
+        &mut self.light
+    }
+
+    fn get_kind(
+        &self
+    ) -> LightKind<'_> {
+        LightKind::Hemisphere(self)
+    }
+}
@@ -0,0 +1,49 @@
+use crate::core::RGB;
+use super::{AmbientLight, DirectionalLight, PointLight, SpotLight, HemisphereLight};
+
+#[derive(Clone, Debug)]
+pub struct LightData {
+    pub color: RGB,
+    pub intensity: f32,
+}
+
+impl LightData {
+    pub fn new(
+        color: RGB,
+        intensity: f32
+    ) -> Self {
+        Self {
+            color,
+            intensity,
+        }
+    }
+
+    pub fn get_radiance(
+        &self
+    ) -> RGB {
+        self.color.map(|c| c * self.intensity)
+    }
+}
+
+// lets renderers read the light specific parameters
+pub enum LightKind<'a> {
+    Ambient(&'a AmbientLight),
+    Directional(&'a DirectionalLight),
+    Point(&'a PointLight),
+    Spot(&'a SpotLight),
+    Hemisphere(&'a HemisphereLight),
+}
+
+pub trait Light {
+    fn get_light(
+        &self
+    ) -> &LightData;
+
+    fn get_light_mut(
+        &mut self
+    ) -> &mut LightData;
+
+    fn get_kind(
+        &self
+    ) -> LightKind<'_>;
+}
@@ -0,0 +1,13 @@
+pub mod light;
+pub mod ambient;
+pub mod directional;
+pub mod point;
+pub mod spot;
+pub mod hemisphere;
+
+pub use light::*;
+pub use ambient::*;
+pub use directional::*;
+pub use point::*;
+pub use spot::*;
+pub use hemisphere::*;
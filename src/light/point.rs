@@ -0,0 +1,70 @@
+use std::{rc::Rc, cell::RefCell};
+use crate::core::{ObjectData, Object3d, Transformable, RGB, new_object_ref};
+use super::{Light, LightData, LightKind};
+
+// shines in all directions; distance 0 means no cutoff, decay is the falloff exponent
+#[derive(Clone)]
+pub struct PointLight {
+    obj: ObjectData,
+    light: LightData,
+    pub distance: f32,
+    pub decay: f32,
+}
+
+impl PointLight {
+    pub fn new(
+        color: RGB,
+        intensity: f32,
+        distance: f32
+    ) -> Rc<RefCell<Self>> {
+        new_object_ref(Self {
+            obj: ObjectData::new(),
+            light: LightData::new(color, intensity),
+            distance,
+            decay: 2.0,
+        })
+    }
+}
+
+impl Object3d for PointLight {
+    fn get_object(
+        &self
+    ) -> &ObjectData {
+        &self.obj
+    }
+
+    fn get_object_mut(
+        &mut self
+    ) -> &mut ObjectData {
+        &mut self.obj
+    }
+
+    fn as_light(
+        &self
+    ) -> Option<&dyn Light> {
+        Some(self)
+    }
+}
+
+impl Transformable for PointLight {
+}
+
+impl Light for PointLight {
+    fn get_light(
+        &self
+    ) -> &LightData {
+        &self.light
+    }
+
+    fn get_light_mut(
+        &mut self
+    ) -> &mut LightData {
+        &mut self.light
+    }
+
+    fn get_kind(
+        &self
+    ) -> LightKind<'_> {
+        LightKind::Point(self)
+    }
+}
@@ -0,0 +1,84 @@
+use std::{rc::Rc, cell::RefCell};
+use crate::{
+    core::{ObjectData, Object3d, Transformable, RGB, new_object_ref},
+    math::Vector3
+};
+use super::{Light, LightData, LightKind};
+
+// a cone pointing towards target, from above by default; angle is the half aperture in radians
+// and penumbra the fraction of the cone that is attenuated
+#[derive(Clone)]
+pub struct SpotLight {
+    obj: ObjectData,
+    light: LightData,
+    pub target: Vector3,
+    pub distance: f32,
+    pub angle: f32,
+    pub penumbra: f32,
+    pub decay: f32,
+}
+
+impl SpotLight {
+    pub fn new(
+        color: RGB,
+        intensity: f32,
+        distance: f32,
+        angle: f32
+    ) -> Rc<RefCell<Self>> {
+        let mut obj = ObjectData::new();
+        obj.set_position(Vector3::new(0.0, 1.0, 0.0));
+        
+        new_object_ref(Self {
+            obj,
+            light: LightData::new(color, intensity),
+            target: Vector3::zero(),
+            distance,
+            angle,
+            penumbra: 0.0,
+            decay: 2.0,
+        })
+    }
+}
+
+impl Object3d for SpotLight {
+    fn get_object(
+        &self
+    ) -> &ObjectData {
+        &self.obj
+    }
+
+    fn get_object_mut(
+        &mut self
+    ) -> &mut ObjectData {
+        &mut self.obj
+    }
+
+    fn as_light(
+        &self
+    ) -> Option<&dyn Light> {
+        Some(self)
+    }
+}
+
+impl Transformable for SpotLight {
+}
+
+impl Light for SpotLight {
+    fn get_light(
+        &self
+    ) -> &LightData {
+        &self.light
+    }
+
+    fn get_light_mut(
+        &mut self
+    ) -> &mut LightData {
+        &mut self.light
+    }
+
+    fn get_kind(
+        &self
+    ) -> LightKind<'_> {
+        LightKind::Spot(self)
+    }
+}
@@ -1,11 +1,23 @@
-use std::{rc::Rc, cell::RefCell};
+use std::{rc::Rc, cell::RefCell, collections::HashMap};
 use crate::{
-    math::{matrix4::Matrix4, vector3::Vector3},
-    core::{BufferGeometry, BufferGeometryMode, Geometrical}, 
-    object::Mesh, 
-    renderer::GlRenderer
+    math::{matrix4::Matrix4, vector3::Vector3, quaternion::Quaternion},
+    core::{BufferGeometry, BufferGeometryMode, Geometrical, Object3d, ObjectRef, new_object_ref}, 
+    object::{Mesh, Group}, 
+    camera::{PerspectiveCamera, OrthographicCamera},
+    material::{Material, MaterialRef, MeshStandardMaterial, Side},
+    texture::{Texture, TextureRef, Wrapping, Filter},
+    renderer::Renderer
 };
 
+// resources shared by nodes, by gltf index
+#[derive(Default)]
+struct Cache {
+    // None is the default material
+    materials: HashMap<Option<usize>, MaterialRef>,
+    // None when the image is an external file, that can't be resolved without a base path
+    textures: HashMap<usize, Option<TextureRef>>,
+}
+
 pub struct Gltf {
     pub geo: BufferGeometry,
 }
@@ -13,15 +25,442 @@ pub struct Gltf {
 impl Gltf {
     pub fn load_from_bytes(
         bytes: &[u8]
-    ) -> Result<Rc<RefCell<Mesh>>, String> {
-        let gltf = gltf::Gltf::from_slice(&bytes).unwrap();
-        let buffers = gltf::import_buffers(&gltf.document, None, gltf.blob).unwrap();
+    ) -> Result<Rc<RefCell<Group>>, String> {
+        let gltf = gltf::Gltf::from_slice(bytes).map_err(|e| e.to_string())?;
+        let buffers = gltf::import_buffers(&gltf.document, None, gltf.blob).map_err(|e| e.to_string())?;
         Self::load(&gltf.document, &buffers)
     }
 
-    pub fn load<'a>(
+    pub fn load_merged_from_bytes(
+        bytes: &[u8]
+    ) -> Result<Rc<RefCell<Mesh>>, String> {
+        let gltf = gltf::Gltf::from_slice(bytes).map_err(|e| e.to_string())?;
+        let buffers = gltf::import_buffers(&gltf.document, None, gltf.blob).map_err(|e| e.to_string())?;
+        Self::load_merged(&gltf.document, &buffers)
+    }
+
+    // the default scene becomes a group, keeping the node hierarchy and local transforms
+    pub fn load(
+        doc: &gltf::Document,
+        buffers: &[gltf::buffer::Data]
+    ) -> Result<Rc<RefCell<Group>>, String> {
+        let scene = doc.default_scene()
+            .or_else(|| doc.scenes().next())
+            .ok_or("No scene found".to_string())?;
+
+        let root = Group::new();
+        let mut cache = Cache::default();
+        
+        for node in scene.nodes() {
+            let child = Self::load_node(&node, buffers, &mut cache)?;
+            root.borrow_mut().add(child)?;
+        }
+
+        Ok(root)
+    }
+
+    fn load_node(
+        node: &gltf::Node<'_>,
+        buffers: &[gltf::buffer::Data],
+        cache: &mut Cache
+    ) -> Result<ObjectRef, String> {
+        let mut meshes = match node.mesh() {
+            Some(mesh) => Self::load_mesh(&mesh, buffers, cache)?,
+            None => vec![],
+        };
+        let camera = node.camera()
+            .map(|camera| Self::load_camera(&camera));
+
+        // a node with a single mesh or a single camera is that object, otherwise it's a group
+        let object: ObjectRef = match (meshes.len(), camera) {
+            (1, None) => {
+                meshes.remove(0)
+            },
+            (0, Some(camera)) => {
+                camera
+            },
+            (_, camera) => {
+                let group: ObjectRef = Group::new();
+                for mesh in meshes {
+                    group.borrow_mut().add(mesh)?;
+                }
+                if let Some(camera) = camera {
+                    group.borrow_mut().add(camera)?;
+                }
+                group
+            }
+        };
+
+        {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            let mut obj = object.borrow_mut();
+            let obj = obj.get_object_mut();
+            if let Some(name) = node.name() {
+                obj.set_name(name);
+            }
+            obj.set_position(Vector3::from_slice(&translation));
+            obj.set_rotation(Quaternion::from_slice(&rotation));
+            obj.set_scale(Vector3::from_slice(&scale));
+        }
+
+        for child in node.children() {
+            let child = Self::load_node(&child, buffers, cache)?;
+            object.borrow_mut().add(child)?;
+        }
+
+        Ok(object)
+    }
+
+    fn load_mesh(
+        mesh: &gltf::Mesh<'_>,
+        buffers: &[gltf::buffer::Data],
+        cache: &mut Cache
+    ) -> Result<Vec<ObjectRef>, String> {
+        let mut meshes = vec![];
+        let mesh_name = mesh.name();
+        
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+            let positions = positions
+                .map(|p| Vector3::from_slice(&p))
+                .collect::<Vec<_>>();
+            
+            let normals = reader.read_normals()
+                .map(|iter| iter.map(|n| Vector3::from_slice(&n)).collect::<Vec<_>>());
+            
+            let colors = reader.read_colors(0)
+                .map(|iter| iter.into_rgb_f32().collect::<Vec<_>>());
+
+            let (uv_set, uv2_set) = Self::get_uv_sets(&primitive.material());
+
+            let uvs = reader.read_tex_coords(uv_set)
+                .map(|iter| iter.into_f32().collect::<Vec<_>>());
+
+            // without its own set the occlusion map reads the first one
+            let uvs2 = match uv2_set {
+                Some(set) => reader.read_tex_coords(set)
+                    .map(|iter| iter.into_f32().collect::<Vec<_>>()),
+                None => None,
+            };
+            
+            let indices = reader.read_indices()
+                .map(|ind| ind.into_u32().collect::<Vec<_>>())
+                .unwrap_or((0..positions.len() as u32).collect());
+
+            let (mode, indices) = Self::convert_indices(primitive.mode(), indices)?;
+
+            let mut geo = Self {
+                geo: BufferGeometry::new(
+                    mode, 
+                    Some(indices), 
+                    Some(positions), 
+                    normals, 
+                    colors
+                ),
+            };
+            geo.geo.set_uvs(uvs);
+            geo.geo.set_uvs2(uvs2);
+
+            let material = Self::load_material(&primitive.material(), buffers, cache)?;
+
+            let mesh: ObjectRef = Mesh::new_ex(&geo, material);
+            if let Some(name) = mesh_name {
+                mesh.borrow_mut().set_name(name);
+            }
+            meshes.push(mesh);
+        }
+
+        Ok(meshes)
+    }
+
+    // the set read by the maps and the one read by the occlusion map when it's another.
+    // materials without an occlusion map keep TEXCOORD_1 as the second set
+    fn get_uv_sets(
+        material: &gltf::Material<'_>
+    ) -> (u32, Option<u32>) {
+        let uv_set = Self::get_uv_set(material);
+
+        let uv2_set = material.occlusion_texture()
+            .map_or(1, |info| info.tex_coord());
+
+        (uv_set, (uv2_set != uv_set).then_some(uv2_set))
+    }
+
+    // the base color map's set, or the first map's without one.
+    // load_material() leaves out the maps reading other sets
+    fn get_uv_set(
+        material: &gltf::Material<'_>
+    ) -> u32 {
+        let pbr = material.pbr_metallic_roughness();
+        [
+            pbr.base_color_texture().map(|info| info.tex_coord()),
+            pbr.metallic_roughness_texture().map(|info| info.tex_coord()),
+            material.normal_texture().map(|info| info.tex_coord()),
+            material.emissive_texture().map(|info| info.tex_coord()),
+        ]
+            .into_iter()
+            .flatten()
+            .next()
+            .unwrap_or(0)
+    }
+
+    fn load_material(
+        material: &gltf::Material<'_>,
+        buffers: &[gltf::buffer::Data],
+        cache: &mut Cache
+    ) -> Result<MaterialRef, String> {
+        if let Some(material) = cache.materials.get(&material.index()) {
+            return Ok(material.clone());
+        }
+
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, a] = pbr.base_color_factor();
+
+        let standard = MeshStandardMaterial::new_ex(
+            [r, g, b], 
+            pbr.metallic_factor(), 
+            pbr.roughness_factor()
+        );
+
+        // geometries carry a single set for these maps
+        let uv_set = Self::get_uv_set(material);
+
+        {
+            let mut standard = standard.borrow_mut();
+            standard.emissive = material.emissive_factor();
+            if let Some(info) = pbr.base_color_texture() {
+                standard.map = Self::load_texture(&info.texture(), buffers, cache)?;
+            }
+            if let Some(info) = pbr.metallic_roughness_texture().filter(|info| info.tex_coord() == uv_set) {
+                standard.metalness_roughness_map = Self::load_texture(&info.texture(), buffers, cache)?;
+            }
+            if let Some(normal) = material.normal_texture().filter(|normal| normal.tex_coord() == uv_set) {
+                standard.normal_scale = normal.scale();
+                standard.normal_map = Self::load_texture(&normal.texture(), buffers, cache)?;
+            }
+            if let Some(occlusion) = material.occlusion_texture() {
+                standard.occlusion_strength = occlusion.strength();
+                standard.occlusion_map = Self::load_texture(&occlusion.texture(), buffers, cache)?;
+            }
+            if let Some(info) = material.emissive_texture().filter(|info| info.tex_coord() == uv_set) {
+                standard.emissive_map = Self::load_texture(&info.texture(), buffers, cache)?;
+            }
+
+            let mat = standard.get_data_mut();
+            if let Some(name) = material.name() {
+                mat.name = name.to_string();
+            }
+            // COLOR_0 multiplies the base color, renderers ignore this when it's missing
+            mat.vertex_colors = true;
+            if material.double_sided() {
+                mat.side = Side::Double;
+            }
+            if material.alpha_mode() == gltf::material::AlphaMode::Blend {
+                mat.opacity = a;
+                mat.transparent = true;
+            }
+        }
+
+        cache.materials.insert(material.index(), standard.clone());
+
+        Ok(standard)
+    }
+
+    fn load_texture(
+        texture: &gltf::Texture<'_>,
+        buffers: &[gltf::buffer::Data],
+        cache: &mut Cache
+    ) -> Result<Option<TextureRef>, String> {
+        if let Some(texture) = cache.textures.get(&texture.index()) {
+            return Ok(texture.clone());
+        }
+
+        let bytes = match texture.source().source() {
+            gltf::image::Source::View { view, .. } => {
+                let buffer = &buffers[view.buffer().index()];
+                let bytes = buffer.get(view.offset()..view.offset() + view.length())
+                    .ok_or("Image buffer view out of range".to_string())?;
+                Some(bytes.to_vec())
+            },
+            gltf::image::Source::Uri { uri, .. } => {
+                // external files are left to the caller
+                match uri.strip_prefix("data:") {
+                    Some(uri) => Some(Self::decode_data_uri(uri)?),
+                    None => None,
+                }
+            },
+        };
+
+        // images the decoders can't read, like progressive jpegs or webp, leave the map empty
+        let result = match bytes.and_then(|bytes| Texture::load_from_bytes(&bytes).ok()) {
+            Some(tex) => {
+                {
+                    let mut tex = tex.borrow_mut();
+                    if let Some(name) = texture.name() {
+                        tex.name = name.to_string();
+                    }
+                    // gltf uvs start at the top left, the first row of the image
+                    tex.flip_y = false;
+                    Self::apply_sampler(&mut tex, &texture.sampler());
+                }
+                Some(tex)
+            },
+            None => None,
+        };
+
+        cache.textures.insert(texture.index(), result.clone());
+        
+        Ok(result)
+    }
+
+    fn apply_sampler(
+        tex: &mut Texture,
+        sampler: &gltf::texture::Sampler<'_>
+    ) {
+        use gltf::texture::{WrappingMode, MagFilter, MinFilter};
+
+        let wrap = |mode| match mode {
+            WrappingMode::ClampToEdge => Wrapping::ClampToEdge,
+            WrappingMode::MirroredRepeat => Wrapping::MirroredRepeat,
+            WrappingMode::Repeat => Wrapping::Repeat,
+        };
+        tex.wrap_s = wrap(sampler.wrap_s());
+        tex.wrap_t = wrap(sampler.wrap_t());
+
+        if let Some(filter) = sampler.mag_filter() {
+            tex.mag_filter = match filter {
+                MagFilter::Nearest => Filter::Nearest,
+                MagFilter::Linear => Filter::Linear,
+            };
+        }
+
+        if let Some(filter) = sampler.min_filter() {
+            tex.min_filter = match filter {
+                MinFilter::Nearest => Filter::Nearest,
+                MinFilter::Linear => Filter::Linear,
+                MinFilter::NearestMipmapNearest => Filter::NearestMipmapNearest,
+                MinFilter::LinearMipmapNearest => Filter::LinearMipmapNearest,
+                MinFilter::NearestMipmapLinear => Filter::NearestMipmapLinear,
+                MinFilter::LinearMipmapLinear => Filter::LinearMipmapLinear,
+            };
+        }
+    }
+
+    // base64 payload of a data uri, after the "data:" scheme
+    fn decode_data_uri(
+        uri: &str
+    ) -> Result<Vec<u8>, String> {
+        let (header, data) = uri.split_once(',')
+            .ok_or("Invalid data uri".to_string())?;
+        if !header.ends_with(";base64") {
+            return Err("Only base64 data uris are supported".to_string());
+        }
+
+        let mut bytes = Vec::with_capacity(data.len() / 4 * 3);
+        let mut acc = 0u32;
+        let mut bits = 0;
+        for c in data.bytes() {
+            let value = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' | b'-' => 62,
+                b'/' | b'_' => 63,
+                b'=' => break,
+                c if c.is_ascii_whitespace() => continue,
+                _ => return Err("Invalid base64 in data uri".to_string()),
+            };
+            acc = (acc << 6) | value as u32;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((acc >> bits) as u8);
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    // strips, fans and loops are expanded, as only lists and line strips can be drawn
+    fn convert_indices(
+        mode: gltf::mesh::Mode,
+        indices: Vec<u32>
+    ) -> Result<(BufferGeometryMode, Vec<u32>), String> {
+        match mode {
+            gltf::mesh::Mode::Triangles => {
+                Ok((BufferGeometryMode::Triangles, indices))
+            },
+            gltf::mesh::Mode::TriangleStrip => {
+                let mut res = vec![];
+                for i in 0..indices.len().saturating_sub(2) {
+                    if i % 2 == 0 {
+                        res.extend([indices[i], indices[i + 1], indices[i + 2]]);
+                    }
+                    else {
+                        res.extend([indices[i + 1], indices[i], indices[i + 2]]);
+                    }
+                }
+                Ok((BufferGeometryMode::Triangles, res))
+            },
+            gltf::mesh::Mode::TriangleFan => {
+                let mut res = vec![];
+                for i in 1..indices.len().saturating_sub(1) {
+                    res.extend([indices[0], indices[i], indices[i + 1]]);
+                }
+                Ok((BufferGeometryMode::Triangles, res))
+            },
+            gltf::mesh::Mode::Lines => {
+                Ok((BufferGeometryMode::Lines, indices))
+            },
+            gltf::mesh::Mode::LineStrip => {
+                Ok((BufferGeometryMode::LineStrip, indices))
+            },
+            gltf::mesh::Mode::LineLoop => {
+                let mut res = indices;
+                if let Some(first) = res.first().cloned() {
+                    res.push(first);
+                }
+                Ok((BufferGeometryMode::LineStrip, res))
+            },
+            gltf::mesh::Mode::Points => {
+                Err("Unsupported primitive".to_string())
+            },
+        }
+    }
+
+    fn load_camera(
+        camera: &gltf::Camera<'_>
+    ) -> ObjectRef {
+        match camera.projection() {
+            gltf::camera::Projection::Perspective(p) => {
+                new_object_ref(PerspectiveCamera::new(
+                    p.yfov().to_degrees(), 
+                    p.aspect_ratio().unwrap_or(1.0), 
+                    p.znear(), 
+                    p.zfar().unwrap_or(1000.0)
+                ))
+            },
+            gltf::camera::Projection::Orthographic(o) => {
+                new_object_ref(OrthographicCamera::new(
+                    -o.xmag(), 
+                    o.xmag(), 
+                    o.ymag(), 
+                    -o.ymag(), 
+                    o.znear(), 
+                    o.zfar()
+                ))
+            },
+        }
+    }
+
+    // all meshes are baked into one, in world space
+    pub fn load_merged(
         doc: &gltf::Document,
-        buffers: &Vec<gltf::buffer::Data>
+        buffers: &[gltf::buffer::Data]
     ) -> Result<Rc<RefCell<Mesh>>, String> {
         
         let scene = doc.default_scene().unwrap();
@@ -46,7 +485,7 @@ impl Gltf {
                                 gltf::mesh::Mode::Triangles => {
                                     for i in 0..prim_ind.len() {
                                         let v = Vector3::from_slice(&prim_pos[prim_ind[i] as usize])
-                                            .apply_matrix4(&world_matrix);
+                                            .apply_matrix4(world_matrix);
                                         positions.push(v);
                                     }
                                 }
@@ -138,7 +577,7 @@ impl Geometrical for Gltf {
 
     fn drop(
         &mut self, 
-        renderer: &GlRenderer
+        renderer: &mut dyn Renderer
     ) {
         (self as &mut dyn Geometrical).destroy(renderer)
     }
@@ -0,0 +1,44 @@
+use std::{rc::Rc, cell::RefCell};
+use crate::{core::RGB, texture::TextureRef};
+use super::{Material, MaterialData, MaterialKind};
+
+// unlit, the color is multiplied by the vertex colors when they are enabled
+#[derive(Clone, Debug)]
+pub struct MeshBasicMaterial {
+    mat: MaterialData,
+    pub color: RGB,
+    // multiplies color and opacity
+    pub map: Option<TextureRef>,
+}
+
+impl MeshBasicMaterial {
+    pub fn new(
+        color: RGB
+    ) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            mat: MaterialData::new(),
+            color,
+            map: None,
+        }))
+    }
+}
+
+impl Material for MeshBasicMaterial {
+    fn get_data(
+        &self
+    ) -> &MaterialData {
+        &self.mat
+    }
+
+    fn get_data_mut(
+        &mut self
+    ) -> &mut MaterialData {
+        &mut self.mat
+    }
+
+    fn get_kind(
+        &self
+    ) -> MaterialKind<'_> {
+        MaterialKind::Basic(self)
+    }
+}
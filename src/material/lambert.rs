@@ -0,0 +1,46 @@
+use std::{rc::Rc, cell::RefCell};
+use crate::{core::RGB, texture::TextureRef};
+use super::{Material, MaterialData, MaterialKind};
+
+// diffuse only lighting
+#[derive(Clone, Debug)]
+pub struct MeshLambertMaterial {
+    mat: MaterialData,
+    pub color: RGB,
+    pub emissive: RGB,
+    // multiplies color and opacity
+    pub map: Option<TextureRef>,
+}
+
+impl MeshLambertMaterial {
+    pub fn new(
+        color: RGB
+    ) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            mat: MaterialData::new(),
+            color,
+            emissive: [0.0; 3],
+            map: None,
+        }))
+    }
+}
+
+impl Material for MeshLambertMaterial {
+    fn get_data(
+        &self
+    ) -> &MaterialData {
+        &self.mat
+    }
+
+    fn get_data_mut(
+        &mut self
+    ) -> &mut MaterialData {
+        &mut self.mat
+    }
+
+    fn get_kind(
+        &self
+    ) -> MaterialKind<'_> {
+        MaterialKind::Lambert(self)
+    }
+}
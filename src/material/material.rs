@@ -0,0 +1,120 @@
+use std::{
+    rc::Rc, 
+    cell::RefCell, 
+    sync::atomic::{AtomicUsize, Ordering}
+};
+use super::{
+    MeshBasicMaterial, 
+    MeshNormalMaterial, 
+    MeshLambertMaterial, 
+    MeshPhongMaterial, 
+    MeshStandardMaterial, 
+    ShaderMaterial
+};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+pub type MaterialRef = Rc<RefCell<dyn Material>>;
+
+// which faces are drawn; front faces are the counter-clockwise ones
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Front,
+    Back,
+    Double,
+}
+
+#[derive(Debug)]
+pub struct MaterialData {
+    pub(crate) id: usize,
+    pub name: String,
+    pub visible: bool,
+    pub opacity: f32,
+    pub transparent: bool,
+    pub vertex_colors: bool,
+    pub wireframe: bool,
+    pub side: Side,
+    pub depth_test: bool,
+    pub depth_write: bool,
+}
+
+impl Clone for MaterialData {
+    fn clone(
+        &self
+    ) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            name: self.name.clone(),
+            visible: self.visible,
+            opacity: self.opacity,
+            transparent: self.transparent,
+            vertex_colors: self.vertex_colors,
+            wireframe: self.wireframe,
+            side: self.side,
+            depth_test: self.depth_test,
+            depth_write: self.depth_write,
+        }
+    }
+}
+
+impl Default for MaterialData {
+    fn default(
+    ) -> Self {
+        Self::new()
+    }
+}
+
+impl MaterialData {
+    pub fn new(
+    ) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            name: String::new(),
+            visible: true,
+            opacity: 1.0,
+            transparent: false,
+            vertex_colors: false,
+            wireframe: false,
+            side: Side::Front,
+            depth_test: true,
+            depth_write: true,
+        }
+    }
+
+    pub fn get_id(
+        &self
+    ) -> usize {
+        self.id
+    }
+
+    // blended materials are drawn after the opaque ones
+    pub fn is_transparent(
+        &self
+    ) -> bool {
+        self.transparent || self.opacity < 1.0
+    }
+}
+
+// lets renderers pick a program and read the material specific parameters
+pub enum MaterialKind<'a> {
+    Basic(&'a MeshBasicMaterial),
+    Normal(&'a MeshNormalMaterial),
+    Lambert(&'a MeshLambertMaterial),
+    Phong(&'a MeshPhongMaterial),
+    Standard(&'a MeshStandardMaterial),
+    Shader(&'a ShaderMaterial),
+}
+
+pub trait Material {
+    fn get_data(
+        &self
+    ) -> &MaterialData;
+
+    fn get_data_mut(
+        &mut self
+    ) -> &mut MaterialData;
+
+    fn get_kind(
+        &self
+    ) -> MaterialKind<'_>;
+}
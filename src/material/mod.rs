@@ -0,0 +1,15 @@
+pub mod material;
+pub mod basic;
+pub mod normal;
+pub mod lambert;
+pub mod phong;
+pub mod standard;
+pub mod shader;
+
+pub use material::*;
+pub use basic::*;
+pub use normal::*;
+pub use lambert::*;
+pub use phong::*;
+pub use standard::*;
+pub use shader::*;
@@ -0,0 +1,37 @@
+use std::{rc::Rc, cell::RefCell};
+use super::{Material, MaterialData, MaterialKind};
+
+// greyscale shading from the object space normals, useful to inspect geometry
+#[derive(Clone, Debug)]
+pub struct MeshNormalMaterial {
+    mat: MaterialData,
+}
+
+impl MeshNormalMaterial {
+    pub fn new(
+    ) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            mat: MaterialData::new(),
+        }))
+    }
+}
+
+impl Material for MeshNormalMaterial {
+    fn get_data(
+        &self
+    ) -> &MaterialData {
+        &self.mat
+    }
+
+    fn get_data_mut(
+        &mut self
+    ) -> &mut MaterialData {
+        &mut self.mat
+    }
+
+    fn get_kind(
+        &self
+    ) -> MaterialKind<'_> {
+        MaterialKind::Normal(self)
+    }
+}
@@ -0,0 +1,50 @@
+use std::{rc::Rc, cell::RefCell};
+use crate::{core::RGB, texture::TextureRef};
+use super::{Material, MaterialData, MaterialKind};
+
+// diffuse plus Blinn-Phong specular highlights
+#[derive(Clone, Debug)]
+pub struct MeshPhongMaterial {
+    mat: MaterialData,
+    pub color: RGB,
+    pub emissive: RGB,
+    pub specular: RGB,
+    pub shininess: f32,
+    // multiplies color and opacity
+    pub map: Option<TextureRef>,
+}
+
+impl MeshPhongMaterial {
+    pub fn new(
+        color: RGB
+    ) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            mat: MaterialData::new(),
+            color,
+            emissive: [0.0; 3],
+            specular: [0.067; 3],
+            shininess: 30.0,
+            map: None,
+        }))
+    }
+}
+
+impl Material for MeshPhongMaterial {
+    fn get_data(
+        &self
+    ) -> &MaterialData {
+        &self.mat
+    }
+
+    fn get_data_mut(
+        &mut self
+    ) -> &mut MaterialData {
+        &mut self.mat
+    }
+
+    fn get_kind(
+        &self
+    ) -> MaterialKind<'_> {
+        MaterialKind::Phong(self)
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    rc::Rc, 
+    cell::RefCell, 
+    collections::HashMap, 
+    hash::{Hash, Hasher, DefaultHasher}
+};
+use crate::{math::{Vector3, Matrix3, Matrix4}, texture::TextureRef};
+use super::{Material, MaterialData, MaterialKind};
+
+#[derive(Clone, Debug)]
+pub enum Uniform {
+    Float(f32),
+    Int(i32),
+    Bool(bool),
+    Vector2([f32; 2]),
+    Vector3(Vector3),
+    Vector4([f32; 4]),
+    Matrix3(Matrix3),
+    Matrix4(Matrix4),
+    // an unset texture leaves the sampler unbound
+    Texture(Option<TextureRef>),
+    FloatArray(Vec<f32>),
+    IntArray(Vec<i32>),
+    Vector2Array(Vec<[f32; 2]>),
+    Vector3Array(Vec<Vector3>),
+    Vector4Array(Vec<[f32; 4]>),
+    Matrix4Array(Vec<Matrix4>),
+}
+
+impl Uniform {
+    // flattened as the renderers pass it to the shader; textures have no value of their own
+    pub fn to_vec(
+        &self
+    ) -> Vec<f32> {
+        match self {
+            Self::Float(v) => vec![*v],
+            Self::Int(v) => vec![*v as f32],
+            Self::Bool(v) => vec![if *v {1.0} else {0.0}],
+            Self::Vector2(v) => v.to_vec(),
+            Self::Vector3(v) => v.to_slice().to_vec(),
+            Self::Vector4(v) => v.to_vec(),
+            Self::Matrix3(m) => m.0.to_vec(),
+            Self::Matrix4(m) => m.0.to_vec(),
+            Self::Texture(_) => vec![],
+            Self::FloatArray(v) => v.clone(),
+            Self::IntArray(v) => v.iter().map(|v| *v as f32).collect(),
+            Self::Vector2Array(v) => v.iter().flatten().cloned().collect(),
+            Self::Vector3Array(v) => v.iter().flat_map(|v| v.to_slice()).collect(),
+            Self::Vector4Array(v) => v.iter().flatten().cloned().collect(),
+            Self::Matrix4Array(v) => v.iter().flat_map(|m| m.0).collect(),
+        }
+    }
+}
+
+// user glsl, compiled by the gl renderer on first use and shared by materials with the same sources.
+// attributes are bound at the built-in locations: position 0, normal 1, color 2, uv 3 and uv2 4,
+// and the projection and model_view matrices are set like for the built-in shaders
+#[derive(Clone, Debug)]
+pub struct ShaderMaterial {
+    mat: MaterialData,
+    pub vertex_shader: String,
+    pub fragment_shader: String,
+    pub uniforms: HashMap<String, Uniform>,
+    // sets the same light uniforms as the lit built-in shaders
+    pub lights: bool,
+}
+
+impl ShaderMaterial {
+    pub fn new(
+        vertex_shader: &str,
+        fragment_shader: &str
+    ) -> Rc<RefCell<Self>> {
+        Self::new_ex(vertex_shader, fragment_shader, HashMap::default())
+    }
+
+    pub fn new_ex(
+        vertex_shader: &str,
+        fragment_shader: &str,
+        uniforms: HashMap<String, Uniform>
+    ) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            mat: MaterialData::new(),
+            vertex_shader: vertex_shader.to_string(),
+            fragment_shader: fragment_shader.to_string(),
+            uniforms,
+            lights: false,
+        }))
+    }
+
+    pub fn get_uniform(
+        &self,
+        name: &str
+    ) -> Option<&Uniform> {
+        self.uniforms.get(name)
+    }
+
+    pub fn set_uniform(
+        &mut self,
+        name: &str,
+        value: Uniform
+    ) {
+        self.uniforms.insert(name.to_string(), value);
+    }
+
+    // identifies the compiled program
+    pub fn get_source_hash(
+        &self
+    ) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.vertex_shader.hash(&mut hasher);
+        self.fragment_shader.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // texture uniforms sorted by name, the order they're bound to texture units
+    pub fn get_textures(
+        &self
+    ) -> Vec<(String, Option<TextureRef>)> {
+        let mut textures = self.uniforms.iter()
+            .filter_map(|(name, uniform)| match uniform {
+                Uniform::Texture(texture) => Some((name.clone(), texture.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        textures.sort_by(|a, b| a.0.cmp(&b.0));
+        textures
+    }
+}
+
+impl Material for ShaderMaterial {
+    fn get_data(
+        &self
+    ) -> &MaterialData {
+        &self.mat
+    }
+
+    fn get_data_mut(
+        &mut self
+    ) -> &mut MaterialData {
+        &mut self.mat
+    }
+
+    fn get_kind(
+        &self
+    ) -> MaterialKind<'_> {
+        MaterialKind::Shader(self)
+    }
+}
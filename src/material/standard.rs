@@ -0,0 +1,73 @@
+use std::{rc::Rc, cell::RefCell};
+use crate::{core::RGB, texture::TextureRef};
+use super::{Material, MaterialData, MaterialKind};
+
+// metallic-roughness pbr, with the same semantics as gltf's pbrMetallicRoughness
+#[derive(Clone, Debug)]
+pub struct MeshStandardMaterial {
+    mat: MaterialData,
+    pub color: RGB,
+    pub metalness: f32,
+    pub roughness: f32,
+    pub emissive: RGB,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    // multiplies color and opacity
+    pub map: Option<TextureRef>,
+    // metalness in blue, roughness in green, multiplying the factors
+    pub metalness_roughness_map: Option<TextureRef>,
+    // tangent space, scaled by normal_scale
+    pub normal_map: Option<TextureRef>,
+    // red channel, read with the second uv set when there is one
+    pub occlusion_map: Option<TextureRef>,
+    pub emissive_map: Option<TextureRef>,
+}
+
+impl MeshStandardMaterial {
+    pub fn new(
+        color: RGB
+    ) -> Rc<RefCell<Self>> {
+        Self::new_ex(color, 0.0, 1.0)
+    }
+
+    pub fn new_ex(
+        color: RGB,
+        metalness: f32,
+        roughness: f32
+    ) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            mat: MaterialData::new(),
+            color,
+            metalness,
+            roughness,
+            emissive: [0.0; 3],
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            map: None,
+            metalness_roughness_map: None,
+            normal_map: None,
+            occlusion_map: None,
+            emissive_map: None,
+        }))
+    }
+}
+
+impl Material for MeshStandardMaterial {
+    fn get_data(
+        &self
+    ) -> &MaterialData {
+        &self.mat
+    }
+
+    fn get_data_mut(
+        &mut self
+    ) -> &mut MaterialData {
+        &mut self.mat
+    }
+
+    fn get_kind(
+        &self
+    ) -> MaterialKind<'_> {
+        MaterialKind::Standard(self)
+    }
+}
@@ -1,5 +1,6 @@
 use serde::{Serialize, Deserialize};
-use super::{Vector3, Triangle, Sphere};
+use crate::core::Object3d;
+use super::{Vector3, Matrix4, Triangle, Sphere};
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Box3 {
@@ -27,6 +28,84 @@ impl Box3 {
         }
     }
 
+    pub fn from_points(
+        points: &[Vector3]
+    ) -> Self {
+        let mut bx = Self::default();
+        for p in points {
+            bx.expand_by_point(p);
+        }
+        bx
+    }
+
+    pub fn expand_by_point(
+        &mut self,
+        point: &Vector3
+    ) {
+        self.min = Vector3::new(self.min.x.min(point.x), self.min.y.min(point.y), self.min.z.min(point.z));
+        self.max = Vector3::new(self.max.x.max(point.x), self.max.y.max(point.y), self.max.z.max(point.z));
+    }
+
+    pub fn union(
+        &self,
+        bx: &Self
+    ) -> Self {
+        Self {
+            min: Vector3::new(self.min.x.min(bx.min.x), self.min.y.min(bx.min.y), self.min.z.min(bx.min.z)),
+            max: Vector3::new(self.max.x.max(bx.max.x), self.max.y.max(bx.max.y), self.max.z.max(bx.max.z)),
+        }
+    }
+
+    // the box around the transformed corners
+    pub fn apply_matrix4(
+        &self,
+        m: &Matrix4
+    ) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+
+        let mut bx = Self::default();
+        for x in [self.min.x, self.max.x] {
+            for y in [self.min.y, self.max.y] {
+                for z in [self.min.z, self.max.z] {
+                    bx.expand_by_point(&Vector3::new(x, y, z).apply_matrix4(m));
+                }
+            }
+        }
+        bx
+    }
+
+    // world space bounds of the object and its descendants. world matrices must be up to date,
+    // see ObjectData::update_matrix_world(). fails if a descendant is borrowed elsewhere
+    pub fn set_from_object(
+        &mut self,
+        object: &mut dyn Object3d
+    ) -> Result<(), String> {
+        *self = Self::default();
+        self.expand_by_object(object)
+    }
+
+    // fails if a descendant is borrowed elsewhere, having expanded by part of the tree
+    pub fn expand_by_object(
+        &mut self,
+        object: &mut dyn Object3d
+    ) -> Result<(), String> {
+        let world = object.get_object().get_world_matrix().clone();
+        if let Some(geometrical) = object.as_geometrical_mut() {
+            let bx = geometrical.get_geometry_mut().compute_bounding_box();
+            *self = self.union(&bx.apply_matrix4(&world));
+        }
+
+        for child in object.get_object().get_children().clone() {
+            let mut child = child.try_borrow_mut()
+                .map_err(|_| format!("A child of object {} is borrowed elsewhere", object.get_object().get_id()))?;
+            self.expand_by_object(&mut *child)?;
+        }
+
+        Ok(())
+    }
+
     pub fn is_empty(
         &self
     ) -> bool {
@@ -96,10 +175,10 @@ impl Box3 {
 		}
         
 		let normal = f0.cross(&f1);
-		return Self::sat_for_axes(
+		Self::sat_for_axes(
             &[normal.x, normal.y, normal.z], 
             &v0, &v1, &v2, &extents
-        );
+        )
     }
 
     fn sat_for_axes(
@@ -113,7 +192,7 @@ impl Box3 {
         let mut i = 0;
         let j = axes.len() - 3;
         while i <= j {
-            let test = Vector3::from_array(&axes, i);
+            let test = Vector3::from_array(axes, i);
             
             let r = extents.x * test.x.abs() + extents.y * test.y.abs() + extents.z * test.z.abs();
 
@@ -168,4 +247,28 @@ impl Box3 {
             )
 		}
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::Mesh;
+
+    #[test]
+    fn set_from_object_fails_on_borrowed_children() {
+        let geo = crate::geometry::Box3::new(1.0, 1.0, 1.0);
+        let parent = Mesh::new(&geo);
+        let child = Mesh::new(&geo);
+        child.borrow_mut().get_object_mut().set_position(Vector3::new(2.0, 0.0, 0.0));
+        parent.borrow_mut().add(child.clone()).unwrap();
+        parent.borrow_mut().get_object_mut().update_matrix_world(true);
+
+        let mut bx = Box3::default();
+        bx.set_from_object(&mut *parent.borrow_mut()).unwrap();
+        assert!((bx.min.x + 0.5).abs() < 1e-4);
+        assert!((bx.max.x - 2.5).abs() < 1e-4);
+
+        let _held = child.borrow_mut();
+        assert!(bx.set_from_object(&mut *parent.borrow_mut()).is_err());
+    }
+}
@@ -50,7 +50,7 @@ impl Capsule {
     ) -> Self {
         Self {
             start: self.start.add(v),
-            end: self.start.add(v),
+            end: self.end.add(v),
             radius: self.radius,
         }
 	}
@@ -138,4 +138,19 @@ impl Capsule {
 
 		None
 	}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate_moves_both_ends() {
+        let capsule = Capsule::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), 0.5)
+            .translate(&Vector3::new(1.0, 2.0, 3.0));
+
+        assert_eq!(capsule.start, Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(capsule.end, Vector3::new(1.0, 3.0, 3.0));
+        assert_eq!(capsule.radius, 0.5);
+    }
+}
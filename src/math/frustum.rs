@@ -0,0 +1,150 @@
+use serde::{Serialize, Deserialize};
+use super::{Vector3, Matrix4, Plane, Box3, Sphere};
+
+// six planes facing inwards: left, right, bottom, top, near and far
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    pub fn new(
+        planes: [Plane; 6]
+    ) -> Self {
+        Self {
+            planes,
+        }
+    }
+
+    // m is usually projection * view, giving the frustum in world space
+    pub fn from_matrix(
+        m: &Matrix4
+    ) -> Self {
+        let e = &m.0;
+        let row = |i: usize| [e[i], e[i + 4], e[i + 8], e[i + 12]];
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        
+        let plane = |sign: f32, r: [f32; 4]| Plane::new(
+            Vector3::new(r3[0] + sign * r[0], r3[1] + sign * r[1], r3[2] + sign * r[2]),
+            r3[3] + sign * r[3]
+        ).normalize();
+
+        Self {
+            planes: [
+                plane(1.0, r0),
+                plane(-1.0, r0),
+                plane(1.0, r1),
+                plane(-1.0, r1),
+                plane(1.0, r2),
+                plane(-1.0, r2),
+            ],
+        }
+    }
+
+    pub fn contains_point(
+        &self,
+        point: &Vector3
+    ) -> bool {
+        self.planes.iter().all(|p| p.distance_to_point(point) >= 0.0)
+    }
+
+    pub fn intersects_sphere(
+        &self,
+        sphere: &Sphere
+    ) -> bool {
+        !sphere.is_empty() && 
+            self.planes.iter().all(|p| p.distance_to_point(&sphere.center) >= -sphere.radius)
+    }
+
+    // conservative, boxes near the frustum's corners may pass without intersecting it
+    pub fn intersects_box(
+        &self,
+        bx: &Box3
+    ) -> bool {
+        if bx.is_empty() {
+            return false;
+        }
+
+        self.planes.iter().all(|p| {
+            // the corner furthest along the normal
+            let corner = Vector3::new(
+                if p.normal.x > 0.0 {bx.max.x} else {bx.min.x},
+                if p.normal.y > 0.0 {bx.max.y} else {bx.min.y},
+                if p.normal.z > 0.0 {bx.max.z} else {bx.min.z},
+            );
+            p.distance_to_point(&corner) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Quaternion;
+
+    // 90 degree fov from z = 5 looking down -z, seeing z in [-5, 4] in world space.
+    // at a distance d from the camera the sides are at x, y = +-d
+    fn frustum(
+    ) -> Frustum {
+        let proj = Matrix4::perspective_fov(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 10.0);
+        let view = Matrix4::compose(
+            &Vector3::new(0.0, 0.0, 5.0), 
+            &Quaternion::identity(), 
+            &Vector3::new(1.0, 1.0, 1.0)
+        ).invert();
+        Frustum::from_matrix(&proj.mul(&view))
+    }
+
+    #[test]
+    fn planes_bound_the_view_volume() {
+        let f = frustum();
+        assert!(f.contains_point(&Vector3::new(0.0, 0.0, 0.0)));
+        assert!(f.contains_point(&Vector3::new(4.9, -4.9, 0.0)));
+        assert!(!f.contains_point(&Vector3::new(5.1, 0.0, 0.0)));
+        assert!(!f.contains_point(&Vector3::new(0.0, 5.1, 0.0)));
+        // in front of the near plane, behind the far one and behind the camera
+        assert!(!f.contains_point(&Vector3::new(0.0, 0.0, 4.5)));
+        assert!(!f.contains_point(&Vector3::new(0.0, 0.0, -5.5)));
+        assert!(!f.contains_point(&Vector3::new(0.0, 0.0, 6.0)));
+
+        for p in f.planes {
+            assert!((p.normal.length() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn spheres_inside_outside_and_straddling() {
+        let f = frustum();
+        assert!(f.intersects_sphere(&Sphere::new(Vector3::new(0.0, 0.0, 0.0), 1.0)));
+        assert!(!f.intersects_sphere(&Sphere::new(Vector3::new(0.0, 0.0, 8.0), 1.0)));
+        assert!(!f.intersects_sphere(&Sphere::new(Vector3::new(8.0, 0.0, 0.0), 1.0)));
+        assert!(!f.intersects_sphere(&Sphere::new(Vector3::new(0.0, 0.0, -7.0), 1.0)));
+        // across the right side, the near plane and the far plane
+        assert!(f.intersects_sphere(&Sphere::new(Vector3::new(6.0, 0.0, 0.0), 1.5)));
+        assert!(f.intersects_sphere(&Sphere::new(Vector3::new(0.0, 0.0, 4.5), 1.0)));
+        assert!(f.intersects_sphere(&Sphere::new(Vector3::new(0.0, 0.0, -5.5), 1.0)));
+
+        assert!(!f.intersects_sphere(&Sphere::empty()));
+    }
+
+    #[test]
+    fn boxes_inside_outside_and_straddling() {
+        let f = frustum();
+        let cube = |x: f32, y: f32, z: f32, half: f32| Box3::new(
+            Vector3::new(x - half, y - half, z - half), 
+            Vector3::new(x + half, y + half, z + half)
+        );
+        assert!(f.intersects_box(&cube(0.0, 0.0, 0.0, 1.0)));
+        assert!(!f.intersects_box(&cube(0.0, 0.0, 8.0, 1.0)));
+        assert!(!f.intersects_box(&cube(0.0, -8.0, 0.0, 1.0)));
+        assert!(!f.intersects_box(&cube(0.0, 0.0, -7.0, 1.0)));
+        // across the left side, the near plane and the far plane
+        assert!(f.intersects_box(&cube(-5.5, 0.0, 0.0, 1.0)));
+        assert!(f.intersects_box(&cube(0.0, 0.0, 4.5, 1.0)));
+        assert!(f.intersects_box(&cube(0.0, 0.0, -5.5, 1.0)));
+        // larger than the frustum
+        assert!(f.intersects_box(&cube(0.0, 0.0, 0.0, 50.0)));
+
+        assert!(!f.intersects_box(&Box3::default()));
+    }
+}
@@ -5,13 +5,14 @@ pub mod triangle;
 pub mod box3;
 pub mod capsule;
 pub mod sphere;
+pub mod spherical;
 pub mod plane;
+pub mod frustum;
 pub mod matrix3;
 pub mod matrix4;
 pub mod path3;
 pub mod euler;
 pub mod quaternion;
-#[cfg(feature = "gltf-loader")]
 pub mod octree;
 pub mod misc;
 
@@ -22,11 +23,12 @@ pub use triangle::*;
 pub use box3::*;
 pub use capsule::*;
 pub use sphere::*;
+pub use spherical::*;
 pub use plane::*;
+pub use frustum::*;
 pub use matrix3::*;
 pub use matrix4::*;
 pub use path3::*;
 pub use euler::*;
 pub use quaternion::*;
-#[cfg(feature = "gltf-loader")]
 pub use octree::*;
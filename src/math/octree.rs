@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 use serde::{Serialize, Deserialize};
+use crate::core::{BufferGeometry, BufferGeometryMode, Object3d};
 use super::{
     Triangle, 
     Box3, 
@@ -22,16 +23,53 @@ pub struct Octree {
     pub triangles: Vec<Triangle>,
 }
 
+impl Default for Octree {
+    fn default(
+    ) -> Self {
+        Self::new()
+    }
+}
+
 impl Octree {
+    // empty; add triangles and then build() it
+    pub fn new(
+    ) -> Self {
+        Self {
+            root: OctreeNode::new(Box3::default()),
+            bounds: Box3::default(),
+            triangles: vec![],
+        }
+    }
+
+    // the geometry's triangles moved by the matrix; lines add nothing
+    pub fn from_geometry(
+        geo: &BufferGeometry,
+        matrix: &Matrix4
+    ) -> Self {
+        let mut octree = Self::new();
+        octree.add_geometry(geo, matrix);
+        octree.build();
+        octree
+    }
+
+    // the triangles of the object and its descendants in world space, hidden ones included.
+    // world matrices must be up to date, see ObjectData::update_matrix_world().
+    // fails if a descendant is borrowed elsewhere
+    pub fn from_object(
+        object: &dyn Object3d
+    ) -> Result<Self, String> {
+        let mut octree = Self::new();
+        octree.add_object(object)?;
+        octree.build();
+        Ok(octree)
+    }
+
+    #[cfg(feature = "gltf-loader")]
     pub fn from_gltf<'a>(
         scene: gltf::Scene<'_>,
         buffers: Vec<gltf::buffer::Data>
     ) -> Result<Self, String> {
-        let mut octree = Self {
-            root: OctreeNode::new(Box3::default()),
-            bounds: Box3::default(),
-            triangles: vec![],
-        };
+        let mut octree = Self::new();
         
         for node in scene.nodes() {
             // for every node on scene..
@@ -104,6 +142,57 @@ impl Octree {
         Ok(octree)
     }
 
+    // build() must be called again after adding
+    pub fn add_geometry(
+        &mut self,
+        geo: &BufferGeometry,
+        matrix: &Matrix4
+    ) {
+        if geo.mode != BufferGeometryMode::Triangles {
+            return;
+        }
+
+        let positions = match &geo.positions {
+            Some(positions) => positions,
+            None => return
+        };
+        let indices: Vec<usize> = match &geo.indices {
+            Some(indices) => indices.iter().map(|&i| i as usize).collect(),
+            None => (0..positions.len()).collect()
+        };
+
+        for face in indices.chunks_exact(3) {
+            // faces indexing past the positions are skipped
+            if let (Some(a), Some(b), Some(c)) = (positions.get(face[0]), positions.get(face[1]), positions.get(face[2])) {
+                self.add_triangle(Triangle::new(
+                    a.apply_matrix4(matrix),
+                    b.apply_matrix4(matrix),
+                    c.apply_matrix4(matrix)
+                ));
+            }
+        }
+    }
+
+    // fails if a descendant is borrowed elsewhere, having added part of the tree;
+    // build() must be called again after adding
+    pub fn add_object(
+        &mut self,
+        object: &dyn Object3d
+    ) -> Result<(), String> {
+        let obj = object.get_object();
+        if let Some(geometrical) = object.as_geometrical() {
+            self.add_geometry(geometrical.get_geometry(), obj.get_world_matrix());
+        }
+
+        for child in obj.get_children() {
+            let child = child.try_borrow()
+                .map_err(|_| format!("A child of object {} is borrowed elsewhere", obj.get_id()))?;
+            self.add_object(&*child)?;
+        }
+
+        Ok(())
+    }
+
     pub fn add_triangle( 
         &mut self,
         tri: Triangle
@@ -149,22 +238,22 @@ impl Octree {
 
 		let mut triangle = 0u32;
         let mut position = Vector3::zero();
-        let mut distance = 1.0e-100;
+        let mut distance = f32::INFINITY;
 
 		for tri in triangles {
             if let Some(intersec) = ray.intersecting_triangle(
                 &self.triangles[tri as usize], true) {
 
 				let dist = intersec.sub(&ray.origin).length();
-				if distance > dist {
-					position = intersec.add(&ray.origin);
+				if dist < distance {
+					position = intersec;
 					distance = dist;
 					triangle = tri;
 				}
 			}
 		}
 
-		if distance < 1.0e-100 {
+		if distance.is_finite() {
             Some((
                 distance, 
                 self.triangles[triangle as usize].clone(), 
@@ -175,21 +264,33 @@ impl Octree {
         }
 	}
 
+    // the triangles near the capsule, in index order so results don't depend on hashing
+    pub fn get_capsule_triangles(
+        &self,
+        capsule: &Capsule
+    ) -> Vec<u32> {
+        let mut triangles: Vec<u32> = self.root.get_capsule_triangles(capsule)
+            .into_iter()
+            .collect();
+        triangles.sort_unstable();
+        triangles
+    }
+
+    // pushes the capsule out of every triangle in turn, returning the direction and length of the total push
     pub fn capsule_intersect( 
         &self,
         capsule: &Capsule
     ) -> Option<(Vector3, f32)> {
 
-        let triangles = self.root.get_capsule_triangles(capsule);
+        let triangles = self.get_capsule_triangles(capsule);
 		
 		let mut hit = false;
-        let mut cap = capsule.clone();
+        let mut cap = *capsule;
         for tri in triangles {
-            if let Some(intersec) = capsule.intersecting_triangle(
+            if let Some(intersec) = cap.intersecting_triangle(
                 &self.triangles[tri as usize]) {
 				hit = true;
 				cap = cap.translate(&intersec.0.mul_scalar(intersec.2));
-                break;
 			}
 		}
 
@@ -293,7 +394,7 @@ impl OctreeNode {
                 continue;
             }
 
-			if sub.triangles.len() > 0 {
+			if !sub.triangles.is_empty() {
 				for tri in &sub.triangles {
 					triangles.insert(tri.to_owned());
 				}
@@ -318,7 +419,7 @@ impl OctreeNode {
                 continue;
             }
 
-			if sub.triangles.len() > 0 {
+			if !sub.triangles.is_empty() {
 				for tri in &sub.triangles {
 					triangles.insert(tri.to_owned());
 				}
@@ -332,6 +433,7 @@ impl OctreeNode {
 	}
 }
 
+#[cfg(feature = "gltf-loader")]
 fn traverse_meshes(
     node: &gltf::Node<'_>,
     world_matrix: Option<&Matrix4>,
@@ -359,4 +461,116 @@ fn traverse_meshes(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::Mesh;
+
+    fn quad(
+        octree: &mut Octree,
+        a: Vector3,
+        b: Vector3,
+        c: Vector3,
+        d: Vector3
+    ) {
+        octree.add_triangle(Triangle::new(a, b, c));
+        octree.add_triangle(Triangle::new(a, c, d));
+    }
+
+    // a floor at y = 0 facing up and a wall at x = 1 facing -x
+    fn corner(
+    ) -> Octree {
+        let mut octree = Octree::new();
+        quad(
+            &mut octree,
+            Vector3::new(-5.0, 0.0, -5.0),
+            Vector3::new(-5.0, 0.0, 5.0),
+            Vector3::new(5.0, 0.0, 5.0),
+            Vector3::new(5.0, 0.0, -5.0)
+        );
+        quad(
+            &mut octree,
+            Vector3::new(1.0, 0.0, -5.0),
+            Vector3::new(1.0, 0.0, 5.0),
+            Vector3::new(1.0, 5.0, 5.0),
+            Vector3::new(1.0, 5.0, -5.0)
+        );
+        octree.build();
+        octree
+    }
+
+    fn assert_near(
+        a: f32,
+        b: f32
+    ) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn capsule_intersect_pushes_out_of_every_triangle() {
+        // 0.2 into the floor and 0.2 into the wall
+        let capsule = Capsule::new(Vector3::new(0.7, 0.3, 0.0), Vector3::new(0.7, 1.3, 0.0), 0.5);
+        let (normal, depth) = corner().capsule_intersect(&capsule).unwrap();
+
+        assert_near(depth, 0.2 * 2f32.sqrt());
+        assert_near(normal.x, -0.5f32.sqrt());
+        assert_near(normal.y, 0.5f32.sqrt());
+        assert_near(normal.z, 0.0);
+    }
+
+    #[test]
+    fn capsule_intersect_tests_the_moved_capsule() {
+        // both floor triangles overlap the capsule, but after the first push the second only touches it
+        let capsule = Capsule::new(Vector3::new(0.0, 0.3, 0.0), Vector3::new(0.0, 1.3, 0.0), 0.5);
+        let (normal, depth) = corner().capsule_intersect(&capsule).unwrap();
+
+        assert_near(depth, 0.2);
+        assert_near(normal.y, 1.0);
+    }
+
+    #[test]
+    fn capsule_intersect_misses() {
+        let capsule = Capsule::new(Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 2.0, 0.0), 0.5);
+        assert!(corner().capsule_intersect(&capsule).is_none());
+    }
+
+    #[test]
+    fn from_geometry_skips_out_of_range_indices() {
+        let geo = BufferGeometry::new(
+            BufferGeometryMode::Triangles,
+            Some(vec![0, 1, 2, 0, 2, 3, 0, 3, 4]),
+            Some(vec![
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(1.0, 1.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0)
+            ]),
+            None,
+            None
+        );
+        let octree = Octree::from_geometry(&geo, &Matrix4::identity());
+
+        assert_eq!(octree.triangles.len(), 2);
+        assert_near(octree.bounds.max.x, 1.0);
+        assert_near(octree.bounds.max.y, 1.0);
+    }
+
+    #[test]
+    fn from_object_fails_on_borrowed_children() {
+        let geo = crate::geometry::Box3::new(1.0, 1.0, 1.0);
+        let parent = Mesh::new(&geo);
+        let child = Mesh::new(&geo);
+        child.borrow_mut().get_object_mut().set_position(Vector3::new(2.0, 0.0, 0.0));
+        parent.borrow_mut().add(child.clone()).unwrap();
+        parent.borrow_mut().get_object_mut().update_matrix_world(true);
+
+        let octree = Octree::from_object(&*parent.borrow()).unwrap();
+        assert_eq!(octree.triangles.len(), 24);
+        assert_near(octree.bounds.max.x, 2.5);
+
+        let _held = child.borrow_mut();
+        assert!(Octree::from_object(&*parent.borrow()).is_err());
+    }
+}
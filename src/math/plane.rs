@@ -8,6 +8,16 @@ pub struct Plane {
 }
 
 impl Plane {
+    pub fn new(
+        normal: Vector3,
+        constant: f32
+    ) -> Self {
+        Self {
+            normal,
+            constant,
+        }
+    }
+
     pub fn from_coplanar_points(
         a: &Vector3,
         b: &Vector3,
@@ -28,4 +38,16 @@ impl Plane {
     ) -> f32 {
 		self.normal.dot(point) + self.constant
 	}
+
+    // scales so the normal has unit length, keeping the plane where it is
+    pub fn normalize(
+        &self
+    ) -> Self {
+        let inv_length = 1.0 / self.normal.length();
+
+        Self {
+            normal: self.normal.mul_scalar(inv_length),
+            constant: self.constant * inv_length,
+        }
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use super::{Vector3, Box3, Triangle};
+use super::{Vector3, Box3, Triangle, Sphere};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Ray {
@@ -31,7 +31,7 @@ impl Ray {
         &self,
         scale: f32
     ) -> Vector3 {
-		self.origin.add(&self.direction).mul_scalar(scale)
+		self.origin.add(&self.direction.mul_scalar(scale))
 	}
 
 	pub fn look_at(
@@ -135,6 +135,91 @@ impl Ray {
         }
     }
 
+    // the closest point is the origin for points behind the ray
+    pub fn distance_sq_to_point(
+        &self,
+        point: &Vector3
+    ) -> f32 {
+        let t = point.sub(&self.origin).dot(&self.direction) / self.direction.length_sq();
+        if t < 0.0 {
+            point.distance_to_sq(&self.origin)
+        }
+        else {
+            point.distance_to_sq(&self.at(t))
+        }
+    }
+
+    pub fn intersects_sphere(
+        &self,
+        sphere: &Sphere
+    ) -> bool {
+        !sphere.is_empty() && 
+            self.distance_sq_to_point(&sphere.center) <= sphere.radius * sphere.radius
+    }
+
+    // squared distance between the ray and the segment v0-v1, with the closest points on each.
+    // the direction must be normalized
+    pub fn distance_sq_to_segment(
+        &self,
+        v0: &Vector3,
+        v1: &Vector3
+    ) -> (f32, Vector3, Vector3) {
+        let seg_center = v0.add(v1).mul_scalar(0.5);
+        let seg_dir = v1.sub(v0).normalize();
+        let seg_extent = v0.distance_to(v1) * 0.5;
+        let diff = self.origin.sub(&seg_center);
+
+        let a01 = -self.direction.dot(&seg_dir);
+        let b0 = diff.dot(&self.direction);
+        let b1 = -diff.dot(&seg_dir);
+        let c = diff.length_sq();
+        let det = (1.0 - a01 * a01).abs();
+
+        // s0 is the parameter along the ray, s1 along the segment from its center
+        let clamp_segment = |s: f32| s.clamp(-seg_extent, seg_extent);
+        let (s0, s1, dist_sq) = if det > 0.0 {
+            let s0 = a01 * b1 - b0;
+            let s1 = a01 * b0 - b1;
+            let ext_det = seg_extent * det;
+
+            if s0 >= 0.0 && s1 >= -ext_det && s1 <= ext_det {
+                // the closest points are inside both
+                let (s0, s1) = (s0 / det, s1 / det);
+                (s0, s1, s0 * (s0 + a01 * s1 + 2.0 * b0) + s1 * (a01 * s0 + s1 + 2.0 * b1) + c)
+            }
+            else if s0 >= 0.0 {
+                // past one end of the segment
+                let s1 = if s1 > ext_det {seg_extent} else {-seg_extent};
+                let s0 = (-(a01 * s1 + b0)).max(0.0);
+                (s0, s1, -s0 * s0 + s1 * (s1 + 2.0 * b1) + c)
+            }
+            else if s1 <= -ext_det || s1 > ext_det {
+                // behind the origin and past an end
+                let end = if s1 <= -ext_det {-seg_extent} else {seg_extent};
+                let s0 = (-(a01 * end + b0)).max(0.0);
+                let s1 = if s0 > 0.0 {end} else {clamp_segment(-b1)};
+                (s0, s1, -s0 * s0 + s1 * (s1 + 2.0 * b1) + c)
+            }
+            else {
+                // behind the origin
+                let s1 = clamp_segment(-b1);
+                (0.0, s1, s1 * (s1 + 2.0 * b1) + c)
+            }
+        }
+        else {
+            // parallel
+            let s1 = if a01 > 0.0 {-seg_extent} else {seg_extent};
+            let s0 = (-(a01 * s1 + b0)).max(0.0);
+            (s0, s1, -s0 * s0 + s1 * (s1 + 2.0 * b1) + c)
+        };
+
+        (
+            dist_sq.max(0.0),
+            self.at(s0),
+            seg_center.add(&seg_dir.mul_scalar(s1))
+        )
+    }
+
     pub fn intersecting_triangle(
         &self,
         tri: &Triangle, 
@@ -1,5 +1,5 @@
 use serde::{Serialize, Deserialize};
-use super::Vector3;
+use super::{Vector3, Matrix4};
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Sphere {
@@ -53,4 +53,26 @@ impl Sphere {
 		let radius_sum = self.radius + sphere.radius;
 		sphere.center.distance_to_sq(&self.center) <= (radius_sum * radius_sum)
 	}
+
+    // the radius grows by the largest scale of the matrix, so the sphere still encloses what it did
+    pub fn apply_matrix4(
+        &self,
+        m: &Matrix4
+    ) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+
+        let e = &m.0;
+        let scale_sq = [
+            e[0] * e[0] + e[1] * e[1] + e[2] * e[2],
+            e[4] * e[4] + e[5] * e[5] + e[6] * e[6],
+            e[8] * e[8] + e[9] * e[9] + e[10] * e[10],
+        ];
+
+        Self {
+            center: self.center.apply_matrix4(m),
+            radius: self.radius * scale_sq.iter().cloned().fold(0.0, f32::max).sqrt(),
+        }
+    }
 }
@@ -0,0 +1,63 @@
+use serde::{Serialize, Deserialize};
+use super::Vector3;
+
+const EPS: f32 = 1e-6;
+
+// phi is the polar angle from the y axis, theta the azimuth around it starting at z
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Spherical {
+    pub radius: f32,
+    pub phi: f32,
+    pub theta: f32,
+}
+
+impl Spherical {
+    pub fn new(
+        radius: f32,
+        phi: f32,
+        theta: f32
+    ) -> Self {
+        Self {
+            radius,
+            phi,
+            theta,
+        }
+    }
+
+    pub fn from_vector3(
+        v: &Vector3
+    ) -> Self {
+        let radius = v.length();
+        if radius == 0.0 {
+            return Self::new(0.0, 0.0, 0.0);
+        }
+
+        Self {
+            radius,
+            phi: (v.y / radius).clamp(-1.0, 1.0).acos(),
+            theta: v.x.atan2(v.z),
+        }
+    }
+
+    pub fn to_vector3(
+        &self
+    ) -> Vector3 {
+        let sin_phi_radius = self.phi.sin() * self.radius;
+
+        Vector3::new(
+            sin_phi_radius * self.theta.sin(),
+            self.phi.cos() * self.radius,
+            sin_phi_radius * self.theta.cos()
+        )
+    }
+
+    // keeps phi off the poles, where the azimuth is undefined
+    pub fn make_safe(
+        &self
+    ) -> Self {
+        Self {
+            phi: self.phi.clamp(EPS, std::f32::consts::PI - EPS),
+            ..*self
+        }
+    }
+}
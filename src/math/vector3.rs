@@ -1,5 +1,6 @@
 use std::{f32::consts::PI, ptr::addr_of};
 use serde::{Serialize, Deserialize};
+use crate::camera::Camera;
 use super::{Matrix4, Quaternion, Euler};
 
 pub const RIGHT: Vector3 = Vector3{x: 1.0, y: 0.0, z: 0.0};
@@ -339,6 +340,41 @@ impl Vector3 {
         }
     }
 
+    // world space to normalized device coordinates, in [-1, 1] when inside the view.
+    // the camera's matrices must be up to date, see Updatable::update_matrix()
+    pub fn project_to_ndc(
+        &self,
+        camera: &dyn Camera
+    ) -> Self {
+        let cam = camera.get_data();
+        self.apply_matrix4(&cam.world_matrix_inverse)
+            .apply_matrix4(&cam.proj_matrix)
+    }
+
+    // normalized device coordinates to world space; z is -1 on the near plane and 1 on the far one
+    pub fn unproject(
+        &self,
+        camera: &dyn Camera
+    ) -> Self {
+        let cam = camera.get_data();
+        self.apply_matrix4(&cam.proj_matrix_inverse)
+            .apply_matrix4(cam.obj.get_world_matrix())
+    }
+
+    // applies only the rotation and scale of m, the result is normalized
+    pub fn transform_direction( 
+        &self,
+        m: &Matrix4
+    ) -> Self {
+        let e = &m.0;
+
+        Self {
+            x: e[ 0] * self.x + e[ 4] * self.y + e[ 8] * self.z,
+            y: e[ 1] * self.x + e[ 5] * self.y + e[ 9] * self.z,
+            z: e[ 2] * self.x + e[ 6] * self.y + e[10] * self.z,
+        }.normalize()
+    }
+
     pub fn apply_quaternion( 
         &self,
         q: &Quaternion
@@ -364,4 +400,66 @@ impl Vector3 {
             a 
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        camera::{PerspectiveCamera, OrthographicCamera},
+        core::{Transformable, Updatable}
+    };
+
+    fn assert_near(
+        a: Vector3,
+        b: Vector3
+    ) {
+        assert!(a.distance_to(&b) < 1e-3, "{:?} != {:?}", a, b);
+    }
+
+    // at (1, 2, 10), turned to look down -x
+    fn place<C: Transformable + Updatable>(
+        camera: &mut C
+    ) {
+        camera.set_position(Vector3::new(1.0, 2.0, 10.0));
+        camera.set_rotation(Quaternion::from_axis_and_angle(&Vector3::new(0.0, 1.0, 0.0), PI / 2.0));
+        camera.update_matrix();
+    }
+
+    #[test]
+    fn projected_points_unproject_to_themselves() {
+        let mut perspective = PerspectiveCamera::new(90.0, 2.0, 1.0, 100.0);
+        let mut orthographic = OrthographicCamera::new(-4.0, 4.0, 3.0, -3.0, 1.0, 50.0);
+        place(&mut perspective);
+        place(&mut orthographic);
+
+        let points = [
+            Vector3::new(-4.0, 2.0, 10.0),
+            Vector3::new(-10.0, 3.5, 11.0),
+            Vector3::new(-30.0, -1.0, 7.0),
+        ];
+        for camera in [&perspective as &dyn Camera, &orthographic] {
+            for p in points {
+                let ndc = p.project_to_ndc(camera);
+                assert!(ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 && ndc.z.abs() <= 1.0);
+                assert_near(ndc.unproject(camera), p);
+            }
+        }
+    }
+
+    #[test]
+    fn ndc_spans_the_view() {
+        let mut camera = PerspectiveCamera::new(90.0, 2.0, 1.0, 100.0);
+        place(&mut camera);
+
+        // straight ahead is the center, the near and far planes are at -1 and 1
+        let ahead = Vector3::new(-4.0, 2.0, 10.0).project_to_ndc(&camera);
+        assert_near(Vector3::new(ahead.x, ahead.y, 0.0), Vector3::zero());
+        assert!((Vector3::new(0.0, 2.0, 10.0).project_to_ndc(&camera).z + 1.0).abs() < 1e-4);
+        assert!((Vector3::new(-99.0, 2.0, 10.0).project_to_ndc(&camera).z - 1.0).abs() < 1e-4);
+
+        // the right edge at 5 units ahead is 5 * tan(45) * aspect to the camera's right, which is -z
+        assert_near(Vector3::new(-4.0, 2.0, 0.0).project_to_ndc(&camera), Vector3::new(1.0, 0.0, ahead.z));
+        assert_near(Vector3::new(1.0, 0.0, -1.0).unproject(&camera), Vector3::new(0.0, 2.0, 8.0));
+    }
+}
@@ -0,0 +1,39 @@
+use std::{rc::Rc, cell::RefCell};
+use crate::core::{
+    ObjectData, 
+    Object3d, 
+    Transformable,
+    new_object_ref
+};
+
+// an empty node, only used to organize the hierarchy
+#[derive(Clone)]
+pub struct Group {
+    obj: ObjectData,
+}
+
+impl Group {
+    pub fn new(
+    ) -> Rc<RefCell<Self>> {
+        new_object_ref(Self {
+            obj: ObjectData::new(),
+        })
+    }
+}
+
+impl Object3d for Group {
+    fn get_object(
+        &self
+    ) -> &ObjectData {
+        &self.obj
+    }
+
+    fn get_object_mut(
+        &mut self
+    ) -> &mut ObjectData {
+        &mut self.obj
+    }
+}
+
+impl Transformable for Group {
+}
@@ -8,27 +8,48 @@ use crate::{
         Geometrical, 
         Renderable, 
         GeometricalRenderable, 
-        Transformable
+        Transformable,
+        new_object_ref
     }, 
-    renderer::GlRenderer, 
-    math::Matrix4, 
-    camera::ObjectCamera
+    renderer::Renderer, 
+    camera::ObjectCamera,
+    material::{Material, MaterialRef, MeshBasicMaterial}
 };
 
 #[derive(Clone)]
 pub struct Mesh {
     obj: ObjectData,
     geo: BufferGeometry,
+    material: MaterialRef,
 }
 
 impl Mesh {
+    // white, showing the geometry's vertex colors when it has them
     pub fn new(
         geo: &dyn Geometrical
     ) -> Rc<RefCell<Self>> {
-        Rc::new(RefCell::new(Self {
+        let material = MeshBasicMaterial::new([1.0, 1.0, 1.0]);
+        material.borrow_mut().get_data_mut().vertex_colors = geo.get_geometry().colors.is_some();
+        Self::new_ex(geo, material)
+    }
+
+    // the material can be shared by many meshes
+    pub fn new_ex(
+        geo: &dyn Geometrical,
+        material: MaterialRef
+    ) -> Rc<RefCell<Self>> {
+        new_object_ref(Self {
             obj: ObjectData::new(),
             geo: geo.get_geometry().clone(),
-        }))
+            material,
+        })
+    }
+
+    pub fn set_material(
+        &mut self,
+        material: MaterialRef
+    ) {
+        self.material = material;
     }
 }
 
@@ -44,6 +65,24 @@ impl Object3d for Mesh {
     ) -> &mut ObjectData {
         &mut self.obj
     }
+
+    fn as_renderable_mut(
+        &mut self
+    ) -> Option<&mut dyn Renderable> {
+        Some(self)
+    }
+
+    fn as_geometrical(
+        &self
+    ) -> Option<&dyn Geometrical> {
+        Some(self)
+    }
+
+    fn as_geometrical_mut(
+        &mut self
+    ) -> Option<&mut dyn Geometrical> {
+        Some(self)
+    }
 }
 
 impl Geometrical for Mesh {
@@ -61,21 +100,25 @@ impl Geometrical for Mesh {
 
     fn drop(
         &mut self, 
-        renderer: &GlRenderer
+        renderer: &mut dyn Renderer
     ) {
         (self as &mut dyn Geometrical).destroy(renderer)
     }
 }
 
 impl Renderable for Mesh {
+    fn get_material(
+        &self
+    ) -> &MaterialRef {
+        &self.material
+    }
+
     fn render(
         &mut self, 
-        world_matrix: Option<&Matrix4>,
         camera: &dyn ObjectCamera,
-        renderer: &GlRenderer
+        renderer: &mut dyn Renderer
     ) {
         (self as &mut dyn Renderable).draw(
-            world_matrix, 
             camera,
             renderer
         )
@@ -1,3 +1,5 @@
 pub mod mesh;
+pub mod group;
 
-pub use mesh::*;
\ No newline at end of file
+pub use mesh::*;
+pub use group::*;
\ No newline at end of file
@@ -0,0 +1,95 @@
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    Other(u8),
+}
+
+// keys named after the keycap, anything else is reported as Other. an event's key depends on the
+// keyboard layout, its code is the key at the same place on a us layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    A, B, C, D, E, F, G, H, I, J, K, L, M,
+    N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Num0, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9,
+    Up,
+    Down,
+    Left,
+    Right,
+    Space,
+    Enter,
+    Escape,
+    Tab,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Shift,
+    Ctrl,
+    Alt,
+    Meta,
+    Other,
+}
+
+// pointer coordinates are in window units with the origin at the top-left corner
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Quit,
+    KeyDown {
+        key: Key,
+        // for controls that go by where the keys are, like wasd
+        code: Key,
+        scancode: Option<usize>,
+        modifiers: Modifiers,
+        repeat: bool,
+    },
+    KeyUp {
+        key: Key,
+        code: Key,
+        scancode: Option<usize>,
+        modifiers: Modifiers,
+    },
+    TextInput(String),
+    MouseMove {
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+        modifiers: Modifiers,
+    },
+    MouseDown {
+        button: MouseButton,
+        x: f32,
+        y: f32,
+        modifiers: Modifiers,
+    },
+    MouseUp {
+        button: MouseButton,
+        x: f32,
+        y: f32,
+        modifiers: Modifiers,
+    },
+    MouseWheel {
+        dx: f32,
+        dy: f32,
+        x: f32,
+        y: f32,
+        modifiers: Modifiers,
+    },
+    Resize {
+        width: u32,
+        height: u32,
+    },
+}
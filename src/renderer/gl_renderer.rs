@@ -1,17 +1,76 @@
-use std::collections::HashMap;
+use std::{mem::size_of, slice::from_raw_parts, collections::HashMap};
 use glow::*;
-use crate::math::Matrix4;
-use super::Event;
-use crate::core::BufferGeometry;
+use crate::{
+    math::{Matrix4, Vector3},
+    core::{BufferGeometry, BufferGeometryMode, Renderable, RGB, UV},
+    material::{Material, MaterialKind, ShaderMaterial, Uniform, Side},
+    texture::{Texture, TextureRef, TextureData, Wrapping, Filter},
+    image::Image
+};
+use super::{Renderer, RenderTarget, ShaderProgramType, ShaderError, ShaderStage, Lights};
+use super::lights::{MAX_DIR_LIGHTS, MAX_POINT_LIGHTS, MAX_SPOT_LIGHTS, MAX_HEMI_LIGHTS};
+#[cfg(feature = "window")]
+use super::{Event, SdlWindow};
 
 #[derive(Clone, Debug)]
 #[repr(C)]
 #[allow(dead_code)]
 pub(crate) enum ShaderUniformType {
+    Bool,
+    Int,
+    Float,
+    Vector2,
     Vector3,
+    Vector4,
+    Matrix3,
     Matrix4,
+    // a sampler, the value is the texture unit
+    Texture,
 }
 
+// attribute locations shared by all shaders
+const POSITION_LOCATION: u32 = 0;
+const NORMAL_LOCATION: u32 = 1;
+const COLOR_LOCATION: u32 = 2;
+const UV_LOCATION: u32 = 3;
+const UV2_LOCATION: u32 = 4;
+
+const PHONG_UNIFORMS: &[(&str, ShaderUniformType)] = &[
+    ("color", ShaderUniformType::Vector3),
+    ("emissive", ShaderUniformType::Vector3),
+    ("specular", ShaderUniformType::Vector3),
+    ("shininess", ShaderUniformType::Float),
+    ("opacity", ShaderUniformType::Float),
+    ("vertex_colors", ShaderUniformType::Bool),
+    ("use_map", ShaderUniformType::Bool),
+    ("map", ShaderUniformType::Texture),
+];
+
+// added to the uniforms of every lit program
+const LIGHT_UNIFORMS: &[(&str, ShaderUniformType)] = &[
+    ("ambient_light", ShaderUniformType::Vector3),
+    ("num_dir_lights", ShaderUniformType::Int),
+    ("dir_light_direction", ShaderUniformType::Vector3),
+    ("dir_light_color", ShaderUniformType::Vector3),
+    ("num_point_lights", ShaderUniformType::Int),
+    ("point_light_position", ShaderUniformType::Vector3),
+    ("point_light_color", ShaderUniformType::Vector3),
+    ("point_light_distance", ShaderUniformType::Float),
+    ("point_light_decay", ShaderUniformType::Float),
+    ("num_spot_lights", ShaderUniformType::Int),
+    ("spot_light_position", ShaderUniformType::Vector3),
+    ("spot_light_direction", ShaderUniformType::Vector3),
+    ("spot_light_color", ShaderUniformType::Vector3),
+    ("spot_light_distance", ShaderUniformType::Float),
+    ("spot_light_decay", ShaderUniformType::Float),
+    ("spot_light_cone_cos", ShaderUniformType::Float),
+    ("spot_light_penumbra_cos", ShaderUniformType::Float),
+    ("num_hemi_lights", ShaderUniformType::Int),
+    ("hemi_light_direction", ShaderUniformType::Vector3),
+    ("hemi_light_sky_color", ShaderUniformType::Vector3),
+    ("hemi_light_ground_color", ShaderUniformType::Vector3),
+];
+
 #[derive(Clone, Debug)]
 pub(crate) struct ShaderUniform {
     pub ty: ShaderUniformType,
@@ -20,17 +79,12 @@ pub(crate) struct ShaderUniform {
 
 #[derive(Clone, Debug)]
 pub(crate) struct ShaderUniformLocations {
-    pub projection: UniformLocation,
-    pub model_view: UniformLocation,
+    // custom shaders don't have to use them
+    pub projection: Option<UniformLocation>,
+    pub model_view: Option<UniformLocation>,
     pub other: HashMap<String, ShaderUniform>,
-}
-
-#[repr(C)]
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub(crate) enum ShaderProgramType {
-    PosOnly = 0,
-    PosAndNormal,
-    PosAndColor,
+    // declared by the renderer but not active in the program, usually optimized out
+    pub missing: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -41,32 +95,113 @@ pub(crate) struct ShaderProgram {
 
 const SHADER_SOURCES: &[(ShaderProgramType, &str, &str, &[(&str, ShaderUniformType)])] = &[
     (
-        ShaderProgramType::PosOnly,
-        include_str!("../shaders/pos/vertex.glsl"), 
-        include_str!("../shaders/pos/frag.glsl"),
-        &[("color", ShaderUniformType::Vector3)],
+        ShaderProgramType::Basic,
+        include_str!("../shaders/basic/vertex.glsl"), 
+        include_str!("../shaders/basic/frag.glsl"),
+        &[
+            ("color", ShaderUniformType::Vector3),
+            ("opacity", ShaderUniformType::Float),
+            ("vertex_colors", ShaderUniformType::Bool),
+            ("use_map", ShaderUniformType::Bool),
+            ("map", ShaderUniformType::Texture),
+        ],
+    ),
+    (
+        ShaderProgramType::Normal,
+        include_str!("../shaders/normal/vertex.glsl"), 
+        include_str!("../shaders/normal/frag.glsl"),
+        &[
+            ("opacity", ShaderUniformType::Float),
+        ],
     ),
+    // lambert is phong without specular
     (
-        ShaderProgramType::PosAndNormal,
-        include_str!("../shaders/pos_normal/vertex.glsl"), 
-        include_str!("../shaders/pos_normal/frag.glsl"),
-        &[],
+        ShaderProgramType::Lambert,
+        include_str!("../shaders/phong/vertex.glsl"), 
+        include_str!("../shaders/phong/frag.glsl"),
+        PHONG_UNIFORMS,
     ),
     (
-        ShaderProgramType::PosAndColor,
-        include_str!("../shaders/pos_color/vertex.glsl"), 
-        include_str!("../shaders/pos_color/frag.glsl"),
-        &[],
-    )
+        ShaderProgramType::Phong,
+        include_str!("../shaders/phong/vertex.glsl"), 
+        include_str!("../shaders/phong/frag.glsl"),
+        PHONG_UNIFORMS,
+    ),
+    (
+        ShaderProgramType::Standard,
+        include_str!("../shaders/phong/vertex.glsl"), 
+        include_str!("../shaders/standard/frag.glsl"),
+        &[
+            ("color", ShaderUniformType::Vector3),
+            ("emissive", ShaderUniformType::Vector3),
+            ("metalness", ShaderUniformType::Float),
+            ("roughness", ShaderUniformType::Float),
+            ("opacity", ShaderUniformType::Float),
+            ("vertex_colors", ShaderUniformType::Bool),
+            ("normal_scale", ShaderUniformType::Float),
+            ("occlusion_strength", ShaderUniformType::Float),
+            ("use_map", ShaderUniformType::Bool),
+            ("map", ShaderUniformType::Texture),
+            ("use_metalness_roughness_map", ShaderUniformType::Bool),
+            ("metalness_roughness_map", ShaderUniformType::Texture),
+            ("use_normal_map", ShaderUniformType::Bool),
+            ("normal_map", ShaderUniformType::Texture),
+            ("use_occlusion_map", ShaderUniformType::Bool),
+            ("occlusion_map", ShaderUniformType::Texture),
+            ("use_emissive_map", ShaderUniformType::Bool),
+            ("emissive_map", ShaderUniformType::Texture),
+        ],
+    ),
 ];
 
+// uploaded textures, replaced when the texture's version changes
+struct GlTexture {
+    texture: NativeTexture,
+    version: usize,
+}
+
+struct GlRenderTarget {
+    width: u32,
+    height: u32,
+    framebuffer: NativeFramebuffer,
+    color: NativeTexture,
+    depth: Option<NativeRenderbuffer>,
+}
+
 pub struct GlRenderer {
     pub(crate) gl: Context,
-    window: sdl2::video::Window,
-    events_loop: sdl2::EventPump,
-    timer: sdl2::TimerSubsystem,
-    _context: sdl2::video::GLContext,
+    #[cfg(feature = "window")]
+    window: Option<SdlWindow>,
     pub(crate) programs: HashMap<ShaderProgramType, ShaderProgram>,
+    size: (u32, u32),
+    pixel_ratio: f32,
+    targets: HashMap<usize, GlRenderTarget>,
+    target: Option<usize>,
+    textures: HashMap<usize, GlTexture>,
+    lights: Lights,
+    // custom programs that failed, so they aren't compiled again on every draw
+    failed: HashMap<ShaderProgramType, ShaderError>,
+}
+
+// why a renderer with its own window couldn't be created
+#[cfg(feature = "window")]
+#[derive(Clone, Debug)]
+pub enum GlRendererError {
+    Window(String),
+    Shader(ShaderError),
+}
+
+#[cfg(feature = "window")]
+impl std::fmt::Display for GlRendererError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>
+    ) -> std::fmt::Result {
+        match self {
+            Self::Window(e) => write!(f, "{}", e),
+            Self::Shader(e) => write!(f, "{}", e),
+        }
+    }
 }
 
 impl Drop for GlRenderer {
@@ -79,47 +214,81 @@ impl Drop for GlRenderer {
             for program in self.programs.values() {
                 gl.delete_program(program.program);
             }
+
+            for target in self.targets.values() {
+                Self::delete_target(gl, target);
+            }
+
+            for texture in self.textures.values() {
+                gl.delete_texture(texture.texture);
+            }
         }
     }
 }
 
 impl GlRenderer {
+    /// # Safety
+    /// The window's GL context is made current on the calling thread, and the renderer must
+    /// stay on that thread.
+    #[cfg(feature = "window")]
     pub unsafe fn new(
         title: &str,
         w: u32,
         h: u32
-    ) -> Self {
-        let sdl = sdl2::init().unwrap();
-        let video = sdl.video().unwrap();
-        
-        let gl_attr = video.gl_attr();
-        gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
-        gl_attr.set_context_version(3, 3);
-        
-        let window = video
-            .window(title, w, h)
-            .opengl()
-            .resizable()
-            .build()
-            .unwrap();
-        
-        // NOTE: if removed the GL initialization will crash
-        let _context = window.gl_create_context().unwrap();
+    ) -> Result<Self, GlRendererError> {
+        Self::new_ex(title, w, h, true)
+    }
+
+    /// # Safety
+    /// See new().
+    #[cfg(feature = "window")]
+    pub unsafe fn new_ex(
+        title: &str,
+        w: u32,
+        h: u32,
+        visible: bool
+    ) -> Result<Self, GlRendererError> {
+        let window = SdlWindow::new_ex(title, w, h, visible)
+            .map_err(GlRendererError::Window)?;
+        let (dw, dh) = window.get_drawable_size();
         
-        let gl =
-            Context::from_loader_function(|s| video.gl_get_proc_address(s) as *const _);
+        let mut renderer = Self::from_context(window.create_context(), dw, dh)
+            .map_err(GlRendererError::Shader)?;
+        renderer.pixel_ratio = window.get_pixel_ratio();
+        renderer.window = Some(window);
         
-        let events_loop = sdl.event_pump().unwrap();
-        let timer = sdl.timer().unwrap();
+        Ok(renderer)
+    }
 
+    // presenting is left to the host. w and h are the drawable size in pixels
+    /// # Safety
+    /// The GL context must be current on the calling thread and outlive the renderer.
+    pub unsafe fn from_context(
+        gl: Context,
+        w: u32,
+        h: u32
+    ) -> Result<Self, ShaderError> {
         Self::configure_gl(&gl, w, h);
 
-        let mut programs = HashMap::default();
+        let mut programs: HashMap<ShaderProgramType, ShaderProgram> = HashMap::default();
 
+        // a failed program deletes the ones made before it
         for source in SHADER_SOURCES {
-            let program = Self::create_program(&gl, source.1, source.2);
+            let program = match Self::create_program(&gl, source.1, source.2) {
+                Ok(program) => program,
+                Err(e) => {
+                    for program in programs.values() {
+                        gl.delete_program(program.program);
+                    }
+                    return Err(e);
+                }
+            };
+            let mut uniforms = source.3.to_vec();
+            if source.0.is_lit() {
+                uniforms.extend_from_slice(LIGHT_UNIFORMS);
+            }
             let uniform_locations = Self::get_uniform_locations(
-                &gl, &program, source.3
+                &gl, &program, &uniforms
             );
             programs.insert(
                 source.0.clone(), 
@@ -130,92 +299,268 @@ impl GlRenderer {
             );
         }
 
-        Self {
+        Ok(Self {
             gl,
-            window,
-            events_loop,
-            timer,
-            _context,
-            programs
+            #[cfg(feature = "window")]
+            window: None,
+            programs,
+            size: (w, h),
+            pixel_ratio: 1.0,
+            targets: HashMap::default(),
+            target: None,
+            textures: HashMap::default(),
+            lights: Lights::default(),
+            failed: HashMap::default(),
+        })
+    }
+
+    pub fn get_context(
+        &self
+    ) -> &Context {
+        &self.gl
+    }
+
+    #[cfg(feature = "window")]
+    pub fn get_window(
+        &self
+    ) -> Option<&SdlWindow> {
+        self.window.as_ref()
+    }
+
+    #[cfg(feature = "window")]
+    pub fn get_window_mut(
+        &mut self
+    ) -> Option<&mut SdlWindow> {
+        self.window.as_mut()
+    }
+
+    pub fn get_size(
+        &self
+    ) -> (u32, u32) {
+        (
+            (self.size.0 as f32 / self.pixel_ratio).round() as _,
+            (self.size.1 as f32 / self.pixel_ratio).round() as _
+        )
+    }
+
+    pub fn get_drawable_size(
+        &self
+    ) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn get_pixel_ratio(
+        &self
+    ) -> f32 {
+        self.pixel_ratio
+    }
+
+    // w and h are in window units, the drawable is scaled by the pixel ratio
+    pub fn set_size(
+        &mut self,
+        w: u32,
+        h: u32
+    ) {
+        self.set_drawable_size(
+            (w as f32 * self.pixel_ratio).round() as _,
+            (h as f32 * self.pixel_ratio).round() as _
+        );
+    }
+
+    fn set_drawable_size(
+        &mut self,
+        w: u32,
+        h: u32
+    ) {
+        self.size = (w, h);
+
+        if self.target.is_none() {
+            unsafe {
+                self.gl.viewport(0, 0, w as _, h as _);
+            }
         }
     }
 
+    pub fn set_pixel_ratio(
+        &mut self,
+        ratio: f32
+    ) {
+        let (w, h) = self.get_size();
+        self.pixel_ratio = ratio;
+        self.set_size(w, h);
+    }
+
+    // resize events also update the drawable size and pixel ratio of the owned window
+    #[cfg(feature = "window")]
     pub fn poll_events(
         &mut self
     ) -> Vec<Event> {
-        let mut events = vec![];
-        
-        for event in self.events_loop.poll_iter() {
-            match event {
-                sdl2::event::Event::Quit { .. } => {
-                    events.push(Event::Quit);
-                },
-                sdl2::event::Event::KeyDown {scancode: Some(scancode), ..} => {
-                    events.push(Event::KeyDown(scancode as _));
-                }
-                _ => {}
-            }
+        let Some(window) = &mut self.window else {
+            return vec![];
+        };
+
+        let events = window.poll_events();
+
+        // the window's own drawable size, the scaled window size can be a pixel off
+        if events.iter().any(|e| matches!(e, Event::Resize { .. })) {
+            let (w, h) = window.get_drawable_size();
+            self.pixel_ratio = window.get_pixel_ratio();
+            self.set_drawable_size(w, h);
         }
-        
+
         events
     }
 
+    #[cfg(feature = "window")]
     pub fn ticks(
         &mut self
     ) -> u32 {
-        self.timer.ticks()
+        self.window.as_mut()
+            .map_or(0, |window| window.ticks())
     }
 
+    #[cfg(feature = "window")]
     pub fn delay(
         &mut self,
         ms: u32
     ) {
-        self.timer.delay(ms);
+        if let Some(window) = &mut self.window {
+            window.delay(ms);
+        }
     }
 
     pub fn swap_window(
         &self
     ) {
-        self.window.gl_swap_window();
+        #[cfg(feature = "window")]
+        if let Some(window) = &self.window {
+            window.swap_window();
+        }
     }
 
-    pub fn clear(
-        &self
-    ) {
-        unsafe {
-            self.gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT)
+    // custom programs are compiled on first use and kept for materials with the same sources.
+    // this builds it ahead of time and reports why it failed; a material that fails isn't drawn
+    pub fn compile(
+        &mut self,
+        material: &dyn Material
+    ) -> Result<(), ShaderError> {
+        let ty = ShaderProgramType::from_material(material);
+        if self.programs.contains_key(&ty) {
+            return Ok(());
+        }
+        if let Some(error) = self.failed.get(&ty) {
+            return Err(error.clone());
+        }
+
+        let MaterialKind::Shader(shader) = material.get_kind() else {
+            return Ok(());
+        };
+        match unsafe { Self::create_custom_program(&self.gl, shader) } {
+            Ok(program) => {
+                self.programs.insert(ty, program);
+                Ok(())
+            },
+            Err(error) => {
+                self.failed.insert(ty, error.clone());
+                Err(error)
+            },
         }
     }
 
-    pub fn create_buffers(
-        &self,
-        geo: &mut BufferGeometry
-    ) {
-        let gl = &self.gl;
+    // uniforms the material sets that its program doesn't use, as they were optimized out or never declared
+    pub fn get_missing_uniforms(
+        &mut self,
+        material: &dyn Material
+    ) -> Result<Vec<String>, ShaderError> {
+        self.compile(material)?;
+        
+        let locations = &self.programs[&ShaderProgramType::from_material(material)].uniform_locations;
+        let mut missing = match material.get_kind() {
+            MaterialKind::Shader(shader) => shader.uniforms.keys()
+                .filter(|name| !locations.other.contains_key(*name))
+                .cloned()
+                .collect(),
+            _ => locations.missing.clone(),
+        };
+        missing.sort();
 
-        unsafe {
-            geo.vbo = Some(gl.create_buffer().unwrap());
-            geo.ebo = Some(gl.create_buffer().unwrap());
-            geo.vao = Some(gl.create_vertex_array().unwrap());
+        Ok(missing)
+    }
+
+    // leaves the new framebuffer bound
+    unsafe fn create_target(
+        gl: &Context,
+        target: &RenderTarget
+    ) -> Result<GlRenderTarget, String> {
+        let framebuffer = gl.create_framebuffer()?;
+        let color = match gl.create_texture() {
+            Ok(color) => color,
+            Err(e) => {
+                gl.delete_framebuffer(framebuffer);
+                return Err(e);
+            }
+        };
+
+        gl.bind_framebuffer(FRAMEBUFFER, Some(framebuffer));
+        gl.bind_texture(TEXTURE_2D, Some(color));
+        gl.tex_image_2d(
+            TEXTURE_2D, 
+            0, 
+            RGBA8 as _, 
+            target.width as _, 
+            target.height as _, 
+            0, 
+            RGBA, 
+            UNSIGNED_BYTE, 
+            None
+        );
+        gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR as _);
+        gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR as _);
+        gl.framebuffer_texture_2d(FRAMEBUFFER, COLOR_ATTACHMENT0, TEXTURE_2D, Some(color), 0);
+        gl.bind_texture(TEXTURE_2D, None);
+
+        let depth = if target.depth_buffer {
+            let depth = match gl.create_renderbuffer() {
+                Ok(depth) => depth,
+                Err(e) => {
+                    gl.bind_framebuffer(FRAMEBUFFER, None);
+                    gl.delete_texture(color);
+                    gl.delete_framebuffer(framebuffer);
+                    return Err(e);
+                }
+            };
+            gl.bind_renderbuffer(RENDERBUFFER, Some(depth));
+            gl.renderbuffer_storage(
+                RENDERBUFFER, 
+                DEPTH_COMPONENT24, 
+                target.width as _, 
+                target.height as _
+            );
+            gl.framebuffer_renderbuffer(FRAMEBUFFER, DEPTH_ATTACHMENT, RENDERBUFFER, Some(depth));
+            gl.bind_renderbuffer(RENDERBUFFER, None);
+            Some(depth)
         }
+        else {
+            None
+        };
+
+        Ok(GlRenderTarget {
+            width: target.width,
+            height: target.height,
+            framebuffer,
+            color,
+            depth,
+        })
     }
 
-    pub fn delete_buffers(
-        &self,
-        geo: &BufferGeometry
+    unsafe fn delete_target(
+        gl: &Context,
+        target: &GlRenderTarget
     ) {
-        let gl = &self.gl;
-
-        unsafe {
-            if let Some(vao) = geo.vao {
-                gl.delete_vertex_array(vao);
-            }
-            if let Some(ebo) = geo.ebo {
-                gl.delete_buffer(ebo);
-            }
-            if let Some(vbo) = geo.vbo {
-                gl.delete_buffer(vbo);
-            }
+        gl.delete_framebuffer(target.framebuffer);
+        gl.delete_texture(target.color);
+        if let Some(depth) = target.depth {
+            gl.delete_renderbuffer(depth);
         }
     }
 
@@ -223,74 +568,152 @@ impl GlRenderer {
         gl: &glow::Context,
         vertex_shader_source: &str,
         fragment_shader_source: &str,
-    ) -> NativeProgram {
-        let program = gl.create_program().expect("Cannot create program");
+    ) -> Result<NativeProgram, ShaderError> {
+        let program = gl.create_program()
+            .map_err(|e| ShaderError::new(ShaderStage::Link, &e, None))?;
     
         let shader_sources = [
-            (VERTEX_SHADER, vertex_shader_source),
-            (FRAGMENT_SHADER, fragment_shader_source),
+            (VERTEX_SHADER, ShaderStage::Vertex, vertex_shader_source),
+            (FRAGMENT_SHADER, ShaderStage::Fragment, fragment_shader_source),
         ];
     
         let mut shaders = Vec::with_capacity(shader_sources.len());
+        let mut error = None;
     
-        for (shader_type, shader_source) in shader_sources.iter() {
-            let shader = gl
-                .create_shader(*shader_type)
-                .expect("Cannot create shader");
+        for (shader_type, stage, shader_source) in shader_sources {
+            let shader = match gl.create_shader(shader_type) {
+                Ok(shader) => shader,
+                Err(e) => {
+                    error = Some(ShaderError::new(stage, &e, None));
+                    break;
+                },
+            };
+            shaders.push(shader);
+
             gl.shader_source(shader, shader_source);
             gl.compile_shader(shader);
             if !gl.get_shader_compile_status(shader) {
-                panic!("{}", gl.get_shader_info_log(shader));
+                error = Some(ShaderError::new(stage, &gl.get_shader_info_log(shader), Some(shader_source)));
+                break;
             }
             gl.attach_shader(program, shader);
-            shaders.push(shader);
         }
 
-        gl.link_program(program);
-        if !gl.get_program_link_status(program) {
-            panic!("{}", gl.get_program_info_log(program));
+        if error.is_none() {
+            gl.link_program(program);
+            if !gl.get_program_link_status(program) {
+                error = Some(ShaderError::new(ShaderStage::Link, &gl.get_program_info_log(program), None));
+            }
         }
 
         for shader in shaders {
             gl.detach_shader(program, shader);
             gl.delete_shader(shader);
         }
-    
-        program
+
+        match error {
+            Some(error) => {
+                gl.delete_program(program);
+                Err(error)
+            },
+            None => Ok(program),
+        }
     }
 
+    // uniforms the compiler dropped have no location, they're skipped instead of failing
     unsafe fn get_uniform_locations(
         gl: &Context,
         program: &NativeProgram,
         uniforms: &[(&str, ShaderUniformType)]
-
     ) -> ShaderUniformLocations {
-        // find uniforms present in all shaders
-        let projection_loc = gl.get_uniform_location(*program, "projection").unwrap();
-        let proj = Matrix4::identity();
-        gl.uniform_matrix_4_f32_slice(Some(&projection_loc), false, proj.to_slice());
+        let mut missing = vec![];
 
-        let model_view_loc = gl.get_uniform_location(*program, "model_view").unwrap();
-        let view = Matrix4::identity();
-        gl.uniform_matrix_4_f32_slice(Some(&model_view_loc), false, view.to_slice());
+        // find uniforms present in all shaders
+        let projection = gl.get_uniform_location(*program, "projection");
+        let model_view = gl.get_uniform_location(*program, "model_view");
+        for (name, location) in [("projection", &projection), ("model_view", &model_view)] {
+            if location.is_none() {
+                missing.push(name.to_string());
+            }
+        }
 
         // find shader-specific uniforms
         let mut other = HashMap::default();
         for uni in uniforms {
-            let location = gl.get_uniform_location(*program, uni.0).unwrap();
-            other.insert(uni.0.to_string(), ShaderUniform{
-                ty: uni.1.clone(),
-                location,
-            });
+            match gl.get_uniform_location(*program, uni.0) {
+                Some(location) => {
+                    other.insert(uni.0.to_string(), ShaderUniform{
+                        ty: uni.1.clone(),
+                        location,
+                    });
+                },
+                None => {
+                    missing.push(uni.0.to_string());
+                },
+            }
         }
         
         ShaderUniformLocations {
-            projection: projection_loc,
-            model_view: model_view_loc,
-            other
+            projection,
+            model_view,
+            other,
+            missing,
+        }
+    }
+
+    // custom shaders declare their own uniforms, so every active one is looked up
+    unsafe fn get_active_uniform_locations(
+        gl: &Context,
+        program: &NativeProgram
+    ) -> ShaderUniformLocations {
+        let mut other = HashMap::default();
+        for index in 0..gl.get_active_uniforms(*program) {
+            let Some(active) = gl.get_active_uniform(*program, index) else {
+                continue;
+            };
+            let ty = match active.utype {
+                BOOL => ShaderUniformType::Bool,
+                INT => ShaderUniformType::Int,
+                FLOAT => ShaderUniformType::Float,
+                FLOAT_VEC2 => ShaderUniformType::Vector2,
+                FLOAT_VEC3 => ShaderUniformType::Vector3,
+                FLOAT_VEC4 => ShaderUniformType::Vector4,
+                FLOAT_MAT3 => ShaderUniformType::Matrix3,
+                FLOAT_MAT4 => ShaderUniformType::Matrix4,
+                SAMPLER_2D => ShaderUniformType::Texture,
+                _ => continue,
+            };
+            // arrays are reported by their first element
+            let name = active.name.trim_end_matches("[0]").to_string();
+            if let Some(location) = gl.get_uniform_location(*program, &name) {
+                other.insert(name, ShaderUniform {
+                    ty,
+                    location,
+                });
+            }
+        }
+
+        ShaderUniformLocations {
+            projection: other.remove("projection").map(|u| u.location),
+            model_view: other.remove("model_view").map(|u| u.location),
+            other,
+            missing: vec![],
         }
     }
 
+    unsafe fn create_custom_program(
+        gl: &Context,
+        shader: &ShaderMaterial
+    ) -> Result<ShaderProgram, ShaderError> {
+        let program = Self::create_program(gl, &shader.vertex_shader, &shader.fragment_shader)?;
+        let uniform_locations = Self::get_active_uniform_locations(gl, &program);
+
+        Ok(ShaderProgram {
+            program,
+            uniform_locations,
+        })
+    }
+
     unsafe fn configure_gl(
         gl: &Context,
         w: u32,
@@ -303,4 +726,905 @@ impl GlRenderer {
         gl.enable(MULTISAMPLE);
         gl.clear_color(0.0, 0.0, 0.0, 1.0);
    }
+}
+impl GlRenderer {
+    // fails when the gl objects can't be created
+    unsafe fn upload(
+        &mut self,
+        geo: &mut BufferGeometry
+    ) -> Result<(), String> {
+        if geo.vbo.is_some() {
+            return Ok(());
+        }
+
+        Self::create_gl_buffers(&self.gl, geo)?;
+
+        let gl = &self.gl;
+
+        // vbos
+        Self::upload_vertices(gl, geo);
+
+        // ebo
+        Self::upload_indices(gl, geo)?;
+
+        // vao
+        Self::config_vao(gl, geo);
+
+        // unbind
+        gl.bind_vertex_array(None);
+        gl.bind_buffer(ELEMENT_ARRAY_BUFFER, None);
+        gl.bind_buffer(ARRAY_BUFFER, None);
+
+        Ok(())
+    }
+
+    // all or none of them are created
+    unsafe fn create_gl_buffers(
+        gl: &Context,
+        geo: &mut BufferGeometry
+    ) -> Result<(), String> {
+        let vbo = gl.create_buffer()?;
+        let ebo = match geo.indices {
+            Some(_) => match gl.create_buffer() {
+                Ok(ebo) => Some(ebo),
+                Err(e) => {
+                    gl.delete_buffer(vbo);
+                    return Err(e);
+                }
+            },
+            None => None,
+        };
+        let vao = match gl.create_vertex_array() {
+            Ok(vao) => vao,
+            Err(e) => {
+                gl.delete_buffer(vbo);
+                if let Some(ebo) = ebo {
+                    gl.delete_buffer(ebo);
+                }
+                return Err(e);
+            }
+        };
+
+        geo.vbo = Some(vbo);
+        geo.ebo = ebo;
+        geo.vao = Some(vao);
+        Ok(())
+    }
+
+    unsafe fn config_vao(
+        gl: &Context,
+        geo: &BufferGeometry
+    ) {
+        let sizes = geo.get_attribute_sizes();
+        let mut offset = 0;
+
+        if sizes.total > 0 {
+            gl.bind_vertex_array(geo.vao);
+
+            if sizes.positions > 0 {
+                gl.vertex_attrib_pointer_f32(
+                    POSITION_LOCATION,
+                    3,
+                    FLOAT,
+                    false,
+                    size_of::<Vector3>() as _,
+                    offset as _
+                );
+                offset += sizes.positions;
+            }
+
+            if sizes.normals > 0 {
+                gl.vertex_attrib_pointer_f32(
+                    NORMAL_LOCATION,
+                    3,
+                    FLOAT,
+                    false,
+                    size_of::<Vector3>() as _,
+                    offset as _
+                );
+                offset += sizes.normals;
+            }
+
+            if sizes.colors > 0 {
+                gl.vertex_attrib_pointer_f32(
+                    COLOR_LOCATION,
+                    3,
+                    FLOAT,
+                    false,
+                    size_of::<RGB>() as _,
+                    offset as _
+                );
+                offset += sizes.colors;
+            }
+
+            if sizes.uvs > 0 {
+                gl.vertex_attrib_pointer_f32(
+                    UV_LOCATION,
+                    2,
+                    FLOAT,
+                    false,
+                    size_of::<UV>() as _,
+                    offset as _
+                );
+                // without a second set, the first one is read in its place
+                if sizes.uvs2 == 0 {
+                    gl.vertex_attrib_pointer_f32(
+                        UV2_LOCATION,
+                        2,
+                        FLOAT,
+                        false,
+                        size_of::<UV>() as _,
+                        offset as _
+                    );
+                }
+                offset += sizes.uvs;
+            }
+
+            if sizes.uvs2 > 0 {
+                gl.vertex_attrib_pointer_f32(
+                    UV2_LOCATION,
+                    2,
+                    FLOAT,
+                    false,
+                    size_of::<UV>() as _,
+                    offset as _
+                );
+            }
+        }
+    }
+
+    // the ebo only exists while there are indices
+    unsafe fn upload_indices(
+        gl: &Context,
+        geo: &mut BufferGeometry
+    ) -> Result<(), String> {
+        if geo.indices.is_none() {
+            if let Some(ebo) = geo.ebo.take() {
+                gl.delete_buffer(ebo);
+            }
+        }
+        else if geo.ebo.is_none() {
+            geo.ebo = Some(gl.create_buffer()?);
+        }
+
+        if let Some(indices) = &geo.indices {
+            let buffer = from_raw_parts(
+                indices.as_ptr() as *const u8,
+                size_of::<u32>() * indices.len()
+            );
+
+            gl.bind_buffer(ELEMENT_ARRAY_BUFFER, geo.ebo);
+            gl.buffer_data_u8_slice(ELEMENT_ARRAY_BUFFER, buffer, STATIC_DRAW);
+        }
+
+        Ok(())
+    }
+
+    unsafe fn upload_vertices(
+        gl: &Context,
+        geo: &mut BufferGeometry
+    ) {
+        let sizes = geo.get_attribute_sizes();
+
+        if sizes.total > 0 {
+            gl.bind_buffer(ARRAY_BUFFER, geo.vbo);
+            gl.buffer_data_size(ARRAY_BUFFER, sizes.total as _, STATIC_DRAW);
+
+            let mut offset = 0;
+            if let Some(positions) = &geo.positions {
+                let buffer = from_raw_parts(
+                    positions.as_ptr() as *const u8,
+                    sizes.positions
+                );
+                gl.buffer_sub_data_u8_slice(ARRAY_BUFFER, offset as _, buffer);
+                offset += sizes.positions;
+            }
+
+            if let Some(normals) = &geo.normals {
+                let buffer = from_raw_parts(
+                    normals.as_ptr() as *const u8,
+                    sizes.normals
+                );
+                gl.buffer_sub_data_u8_slice(ARRAY_BUFFER, offset as _, buffer);
+                offset += sizes.normals;
+            }
+
+            if let Some(colors) = &geo.colors {
+                let buffer = from_raw_parts(
+                    colors.as_ptr() as *const u8,
+                    sizes.colors
+                );
+
+                gl.buffer_sub_data_u8_slice(ARRAY_BUFFER, offset as _, buffer);
+                offset += sizes.colors;
+            }
+
+            if let Some(uvs) = &geo.uvs {
+                let buffer = from_raw_parts(
+                    uvs.as_ptr() as *const u8,
+                    sizes.uvs
+                );
+
+                gl.buffer_sub_data_u8_slice(ARRAY_BUFFER, offset as _, buffer);
+                offset += sizes.uvs;
+            }
+
+            if let Some(uvs2) = &geo.uvs2 {
+                let buffer = from_raw_parts(
+                    uvs2.as_ptr() as *const u8,
+                    sizes.uvs2
+                );
+
+                gl.buffer_sub_data_u8_slice(ARRAY_BUFFER, offset as _, buffer);
+            }
+
+            geo.dirt = false;
+        }
+    }
+
+    fn get_locations(
+        geo: &BufferGeometry
+    ) -> Vec<u32> {
+        let mut locations = vec![];
+        if geo.positions.is_some() {
+            locations.push(POSITION_LOCATION);
+        }
+        if geo.normals.is_some() {
+            locations.push(NORMAL_LOCATION);
+        }
+        if geo.colors.is_some() {
+            locations.push(COLOR_LOCATION);
+        }
+        if geo.uvs.is_some() {
+            locations.push(UV_LOCATION);
+        }
+        if geo.uvs.is_some() || geo.uvs2.is_some() {
+            locations.push(UV2_LOCATION);
+        }
+        locations
+    }
+
+    unsafe fn bind(
+        &self,
+        geo: &BufferGeometry
+    ) {
+        let gl = &self.gl;
+
+        gl.bind_vertex_array(geo.vao);
+
+        for location in Self::get_locations(geo) {
+            gl.enable_vertex_attrib_array(location);
+        }
+
+        gl.bind_buffer(ARRAY_BUFFER, geo.vbo);
+        gl.bind_buffer(ELEMENT_ARRAY_BUFFER, geo.ebo);
+    }
+
+    unsafe fn unbind(
+        &self,
+        geo: &BufferGeometry
+    ) {
+        let gl = &self.gl;
+
+        gl.bind_buffer(ELEMENT_ARRAY_BUFFER, None);
+        gl.bind_buffer(ARRAY_BUFFER, None);
+
+        for location in Self::get_locations(geo) {
+            gl.disable_vertex_attrib_array(location);
+        }
+
+        gl.bind_vertex_array(None);
+
+        gl.use_program(None);
+    }
+
+    unsafe fn apply_material_state(
+        &self,
+        material: &dyn Material
+    ) {
+        let gl = &self.gl;
+        let mat = material.get_data();
+
+        match mat.side {
+            Side::Front => {
+                gl.enable(CULL_FACE);
+                gl.cull_face(BACK);
+            },
+            Side::Back => {
+                gl.enable(CULL_FACE);
+                gl.cull_face(FRONT);
+            },
+            Side::Double => {
+                gl.disable(CULL_FACE);
+            },
+        }
+
+        if mat.depth_test {
+            gl.enable(DEPTH_TEST);
+        }
+        else {
+            gl.disable(DEPTH_TEST);
+        }
+        gl.depth_mask(mat.depth_write);
+
+        if mat.is_transparent() {
+            gl.enable(BLEND);
+            gl.blend_func_separate(SRC_ALPHA, ONE_MINUS_SRC_ALPHA, ONE, ONE_MINUS_SRC_ALPHA);
+        }
+        else {
+            gl.disable(BLEND);
+        }
+
+        // gles has no polygon mode, draw() outlines the triangles itself there
+        if !gl.version().is_embedded {
+            gl.polygon_mode(FRONT_AND_BACK, if mat.wireframe {LINE} else {FILL});
+        }
+    }
+
+    fn get_uniform_values(
+        &self,
+        material: &dyn Material,
+        geo: &BufferGeometry
+    ) -> HashMap<String, Vec<f32>> {
+        let mat = material.get_data();
+        let vertex_colors = mat.vertex_colors && geo.colors.is_some();
+        
+        let mut values = HashMap::<String, Vec<f32>>::default();
+        values.insert("opacity".to_string(), vec![mat.opacity]);
+        values.insert("vertex_colors".to_string(), vec![if vertex_colors {1.0} else {0.0}]);
+
+        for (unit, (name, texture)) in Self::get_textures(material).iter().enumerate() {
+            values.insert(name.to_string(), vec![unit as f32]);
+            values.insert(format!("use_{}", name), vec![if texture.is_some() {1.0} else {0.0}]);
+        }
+
+        match material.get_kind() {
+            MaterialKind::Basic(basic) => {
+                values.insert("color".to_string(), basic.color.to_vec());
+            },
+            MaterialKind::Normal(_) => {
+            },
+            MaterialKind::Lambert(lambert) => {
+                values.insert("color".to_string(), lambert.color.to_vec());
+                values.insert("emissive".to_string(), lambert.emissive.to_vec());
+                values.insert("specular".to_string(), vec![0.0; 3]);
+                values.insert("shininess".to_string(), vec![1.0]);
+                self.add_light_values(&mut values);
+            },
+            MaterialKind::Phong(phong) => {
+                values.insert("color".to_string(), phong.color.to_vec());
+                values.insert("emissive".to_string(), phong.emissive.to_vec());
+                values.insert("specular".to_string(), phong.specular.to_vec());
+                values.insert("shininess".to_string(), vec![phong.shininess]);
+                self.add_light_values(&mut values);
+            },
+            MaterialKind::Standard(standard) => {
+                values.insert("color".to_string(), standard.color.to_vec());
+                values.insert("emissive".to_string(), standard.emissive.to_vec());
+                values.insert("metalness".to_string(), vec![standard.metalness]);
+                values.insert("roughness".to_string(), vec![standard.roughness]);
+                values.insert("normal_scale".to_string(), vec![standard.normal_scale]);
+                values.insert("occlusion_strength".to_string(), vec![standard.occlusion_strength]);
+                self.add_light_values(&mut values);
+            },
+            MaterialKind::Shader(shader) => {
+                if shader.lights {
+                    self.add_light_values(&mut values);
+                }
+                for (name, uniform) in &shader.uniforms {
+                    if !matches!(uniform, Uniform::Texture(_)) {
+                        values.insert(name.clone(), uniform.to_vec());
+                    }
+                }
+            },
+        }
+
+        values
+    }
+
+    // sampler uniform names, in texture unit order
+    fn get_textures(
+        material: &dyn Material
+    ) -> Vec<(String, Option<TextureRef>)> {
+        let textures = match material.get_kind() {
+            MaterialKind::Basic(basic) => vec![
+                ("map", basic.map.clone()),
+            ],
+            MaterialKind::Normal(_) => vec![],
+            MaterialKind::Lambert(lambert) => vec![
+                ("map", lambert.map.clone()),
+            ],
+            MaterialKind::Phong(phong) => vec![
+                ("map", phong.map.clone()),
+            ],
+            MaterialKind::Standard(standard) => vec![
+                ("map", standard.map.clone()),
+                ("metalness_roughness_map", standard.metalness_roughness_map.clone()),
+                ("normal_map", standard.normal_map.clone()),
+                ("occlusion_map", standard.occlusion_map.clone()),
+                ("emissive_map", standard.emissive_map.clone()),
+            ],
+            MaterialKind::Shader(shader) => {
+                return shader.get_textures();
+            },
+        };
+
+        textures.into_iter()
+            .map(|(name, texture)| (name.to_string(), texture))
+            .collect()
+    }
+
+    // uploads the texture on first use and again after its version changed
+    unsafe fn get_texture(
+        &mut self,
+        texture: &Texture
+    ) -> Result<NativeTexture, String> {
+        if let Some(cached) = self.textures.get(&texture.id) {
+            if cached.version == texture.version {
+                return Ok(cached.texture);
+            }
+        }
+
+        let native = match self.textures.get(&texture.id) {
+            Some(cached) => cached.texture,
+            None => self.gl.create_texture()?,
+        };
+        Self::upload_texture(&self.gl, native, texture);
+        
+        self.textures.insert(texture.id, GlTexture {
+            texture: native,
+            version: texture.version,
+        });
+
+        Ok(native)
+    }
+
+    unsafe fn upload_texture(
+        gl: &Context,
+        native: NativeTexture,
+        texture: &Texture
+    ) {
+        let (width, height) = (texture.get_width(), texture.get_height());
+        let (internal_format, ty, mut pixels) = match texture.get_data() {
+            TextureData::Rgba8(data) => (RGBA8, UNSIGNED_BYTE, data.clone()),
+            TextureData::Float(data) => (
+                RGBA32F, 
+                FLOAT, 
+                from_raw_parts(data.as_ptr() as *const u8, data.len() * size_of::<f32>()).to_vec()
+            ),
+        };
+
+        // gl expects the bottom row first
+        if texture.flip_y && height > 0 {
+            let row = pixels.len() / height as usize;
+            pixels = pixels.chunks_exact(row)
+                .rev()
+                .flatten()
+                .cloned()
+                .collect();
+        }
+
+        gl.bind_texture(TEXTURE_2D, Some(native));
+        gl.pixel_store_i32(UNPACK_ALIGNMENT, 1);
+        gl.tex_image_2d(
+            TEXTURE_2D, 
+            0, 
+            internal_format as _, 
+            width as _, 
+            height as _, 
+            0, 
+            RGBA, 
+            ty, 
+            Some(&pixels)
+        );
+
+        let wrap = |mode: Wrapping| match mode {
+            Wrapping::Repeat => REPEAT,
+            Wrapping::ClampToEdge => CLAMP_TO_EDGE,
+            Wrapping::MirroredRepeat => MIRRORED_REPEAT,
+        };
+        gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_S, wrap(texture.wrap_s) as _);
+        gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_T, wrap(texture.wrap_t) as _);
+
+        // a mipmapped filter without mipmaps would leave the texture incomplete
+        let mipmaps = texture.generate_mipmaps && texture.min_filter.uses_mipmaps();
+        let min_filter = match texture.min_filter {
+            _ if !mipmaps => if texture.min_filter.is_linear() {LINEAR} else {NEAREST},
+            Filter::NearestMipmapNearest => NEAREST_MIPMAP_NEAREST,
+            Filter::LinearMipmapNearest => LINEAR_MIPMAP_NEAREST,
+            Filter::NearestMipmapLinear => NEAREST_MIPMAP_LINEAR,
+            _ => LINEAR_MIPMAP_LINEAR,
+        };
+        let mag_filter = if texture.mag_filter.is_linear() {LINEAR} else {NEAREST};
+        gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MIN_FILTER, min_filter as _);
+        gl.tex_parameter_i32(TEXTURE_2D, TEXTURE_MAG_FILTER, mag_filter as _);
+
+        if mipmaps {
+            gl.generate_mipmap(TEXTURE_2D);
+        }
+
+        gl.bind_texture(TEXTURE_2D, None);
+    }
+
+    fn add_light_values(
+        &self,
+        values: &mut HashMap<String, Vec<f32>>
+    ) {
+        let lights = &self.lights;
+        let mut set = |name: &str, value: Vec<f32>| {
+            values.insert(name.to_string(), value);
+        };
+
+        set("ambient_light", lights.ambient.to_vec());
+
+        let dir = &lights.directional[..lights.directional.len().min(MAX_DIR_LIGHTS)];
+        set("num_dir_lights", vec![dir.len() as f32]);
+        set("dir_light_direction", dir.iter().flat_map(|l| l.direction.to_slice()).collect());
+        set("dir_light_color", dir.iter().flat_map(|l| l.color).collect());
+
+        let point = &lights.point[..lights.point.len().min(MAX_POINT_LIGHTS)];
+        set("num_point_lights", vec![point.len() as f32]);
+        set("point_light_position", point.iter().flat_map(|l| l.position.to_slice()).collect());
+        set("point_light_color", point.iter().flat_map(|l| l.color).collect());
+        set("point_light_distance", point.iter().map(|l| l.distance).collect());
+        set("point_light_decay", point.iter().map(|l| l.decay).collect());
+
+        let spot = &lights.spot[..lights.spot.len().min(MAX_SPOT_LIGHTS)];
+        set("num_spot_lights", vec![spot.len() as f32]);
+        set("spot_light_position", spot.iter().flat_map(|l| l.position.to_slice()).collect());
+        set("spot_light_direction", spot.iter().flat_map(|l| l.direction.to_slice()).collect());
+        set("spot_light_color", spot.iter().flat_map(|l| l.color).collect());
+        set("spot_light_distance", spot.iter().map(|l| l.distance).collect());
+        set("spot_light_decay", spot.iter().map(|l| l.decay).collect());
+        set("spot_light_cone_cos", spot.iter().map(|l| l.cone_cos).collect());
+        set("spot_light_penumbra_cos", spot.iter().map(|l| l.penumbra_cos).collect());
+
+        let hemi = &lights.hemisphere[..lights.hemisphere.len().min(MAX_HEMI_LIGHTS)];
+        set("num_hemi_lights", vec![hemi.len() as f32]);
+        set("hemi_light_direction", hemi.iter().flat_map(|l| l.direction.to_slice()).collect());
+        set("hemi_light_sky_color", hemi.iter().flat_map(|l| l.sky_color).collect());
+        set("hemi_light_ground_color", hemi.iter().flat_map(|l| l.ground_color).collect());
+    }
+
+    // fails when the gl objects can't be created, and the draw is skipped
+    unsafe fn update(
+        &mut self,
+        geo: &mut BufferGeometry,
+        material: &dyn Material,
+        projection: &Matrix4,
+        model_view: &Matrix4,
+        program: &ShaderProgram
+    ) -> Result<(), String> {
+        self.upload(geo)?;
+
+        let gl = &self.gl;
+
+        if geo.dirt {
+            // attributes and indices may have been added, removed or resized
+            Self::upload_vertices(gl, geo);
+            Self::upload_indices(gl, geo)?;
+            Self::config_vao(gl, geo);
+            gl.bind_vertex_array(None);
+            gl.bind_buffer(ELEMENT_ARRAY_BUFFER, None);
+            gl.bind_buffer(ARRAY_BUFFER, None);
+            geo.dirt = false;
+        }
+
+        // update matrices
+        gl.uniform_matrix_4_f32_slice(
+            program.uniform_locations.projection.as_ref(),
+            false,
+            projection.to_slice()
+        );
+
+        gl.uniform_matrix_4_f32_slice(
+            program.uniform_locations.model_view.as_ref(),
+            false,
+            model_view.to_slice()
+        );
+
+        // bind the material's textures to consecutive units
+        for (unit, (_, texture)) in Self::get_textures(material).iter().enumerate() {
+            if let Some(texture) = texture {
+                let texture = match self.get_texture(&texture.borrow()) {
+                    Ok(texture) => texture,
+                    Err(e) => {
+                        self.gl.active_texture(TEXTURE0);
+                        return Err(e);
+                    }
+                };
+                self.gl.active_texture(TEXTURE0 + unit as u32);
+                self.gl.bind_texture(TEXTURE_2D, Some(texture));
+            }
+        }
+        self.gl.active_texture(TEXTURE0);
+
+        // update uniforms depending on the shader used
+        let uniform_values = self.get_uniform_values(material, geo);
+        let gl = &self.gl;
+
+        for (name, uniform) in &program.uniform_locations.other {
+            // custom shaders may declare uniforms the material doesn't set,
+            // and arrays can be empty when there are no lights of a kind
+            let Some(values) = uniform_values.get(name) else {
+                continue;
+            };
+            if values.is_empty() {
+                continue;
+            }
+            
+            match uniform.ty {
+                ShaderUniformType::Bool | ShaderUniformType::Int | ShaderUniformType::Texture => {
+                    let values = values.iter().map(|v| *v as i32).collect::<Vec<_>>();
+                    gl.uniform_1_i32_slice(
+                        Some(&uniform.location), &values
+                    );
+                },
+                ShaderUniformType::Float => {
+                    gl.uniform_1_f32_slice(
+                        Some(&uniform.location), values
+                    );
+                },
+                ShaderUniformType::Vector2 => {
+                    gl.uniform_2_f32_slice(
+                        Some(&uniform.location), values
+                    );
+                },
+                ShaderUniformType::Vector3 => {
+                    gl.uniform_3_f32_slice(
+                        Some(&uniform.location), values
+                    );
+                },
+                ShaderUniformType::Vector4 => {
+                    gl.uniform_4_f32_slice(
+                        Some(&uniform.location), values
+                    );
+                },
+                ShaderUniformType::Matrix3 => {
+                    gl.uniform_matrix_3_f32_slice(
+                        Some(&uniform.location), false, values
+                    );
+                },
+                ShaderUniformType::Matrix4 => {
+                    gl.uniform_matrix_4_f32_slice(
+                        Some(&uniform.location), false, values
+                    );
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    // none when the material's program failed to build, see compile
+    fn select_program(
+        &mut self,
+        material: &dyn Material
+    ) -> Option<ShaderProgram> {
+        self.compile(material).ok()?;
+
+        let program = self.programs[&ShaderProgramType::from_material(material)].clone();
+
+        unsafe {
+            self.gl.use_program(Some(program.program));
+        }
+
+        Some(program)
+    }
+}
+
+impl Renderer for GlRenderer {
+    fn clear(
+        &mut self
+    ) {
+        unsafe {
+            self.gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT)
+        }
+    }
+
+    fn set_lights(
+        &mut self,
+        lights: &Lights
+    ) {
+        self.lights = lights.clone();
+    }
+
+    fn create_buffers(
+        &mut self,
+        geo: &mut BufferGeometry
+    ) {
+        // on failure they are left unset, and the first draw tries again
+        unsafe {
+            let _ = Self::create_gl_buffers(&self.gl, geo);
+        }
+    }
+
+    fn delete_buffers(
+        &mut self,
+        geo: &mut BufferGeometry
+    ) {
+        let gl = &self.gl;
+
+        unsafe {
+            if let Some(vao) = geo.vao.take() {
+                gl.delete_vertex_array(vao);
+            }
+            if let Some(ebo) = geo.ebo.take() {
+                gl.delete_buffer(ebo);
+            }
+            if let Some(vbo) = geo.vbo.take() {
+                gl.delete_buffer(vbo);
+            }
+        }
+    }
+
+    fn delete_texture(
+        &mut self,
+        texture: &Texture
+    ) {
+        if let Some(cached) = self.textures.remove(&texture.id) {
+            unsafe {
+                self.gl.delete_texture(cached.texture);
+            }
+        }
+    }
+
+    fn draw(
+        &mut self,
+        object: &mut dyn Renderable,
+        projection: &Matrix4,
+        model_view: &Matrix4
+    ) {
+        let material = object.get_material().clone();
+        let material = &*material.borrow();
+        let geo = object.get_geometry_mut();
+
+        unsafe {
+            let Some(program) = self.select_program(material) else {
+                return;
+            };
+
+            self.apply_material_state(material);
+            if self.update(geo, material, projection, model_view, &program).is_err() {
+                return;
+            }
+            self.bind(geo);
+
+            let gl = &self.gl;
+
+            // each triangle as a line loop, without a polygon mode
+            let outline = material.get_data().wireframe && 
+                geo.mode == BufferGeometryMode::Triangles && 
+                gl.version().is_embedded;
+
+            if let Some(indices) = &geo.indices {
+                if outline {
+                    for i in 0..indices.len() / 3 {
+                        gl.draw_elements(LINE_LOOP, 3, UNSIGNED_INT, (i * 3 * size_of::<u32>()) as _);
+                    }
+                }
+                else {
+                    gl.draw_elements(
+                        geo.mode as _,
+                        indices.len() as _,
+                        UNSIGNED_INT,
+                        0
+                    );
+                }
+            }
+            else if let Some(positions) = &geo.positions {
+                if outline {
+                    for i in 0..positions.len() / 3 {
+                        gl.draw_arrays(LINE_LOOP, (i * 3) as _, 3);
+                    }
+                }
+                else {
+                    gl.draw_arrays(
+                        geo.mode as _,
+                        0,
+                        positions.len() as _
+                    );
+                }
+            }
+
+            self.unbind(geo);
+        }
+    }
+
+    fn present(
+        &mut self
+    ) {
+        if self.target.is_none() {
+            self.swap_window();
+        }
+    }
+
+    fn set_render_target(
+        &mut self,
+        target: Option<&RenderTarget>
+    ) {
+        let gl = &self.gl;
+
+        unsafe {
+            match target {
+                Some(target) => {
+                    let stale = self.targets.get(&target.id)
+                        .is_none_or(|t| 
+                            t.width != target.width || 
+                            t.height != target.height || 
+                            t.depth.is_some() != target.depth_buffer
+                        );
+
+                    if stale {
+                        if let Some(old) = self.targets.remove(&target.id) {
+                            Self::delete_target(gl, &old);
+                        }
+                        match Self::create_target(gl, target) {
+                            Ok(created) => {
+                                self.targets.insert(target.id, created);
+                            },
+                            Err(_) => {
+                                // renders to the window instead
+                                self.set_render_target(None);
+                                return;
+                            }
+                        }
+                    }
+
+                    gl.bind_framebuffer(FRAMEBUFFER, Some(self.targets[&target.id].framebuffer));
+                    gl.viewport(0, 0, target.width as _, target.height as _);
+                    self.target = Some(target.id);
+                },
+                None => {
+                    gl.bind_framebuffer(FRAMEBUFFER, None);
+                    gl.viewport(0, 0, self.size.0 as _, self.size.1 as _);
+                    self.target = None;
+                }
+            }
+        }
+    }
+
+    fn delete_render_target(
+        &mut self,
+        target: &RenderTarget
+    ) {
+        if self.target == Some(target.id) {
+            self.set_render_target(None);
+        }
+
+        if let Some(old) = self.targets.remove(&target.id) {
+            unsafe {
+                Self::delete_target(&self.gl, &old);
+            }
+        }
+    }
+
+    fn read_pixels(
+        &mut self
+    ) -> Image {
+        let (width, height) = match self.target {
+            Some(id) => (self.targets[&id].width, self.targets[&id].height),
+            None => self.size,
+        };
+
+        let mut img = Image::new(width, height);
+
+        unsafe {
+            self.gl.pixel_store_i32(PACK_ALIGNMENT, 1);
+            self.gl.read_pixels(
+                0, 
+                0, 
+                width as _, 
+                height as _, 
+                RGBA, 
+                UNSIGNED_BYTE, 
+                PixelPackData::Slice(&mut img.data)
+            );
+        }
+
+        // GL rows start at the bottom
+        img.flip_y();
+
+        img
+    }
 }
\ No newline at end of file
@@ -0,0 +1,143 @@
+use crate::{
+    core::RGB,
+    math::{Matrix4, Vector3, UP},
+    light::{Light, LightKind},
+};
+
+// must match the array sizes in the lit fragment shaders
+pub(crate) const MAX_DIR_LIGHTS: usize = 4;
+pub(crate) const MAX_POINT_LIGHTS: usize = 8;
+pub(crate) const MAX_SPOT_LIGHTS: usize = 4;
+pub(crate) const MAX_HEMI_LIGHTS: usize = 2;
+
+// positions and directions are in view space, directions point towards the light,
+// colors are premultiplied by the intensity
+
+#[derive(Clone, Debug)]
+pub struct DirectionalLightInfo {
+    pub direction: Vector3,
+    pub color: RGB,
+}
+
+#[derive(Clone, Debug)]
+pub struct PointLightInfo {
+    pub position: Vector3,
+    pub color: RGB,
+    pub distance: f32,
+    pub decay: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct SpotLightInfo {
+    pub position: Vector3,
+    pub direction: Vector3,
+    pub color: RGB,
+    pub distance: f32,
+    pub decay: f32,
+    pub cone_cos: f32,
+    pub penumbra_cos: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct HemisphereLightInfo {
+    pub direction: Vector3,
+    pub sky_color: RGB,
+    pub ground_color: RGB,
+}
+
+// the lights of a frame, gathered by the scene before drawing
+#[derive(Clone, Debug, Default)]
+pub struct Lights {
+    pub ambient: RGB,
+    pub directional: Vec<DirectionalLightInfo>,
+    pub point: Vec<PointLightInfo>,
+    pub spot: Vec<SpotLightInfo>,
+    pub hemisphere: Vec<HemisphereLightInfo>,
+}
+
+impl Lights {
+    pub fn new(
+    ) -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(
+        &self
+    ) -> bool {
+        self.ambient == [0.0; 3] &&
+            self.directional.is_empty() &&
+            self.point.is_empty() &&
+            self.spot.is_empty() &&
+            self.hemisphere.is_empty()
+    }
+
+    // the lights the shaders can take, extra ones of each kind are left out
+    pub fn capped(
+        &self
+    ) -> Self {
+        Self {
+            ambient: self.ambient,
+            directional: self.directional.iter().take(MAX_DIR_LIGHTS).cloned().collect(),
+            point: self.point.iter().take(MAX_POINT_LIGHTS).cloned().collect(),
+            spot: self.spot.iter().take(MAX_SPOT_LIGHTS).cloned().collect(),
+            hemisphere: self.hemisphere.iter().take(MAX_HEMI_LIGHTS).cloned().collect(),
+        }
+    }
+
+    pub fn add(
+        &mut self,
+        light: &dyn Light,
+        world_matrix: &Matrix4,
+        view_matrix: &Matrix4
+    ) {
+        let e = &world_matrix.0;
+        let world_position = Vector3::new(e[12], e[13], e[14]);
+        let color = light.get_light().get_radiance();
+        // a light sitting on its target shines from above
+        let towards = |target: &Vector3| {
+            let dir = world_position.sub(target);
+            if dir.length_sq() > 0.0 {dir} else {UP}
+        };
+
+        match light.get_kind() {
+            LightKind::Ambient(_) => {
+                for (a, c) in self.ambient.iter_mut().zip(color) {
+                    *a += c;
+                }
+            },
+            LightKind::Directional(l) => {
+                self.directional.push(DirectionalLightInfo {
+                    direction: towards(&l.target).transform_direction(view_matrix),
+                    color,
+                });
+            },
+            LightKind::Point(l) => {
+                self.point.push(PointLightInfo {
+                    position: world_position.apply_matrix4(view_matrix),
+                    color,
+                    distance: l.distance,
+                    decay: l.decay,
+                });
+            },
+            LightKind::Spot(l) => {
+                self.spot.push(SpotLightInfo {
+                    position: world_position.apply_matrix4(view_matrix),
+                    direction: towards(&l.target).transform_direction(view_matrix),
+                    color,
+                    distance: l.distance,
+                    decay: l.decay,
+                    cone_cos: l.angle.cos(),
+                    penumbra_cos: (l.angle * (1.0 - l.penumbra)).cos(),
+                });
+            },
+            LightKind::Hemisphere(l) => {
+                let intensity = light.get_light().intensity;
+                self.hemisphere.push(HemisphereLightInfo {
+                    direction: towards(&Vector3::zero()).transform_direction(view_matrix),
+                    sky_color: color,
+                    ground_color: l.ground_color.map(|c| c * intensity),
+                });
+            },
+        }
+    }
+}
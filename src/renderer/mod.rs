@@ -1,14 +1,25 @@
-pub enum Event {
-    Quit,
-    KeyDown(usize),
-}
-
-#[cfg(feature = "renderer")]
-pub mod gl_renderer;
-#[cfg(not(feature = "renderer"))]
+pub mod event;
+pub mod renderer;
+pub mod lights;
+pub mod render_target;
+pub mod program;
 pub mod null_renderer;
+pub mod recording_renderer;
+pub mod soft_renderer;
+#[cfg(feature = "gl")]
+pub mod gl_renderer;
+#[cfg(feature = "window")]
+pub mod sdl_window;
 
-#[cfg(feature = "renderer")]
-pub use gl_renderer::*;
-#[cfg(not(feature = "renderer"))]
+pub use event::*;
+pub use renderer::*;
+pub use lights::*;
+pub use render_target::*;
+pub use program::*;
 pub use null_renderer::*;
+pub use recording_renderer::*;
+pub use soft_renderer::*;
+#[cfg(feature = "gl")]
+pub use gl_renderer::*;
+#[cfg(feature = "window")]
+pub use sdl_window::*;
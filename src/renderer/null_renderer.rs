@@ -1,50 +1,75 @@
-use crate::core::BufferGeometry;
-use super::Event;
+use crate::{math::Matrix4, core::{BufferGeometry, Renderable}, image::Image, texture::Texture};
+use super::{Renderer, RenderTarget, Lights};
 
-pub struct GlRenderer {
+#[derive(Default)]
+pub struct NullRenderer {
 }
 
-impl GlRenderer {
-    pub unsafe fn new(
-        _title: &str,
-        _w: u32,
-        _h: u32
+impl NullRenderer {
+    pub fn new(
     ) -> Self {
         Self {}
     }
+}
 
-    pub fn poll_events(
+impl Renderer for NullRenderer {
+    fn clear(
         &mut self
-    ) -> Vec<Event> {
-        vec![]
+    ) {
     }
 
-    pub fn ticks(
-        &mut self
-    ) -> u32 {
-        0
+    fn set_lights(
+        &mut self,
+        _lights: &Lights
+    ) {
     }
 
-    pub fn delay(
+    fn create_buffers(
         &mut self,
-        _ms: u32
+        _geo: &mut BufferGeometry
     ) {
     }
 
-    pub fn swap_window(
-        &self
+    fn delete_buffers(
+        &mut self,
+        _geo: &mut BufferGeometry
     ) {
     }
 
-    pub fn clear(
-        &self
+    fn delete_texture(
+        &mut self,
+        _texture: &Texture
     ) {
     }
 
-    pub fn delete_buffers(
-        &self,
-        _geo: &BufferGeometry
+    fn draw(
+        &mut self,
+        _object: &mut dyn Renderable,
+        _projection: &Matrix4,
+        _model_view: &Matrix4
+    ) {
+    }
+
+    fn present(
+        &mut self
+    ) {
+    }
+
+    fn set_render_target(
+        &mut self,
+        _target: Option<&RenderTarget>
+    ) {
+    }
+
+    fn delete_render_target(
+        &mut self,
+        _target: &RenderTarget
     ) {
     }
-}
 
+    fn read_pixels(
+        &mut self
+    ) -> Image {
+        Image::default()
+    }
+}
@@ -0,0 +1,120 @@
+use crate::material::{Material, MaterialKind};
+
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderProgramType {
+    Basic,
+    Normal,
+    Lambert,
+    Phong,
+    Standard,
+    // a ShaderMaterial, by the hash of its sources
+    Custom(u64),
+}
+
+impl ShaderProgramType {
+    pub fn from_material(
+        material: &dyn Material
+    ) -> Self {
+        match material.get_kind() {
+            MaterialKind::Basic(_) => Self::Basic,
+            MaterialKind::Normal(_) => Self::Normal,
+            MaterialKind::Lambert(_) => Self::Lambert,
+            MaterialKind::Phong(_) => Self::Phong,
+            MaterialKind::Standard(_) => Self::Standard,
+            MaterialKind::Shader(shader) => Self::Custom(shader.get_source_hash()),
+        }
+    }
+
+    // programs that take the scene lights
+    pub fn is_lit(
+        &self
+    ) -> bool {
+        matches!(self, Self::Lambert | Self::Phong | Self::Standard)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Link,
+}
+
+// a shader that failed to compile or link; the log has the offending source lines inlined
+#[derive(Clone, Debug)]
+pub struct ShaderError {
+    pub stage: ShaderStage,
+    pub log: String,
+}
+
+impl ShaderError {
+    pub fn new(
+        stage: ShaderStage,
+        log: &str,
+        source: Option<&str>
+    ) -> Self {
+        let log = match source {
+            Some(source) => Self::annotate(log, source),
+            None => log.trim_end().to_string(),
+        };
+
+        Self {
+            stage,
+            log,
+        }
+    }
+
+    // drivers prefix messages with the source string and line, as in
+    // "0:12(5): error", "0(12) : error" or "ERROR: 0:12: error"
+    fn get_line_number(
+        message: &str
+    ) -> Option<usize> {
+        let message = message.trim_start();
+        let message = ["ERROR:", "WARNING:"].iter()
+            .find_map(|prefix| message.strip_prefix(prefix))
+            .unwrap_or(message)
+            .trim_start();
+
+        let rest = message.trim_start_matches(|c: char| c.is_ascii_digit());
+        if rest.len() == message.len() {
+            return None;
+        }
+        let rest = rest.strip_prefix(':').or_else(|| rest.strip_prefix('('))?;
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        rest[..digits].parse().ok()
+    }
+
+    fn annotate(
+        log: &str,
+        source: &str
+    ) -> String {
+        let lines = source.lines().collect::<Vec<_>>();
+        let mut annotated = vec![];
+
+        for message in log.lines().filter(|l| !l.trim().is_empty()) {
+            annotated.push(message.to_string());
+            if let Some(line) = Self::get_line_number(message) {
+                if let Some(text) = line.checked_sub(1).and_then(|i| lines.get(i)) {
+                    annotated.push(format!("{:>5} | {}", line, text.trim_end()));
+                }
+            }
+        }
+
+        annotated.join("\n")
+    }
+}
+
+impl std::fmt::Display for ShaderError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>
+    ) -> std::fmt::Result {
+        let stage = match self.stage {
+            ShaderStage::Vertex => "vertex shader compilation",
+            ShaderStage::Fragment => "fragment shader compilation",
+            ShaderStage::Link => "program link",
+        };
+        write!(f, "{} failed:\n{}", stage, self.log)
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{
+    math::Matrix4,
+    core::{BufferGeometry, BufferGeometryMode, Object3d, Renderable},
+    image::Image,
+    texture::Texture
+};
+use super::{Renderer, RenderTarget, ShaderProgramType, Lights};
+
+#[derive(Clone, Debug)]
+pub struct DrawCall {
+    pub object_id: usize,
+    pub material_id: usize,
+    pub mode: BufferGeometryMode,
+    pub vertex_count: usize,
+    pub index_count: Option<usize>,
+    pub program: ShaderProgramType,
+    pub projection: Matrix4,
+    pub model_view: Matrix4,
+    pub world_matrix: Matrix4,
+}
+
+impl DrawCall {
+    pub fn is_object(
+        &self,
+        obj: &dyn Object3d
+    ) -> bool {
+        self.object_id == obj.get_id()
+    }
+}
+
+#[derive(Default)]
+pub struct RecordingRenderer {
+    pub draws: Vec<DrawCall>,
+    pub lights: Lights,
+    pub clears: usize,
+    pub presents: usize,
+    pub buffers_created: usize,
+    pub buffers_deleted: usize,
+}
+
+impl RecordingRenderer {
+    pub fn new(
+    ) -> Self {
+        Self::default()
+    }
+
+    pub fn take_draws(
+        &mut self
+    ) -> Vec<DrawCall> {
+        std::mem::take(&mut self.draws)
+    }
+
+    pub fn reset(
+        &mut self
+    ) {
+        *self = Self::default();
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn clear(
+        &mut self
+    ) {
+        self.clears += 1;
+    }
+
+    fn set_lights(
+        &mut self,
+        lights: &Lights
+    ) {
+        self.lights = lights.clone();
+    }
+
+    fn create_buffers(
+        &mut self,
+        _geo: &mut BufferGeometry
+    ) {
+        self.buffers_created += 1;
+    }
+
+    fn delete_buffers(
+        &mut self,
+        _geo: &mut BufferGeometry
+    ) {
+        self.buffers_deleted += 1;
+    }
+
+    fn delete_texture(
+        &mut self,
+        _texture: &Texture
+    ) {
+    }
+
+    fn draw(
+        &mut self,
+        object: &mut dyn Renderable,
+        projection: &Matrix4,
+        model_view: &Matrix4
+    ) {
+        let obj = object.get_object();
+        let geo = object.get_geometry();
+        let material = object.get_material().borrow();
+
+        self.draws.push(DrawCall {
+            object_id: obj.get_id(),
+            material_id: material.get_data().get_id(),
+            mode: geo.mode,
+            vertex_count: geo.positions.as_ref().map_or(0, |p| p.len()),
+            index_count: geo.indices.as_ref().map(|i| i.len()),
+            program: ShaderProgramType::from_material(&*material),
+            projection: projection.clone(),
+            model_view: model_view.clone(),
+            world_matrix: obj.world_matrix.clone(),
+        });
+    }
+
+    fn present(
+        &mut self
+    ) {
+        self.presents += 1;
+    }
+
+    fn set_render_target(
+        &mut self,
+        _target: Option<&RenderTarget>
+    ) {
+    }
+
+    fn delete_render_target(
+        &mut self,
+        _target: &RenderTarget
+    ) {
+    }
+
+    fn read_pixels(
+        &mut self
+    ) -> Image {
+        Image::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{rc::Rc, cell::RefCell};
+    use crate::{
+        core::{BufferGeometryMode, Object3d},
+        math::{Vector3, Quaternion, Matrix4},
+        camera::{Camera, PerspectiveCamera},
+        geometry::Box3,
+        material::{Material, MeshBasicMaterial},
+        object::{Mesh, Group},
+        scene::Scene,
+    };
+    use super::RecordingRenderer;
+
+    fn assert_matrix_near(
+        a: &Matrix4,
+        b: &Matrix4
+    ) {
+        for (x, y) in a.to_slice().iter().zip(b.to_slice()) {
+            assert!((x - y).abs() < 1e-4, "{:?} != {:?}", a.to_slice(), b.to_slice());
+        }
+    }
+
+    #[test]
+    fn records_opaque_then_transparent_and_skips_culled() {
+        let renderer = Rc::new(RefCell::new(RecordingRenderer::new()));
+        let mut scene = Scene::new(renderer.clone());
+        let geo = Box3::new(1.0, 1.0, 1.0);
+
+        let glass_material = MeshBasicMaterial::new([1.0, 0.0, 0.0]);
+        glass_material.borrow_mut().get_data_mut().opacity = 0.5;
+        let solid_material = MeshBasicMaterial::new([0.0, 1.0, 0.0]);
+
+        // added first and in front, still drawn after the opaque mesh
+        let glass = Mesh::new_ex(&geo, glass_material.clone());
+        glass.borrow_mut().get_object_mut().set_position(Vector3::new(0.0, 0.0, -5.0));
+        let solid = Mesh::new_ex(&geo, solid_material.clone());
+        solid.borrow_mut().get_object_mut().set_position(Vector3::new(0.0, 0.0, -10.0));
+        // far outside the view
+        let culled = Mesh::new_ex(&geo, solid_material.clone());
+        culled.borrow_mut().get_object_mut().set_position(Vector3::new(100.0, 0.0, -5.0));
+
+        scene.add(glass.clone());
+        scene.add(solid.clone());
+        scene.add(culled.clone());
+
+        let mut camera = PerspectiveCamera::new(60.0, 1.0, 0.1, 100.0);
+        scene.render(&mut camera);
+
+        let renderer = renderer.borrow();
+        assert_eq!(renderer.clears, 1);
+        assert_eq!(renderer.presents, 1);
+        assert_eq!(renderer.draws.len(), 2);
+
+        let expected = [
+            (solid.borrow().get_id(), solid_material.borrow().get_data().get_id()),
+            (glass.borrow().get_id(), glass_material.borrow().get_data().get_id()),
+        ];
+        for (draw, (object_id, material_id)) in renderer.draws.iter().zip(expected) {
+            assert_eq!(draw.object_id, object_id);
+            assert_eq!(draw.material_id, material_id);
+            assert_eq!(draw.mode, BufferGeometryMode::Triangles);
+            assert_eq!(draw.vertex_count, 24);
+            assert_eq!(draw.index_count, Some(36));
+        }
+
+        assert!(!renderer.draws.iter().any(|draw| draw.object_id == culled.borrow().get_id()));
+    }
+
+    #[test]
+    fn records_children_with_their_world_matrices_and_skips_hidden_subtrees() {
+        let renderer = Rc::new(RefCell::new(RecordingRenderer::new()));
+        let mut scene = Scene::new(renderer.clone());
+        let geo = Box3::new(1.0, 1.0, 1.0);
+
+        // parent -> child -> grandchild, with the parent turned a quarter around y and scaled
+        let parent = Group::new();
+        parent.borrow_mut().get_object_mut()
+            .set_position(Vector3::new(0.0, 0.0, -10.0))
+            .set_rotation(Quaternion::from_axis_and_angle(&Vector3::new(0.0, 1.0, 0.0), std::f32::consts::FRAC_PI_2))
+            .set_scale(Vector3::new(2.0, 2.0, 2.0));
+        let child = Mesh::new(&geo);
+        child.borrow_mut().get_object_mut().set_position(Vector3::new(1.0, 0.0, 0.0));
+        let grandchild = Mesh::new(&geo);
+        grandchild.borrow_mut().get_object_mut().set_position(Vector3::new(0.0, 1.0, 0.0));
+        child.borrow_mut().add(grandchild.clone()).unwrap();
+        parent.borrow_mut().add(child.clone()).unwrap();
+
+        // in view, but under a hidden group
+        let hidden = Group::new();
+        hidden.borrow_mut().get_object_mut().visible = false;
+        let hidden_child = Mesh::new(&geo);
+        hidden_child.borrow_mut().get_object_mut().set_position(Vector3::new(0.0, 0.0, -10.0));
+        hidden.borrow_mut().add(hidden_child.clone()).unwrap();
+
+        scene.add(parent.clone());
+        scene.add(hidden.clone());
+
+        let mut camera = PerspectiveCamera::new(60.0, 1.0, 0.1, 100.0);
+        camera.get_object_mut().set_position(Vector3::new(0.0, 0.0, 5.0));
+        scene.render(&mut camera);
+
+        let renderer = renderer.borrow();
+        assert_eq!(renderer.draws.len(), 2);
+        assert!(!renderer.draws.iter().any(|draw| draw.is_object(&*hidden_child.borrow())));
+
+        let parent_matrix = Matrix4::compose(
+            &Vector3::new(0.0, 0.0, -10.0),
+            &Quaternion::from_axis_and_angle(&Vector3::new(0.0, 1.0, 0.0), std::f32::consts::FRAC_PI_2),
+            &Vector3::new(2.0, 2.0, 2.0)
+        );
+        let child_world = parent_matrix.mul(&Matrix4::compose(
+            &Vector3::new(1.0, 0.0, 0.0),
+            &Quaternion::identity(),
+            &Vector3::one()
+        ));
+        let grandchild_world = child_world.mul(&Matrix4::compose(
+            &Vector3::new(0.0, 1.0, 0.0),
+            &Quaternion::identity(),
+            &Vector3::one()
+        ));
+        let view = camera.get_data().world_matrix_inverse.clone();
+
+        let child_draw = renderer.draws.iter().find(|draw| draw.is_object(&*child.borrow())).unwrap();
+        assert_matrix_near(&child_draw.world_matrix, &child_world);
+        assert_matrix_near(&child_draw.model_view, &view.mul(&child_world));
+        assert_matrix_near(&child_draw.projection, &camera.get_data().proj_matrix);
+        // +x turned to -z and doubled
+        let e = child_draw.world_matrix.to_slice();
+        assert!((e[12] - 0.0).abs() < 1e-4 && (e[13] - 0.0).abs() < 1e-4 && (e[14] + 12.0).abs() < 1e-4);
+        let e = child_draw.model_view.to_slice();
+        assert!((e[14] + 17.0).abs() < 1e-4);
+
+        let grandchild_draw = renderer.draws.iter().find(|draw| draw.is_object(&*grandchild.borrow())).unwrap();
+        assert_matrix_near(&grandchild_draw.world_matrix, &grandchild_world);
+        assert_matrix_near(&grandchild_draw.model_view, &view.mul(&grandchild_world));
+        let e = grandchild_draw.world_matrix.to_slice();
+        assert!((e[12] - 0.0).abs() < 1e-4 && (e[13] - 2.0).abs() < 1e-4 && (e[14] + 12.0).abs() < 1e-4);
+    }
+}
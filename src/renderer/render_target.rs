@@ -0,0 +1,36 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+// offscreen surface with a RGBA8 color attachment and an optional depth attachment;
+// backend resources are created lazily by the renderer the target is bound to
+#[derive(Debug)]
+pub struct RenderTarget {
+    pub(crate) id: usize,
+    pub width: u32,
+    pub height: u32,
+    pub depth_buffer: bool,
+}
+
+impl RenderTarget {
+    pub fn new(
+        width: u32,
+        height: u32
+    ) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            width,
+            height,
+            depth_buffer: true,
+        }
+    }
+
+    pub fn set_size(
+        &mut self,
+        width: u32,
+        height: u32
+    ) {
+        self.width = width;
+        self.height = height;
+    }
+}
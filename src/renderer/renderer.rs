@@ -0,0 +1,56 @@
+use crate::{math::Matrix4, core::{BufferGeometry, Renderable}, image::Image, texture::Texture};
+use super::{RenderTarget, Lights};
+
+pub trait Renderer {
+    fn clear(
+        &mut self
+    );
+
+    // called once per frame, before the draws that are lit by them
+    fn set_lights(
+        &mut self,
+        lights: &Lights
+    );
+
+    fn create_buffers(
+        &mut self,
+        geo: &mut BufferGeometry
+    );
+
+    fn delete_buffers(
+        &mut self,
+        geo: &mut BufferGeometry
+    );
+
+    // frees whatever the renderer created for the texture, it's uploaded again when drawn
+    fn delete_texture(
+        &mut self,
+        texture: &Texture
+    );
+
+    fn draw(
+        &mut self,
+        object: &mut dyn Renderable,
+        projection: &Matrix4,
+        model_view: &Matrix4
+    );
+
+    fn present(
+        &mut self
+    );
+
+    fn set_render_target(
+        &mut self,
+        target: Option<&RenderTarget>
+    );
+
+    fn delete_render_target(
+        &mut self,
+        target: &RenderTarget
+    );
+
+    // RGBA8 of the bound target (or the default surface), rows ordered from top to bottom
+    fn read_pixels(
+        &mut self
+    ) -> Image;
+}
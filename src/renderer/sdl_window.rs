@@ -0,0 +1,360 @@
+use glow::Context;
+use sdl2::{
+    event::WindowEvent,
+    keyboard::{Keycode, Scancode, Mod},
+    mouse::MouseWheelDirection
+};
+use super::{Event, Key, Modifiers, MouseButton};
+
+pub struct SdlWindow {
+    video: sdl2::VideoSubsystem,
+    window: sdl2::video::Window,
+    events_loop: sdl2::EventPump,
+    keyboard: sdl2::keyboard::KeyboardUtil,
+    pointer: (f32, f32),
+    timer: sdl2::TimerSubsystem,
+    _context: sdl2::video::GLContext,
+}
+
+impl SdlWindow {
+    pub fn new(
+        title: &str,
+        w: u32,
+        h: u32
+    ) -> Result<Self, String> {
+        Self::new_ex(title, w, h, true)
+    }
+
+    // a hidden window still provides the GL context, so scenes can be drawn into render targets
+    pub fn new_ex(
+        title: &str,
+        w: u32,
+        h: u32,
+        visible: bool
+    ) -> Result<Self, String> {
+        let sdl = sdl2::init()?;
+        let video = sdl.video()?;
+        
+        let gl_attr = video.gl_attr();
+        gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
+        gl_attr.set_context_version(3, 3);
+        
+        let mut builder = video.window(title, w, h);
+        builder.opengl().resizable().allow_highdpi();
+        if !visible {
+            builder.hidden();
+        }
+        let window = builder
+            .build()
+            .map_err(|e| e.to_string())?;
+        
+        // NOTE: if removed the GL initialization will crash
+        let _context = window.gl_create_context()?;
+        
+        let events_loop = sdl.event_pump()?;
+        let keyboard = sdl.keyboard();
+        let timer = sdl.timer()?;
+
+        Ok(Self {
+            video,
+            window,
+            events_loop,
+            keyboard,
+            pointer: (0.0, 0.0),
+            timer,
+            _context,
+        })
+    }
+
+    /// # Safety
+    /// The window's GL context must be current on the calling thread and outlive the returned one.
+    pub unsafe fn create_context(
+        &self
+    ) -> Context {
+        Context::from_loader_function(|s| self.video.gl_get_proc_address(s) as *const _)
+    }
+
+    pub fn poll_events(
+        &mut self
+    ) -> Vec<Event> {
+        let mut events = vec![];
+        
+        for event in self.events_loop.poll_iter() {
+            match event {
+                sdl2::event::Event::Quit { .. } => {
+                    events.push(Event::Quit);
+                },
+                sdl2::event::Event::KeyDown {keycode, scancode, keymod, repeat, ..} => {
+                    events.push(Event::KeyDown {
+                        key: map_key(keycode),
+                        code: map_code(scancode),
+                        scancode: scancode.map(|scancode| scancode as _),
+                        modifiers: map_modifiers(keymod),
+                        repeat,
+                    });
+                },
+                sdl2::event::Event::KeyUp {keycode, scancode, keymod, ..} => {
+                    events.push(Event::KeyUp {
+                        key: map_key(keycode),
+                        code: map_code(scancode),
+                        scancode: scancode.map(|scancode| scancode as _),
+                        modifiers: map_modifiers(keymod),
+                    });
+                },
+                sdl2::event::Event::TextInput {text, ..} => {
+                    events.push(Event::TextInput(text));
+                },
+                sdl2::event::Event::MouseMotion {x, y, xrel, yrel, ..} => {
+                    self.pointer = (x as f32, y as f32);
+                    events.push(Event::MouseMove {
+                        x: x as f32,
+                        y: y as f32,
+                        dx: xrel as f32,
+                        dy: yrel as f32,
+                        modifiers: map_modifiers(self.keyboard.mod_state()),
+                    });
+                },
+                sdl2::event::Event::MouseButtonDown {mouse_btn, x, y, ..} => {
+                    self.pointer = (x as f32, y as f32);
+                    events.push(Event::MouseDown {
+                        button: map_button(mouse_btn),
+                        x: x as f32,
+                        y: y as f32,
+                        modifiers: map_modifiers(self.keyboard.mod_state()),
+                    });
+                },
+                sdl2::event::Event::MouseButtonUp {mouse_btn, x, y, ..} => {
+                    self.pointer = (x as f32, y as f32);
+                    events.push(Event::MouseUp {
+                        button: map_button(mouse_btn),
+                        x: x as f32,
+                        y: y as f32,
+                        modifiers: map_modifiers(self.keyboard.mod_state()),
+                    });
+                },
+                sdl2::event::Event::MouseWheel {precise_x, precise_y, direction, ..} => {
+                    let sign = if direction == MouseWheelDirection::Flipped {-1.0} else {1.0};
+                    events.push(Event::MouseWheel {
+                        dx: precise_x * sign,
+                        dy: precise_y * sign,
+                        x: self.pointer.0,
+                        y: self.pointer.1,
+                        modifiers: map_modifiers(self.keyboard.mod_state()),
+                    });
+                },
+                sdl2::event::Event::Window {win_event: WindowEvent::SizeChanged(w, h), ..} => {
+                    events.push(Event::Resize {
+                        width: w.max(0) as _,
+                        height: h.max(0) as _,
+                    });
+                },
+                _ => {}
+            }
+        }
+        
+        events
+    }
+
+    pub fn ticks(
+        &mut self
+    ) -> u32 {
+        self.timer.ticks()
+    }
+
+    pub fn delay(
+        &mut self,
+        ms: u32
+    ) {
+        self.timer.delay(ms);
+    }
+
+    pub fn swap_window(
+        &self
+    ) {
+        self.window.gl_swap_window();
+    }
+
+    pub fn get_size(
+        &self
+    ) -> (u32, u32) {
+        self.window.size()
+    }
+
+    pub fn get_drawable_size(
+        &self
+    ) -> (u32, u32) {
+        self.window.drawable_size()
+    }
+
+    pub fn get_pixel_ratio(
+        &self
+    ) -> f32 {
+        let (w, _) = self.get_size();
+        let (dw, _) = self.get_drawable_size();
+        if w > 0 {
+            dw as f32 / w as f32
+        }
+        else {
+            1.0
+        }
+    }
+}
+
+fn map_modifiers(
+    keymod: Mod
+) -> Modifiers {
+    Modifiers {
+        shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+        ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+        alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+        meta: keymod.intersects(Mod::LGUIMOD | Mod::RGUIMOD),
+    }
+}
+
+fn map_button(
+    button: sdl2::mouse::MouseButton
+) -> MouseButton {
+    match button {
+        sdl2::mouse::MouseButton::Left => MouseButton::Left,
+        sdl2::mouse::MouseButton::Middle => MouseButton::Middle,
+        sdl2::mouse::MouseButton::Right => MouseButton::Right,
+        sdl2::mouse::MouseButton::X1 => MouseButton::Other(4),
+        sdl2::mouse::MouseButton::X2 => MouseButton::Other(5),
+        sdl2::mouse::MouseButton::Unknown => MouseButton::Other(0),
+    }
+}
+
+fn map_key(
+    keycode: Option<Keycode>
+) -> Key {
+    let Some(keycode) = keycode else {
+        return Key::Other;
+    };
+    
+    match keycode {
+        Keycode::A => Key::A,
+        Keycode::B => Key::B,
+        Keycode::C => Key::C,
+        Keycode::D => Key::D,
+        Keycode::E => Key::E,
+        Keycode::F => Key::F,
+        Keycode::G => Key::G,
+        Keycode::H => Key::H,
+        Keycode::I => Key::I,
+        Keycode::J => Key::J,
+        Keycode::K => Key::K,
+        Keycode::L => Key::L,
+        Keycode::M => Key::M,
+        Keycode::N => Key::N,
+        Keycode::O => Key::O,
+        Keycode::P => Key::P,
+        Keycode::Q => Key::Q,
+        Keycode::R => Key::R,
+        Keycode::S => Key::S,
+        Keycode::T => Key::T,
+        Keycode::U => Key::U,
+        Keycode::V => Key::V,
+        Keycode::W => Key::W,
+        Keycode::X => Key::X,
+        Keycode::Y => Key::Y,
+        Keycode::Z => Key::Z,
+        Keycode::Num0 | Keycode::Kp0 => Key::Num0,
+        Keycode::Num1 | Keycode::Kp1 => Key::Num1,
+        Keycode::Num2 | Keycode::Kp2 => Key::Num2,
+        Keycode::Num3 | Keycode::Kp3 => Key::Num3,
+        Keycode::Num4 | Keycode::Kp4 => Key::Num4,
+        Keycode::Num5 | Keycode::Kp5 => Key::Num5,
+        Keycode::Num6 | Keycode::Kp6 => Key::Num6,
+        Keycode::Num7 | Keycode::Kp7 => Key::Num7,
+        Keycode::Num8 | Keycode::Kp8 => Key::Num8,
+        Keycode::Num9 | Keycode::Kp9 => Key::Num9,
+        Keycode::Up => Key::Up,
+        Keycode::Down => Key::Down,
+        Keycode::Left => Key::Left,
+        Keycode::Right => Key::Right,
+        Keycode::Space => Key::Space,
+        Keycode::Return | Keycode::KpEnter => Key::Enter,
+        Keycode::Escape => Key::Escape,
+        Keycode::Tab => Key::Tab,
+        Keycode::Backspace => Key::Backspace,
+        Keycode::Delete => Key::Delete,
+        Keycode::Insert => Key::Insert,
+        Keycode::Home => Key::Home,
+        Keycode::End => Key::End,
+        Keycode::PageUp => Key::PageUp,
+        Keycode::PageDown => Key::PageDown,
+        Keycode::LShift | Keycode::RShift => Key::Shift,
+        Keycode::LCtrl | Keycode::RCtrl => Key::Ctrl,
+        Keycode::LAlt | Keycode::RAlt => Key::Alt,
+        Keycode::LGui | Keycode::RGui => Key::Meta,
+        _ => Key::Other,
+    }
+}
+
+// the key at the scancode's place on a us layout
+fn map_code(
+    scancode: Option<Scancode>
+) -> Key {
+    let Some(scancode) = scancode else {
+        return Key::Other;
+    };
+    
+    match scancode {
+        Scancode::A => Key::A,
+        Scancode::B => Key::B,
+        Scancode::C => Key::C,
+        Scancode::D => Key::D,
+        Scancode::E => Key::E,
+        Scancode::F => Key::F,
+        Scancode::G => Key::G,
+        Scancode::H => Key::H,
+        Scancode::I => Key::I,
+        Scancode::J => Key::J,
+        Scancode::K => Key::K,
+        Scancode::L => Key::L,
+        Scancode::M => Key::M,
+        Scancode::N => Key::N,
+        Scancode::O => Key::O,
+        Scancode::P => Key::P,
+        Scancode::Q => Key::Q,
+        Scancode::R => Key::R,
+        Scancode::S => Key::S,
+        Scancode::T => Key::T,
+        Scancode::U => Key::U,
+        Scancode::V => Key::V,
+        Scancode::W => Key::W,
+        Scancode::X => Key::X,
+        Scancode::Y => Key::Y,
+        Scancode::Z => Key::Z,
+        Scancode::Num0 | Scancode::Kp0 => Key::Num0,
+        Scancode::Num1 | Scancode::Kp1 => Key::Num1,
+        Scancode::Num2 | Scancode::Kp2 => Key::Num2,
+        Scancode::Num3 | Scancode::Kp3 => Key::Num3,
+        Scancode::Num4 | Scancode::Kp4 => Key::Num4,
+        Scancode::Num5 | Scancode::Kp5 => Key::Num5,
+        Scancode::Num6 | Scancode::Kp6 => Key::Num6,
+        Scancode::Num7 | Scancode::Kp7 => Key::Num7,
+        Scancode::Num8 | Scancode::Kp8 => Key::Num8,
+        Scancode::Num9 | Scancode::Kp9 => Key::Num9,
+        Scancode::Up => Key::Up,
+        Scancode::Down => Key::Down,
+        Scancode::Left => Key::Left,
+        Scancode::Right => Key::Right,
+        Scancode::Space => Key::Space,
+        Scancode::Return | Scancode::KpEnter => Key::Enter,
+        Scancode::Escape => Key::Escape,
+        Scancode::Tab => Key::Tab,
+        Scancode::Backspace => Key::Backspace,
+        Scancode::Delete => Key::Delete,
+        Scancode::Insert => Key::Insert,
+        Scancode::Home => Key::Home,
+        Scancode::End => Key::End,
+        Scancode::PageUp => Key::PageUp,
+        Scancode::PageDown => Key::PageDown,
+        Scancode::LShift | Scancode::RShift => Key::Shift,
+        Scancode::LCtrl | Scancode::RCtrl => Key::Ctrl,
+        Scancode::LAlt | Scancode::RAlt => Key::Alt,
+        Scancode::LGui | Scancode::RGui => Key::Meta,
+        _ => Key::Other,
+    }
+}
@@ -0,0 +1,1118 @@
+use std::{collections::HashMap, f32::consts::PI};
+use crate::{
+    image::Image,
+    core::{BufferGeometry, BufferGeometryMode, Renderable, RGB, UV},
+    math::{Matrix4, Vector3},
+    material::{Material, MaterialKind, MeshStandardMaterial, Uniform, Side},
+    texture::{Texture, TextureRef},
+};
+use super::{Renderer, RenderTarget, Lights};
+
+// values interpolated across primitives
+#[derive(Clone, Copy, Default)]
+struct Varying {
+    color: [f32; 4],
+    normal: Vector3,
+    position: Vector3,
+    uv: UV,
+    uv2: UV,
+    // view space, only set for normal mapped materials
+    tangent: Vector3,
+    bitangent: Vector3,
+}
+
+impl Varying {
+    fn scale(
+        &self,
+        s: f32
+    ) -> Self {
+        Self {
+            color: self.color.map(|c| c * s),
+            normal: self.normal.mul_scalar(s),
+            position: self.position.mul_scalar(s),
+            uv: self.uv.map(|c| c * s),
+            uv2: self.uv2.map(|c| c * s),
+            tangent: self.tangent.mul_scalar(s),
+            bitangent: self.bitangent.mul_scalar(s),
+        }
+    }
+
+    fn add(
+        &self,
+        other: &Self
+    ) -> Self {
+        Self {
+            color: [
+                self.color[0] + other.color[0],
+                self.color[1] + other.color[1],
+                self.color[2] + other.color[2],
+                self.color[3] + other.color[3],
+            ],
+            normal: self.normal.add(&other.normal),
+            position: self.position.add(&other.position),
+            uv: [self.uv[0] + other.uv[0], self.uv[1] + other.uv[1]],
+            uv2: [self.uv2[0] + other.uv2[0], self.uv2[1] + other.uv2[1]],
+            tangent: self.tangent.add(&other.tangent),
+            bitangent: self.bitangent.add(&other.bitangent),
+        }
+    }
+
+    fn lerp(
+        &self,
+        other: &Self,
+        t: f32
+    ) -> Self {
+        self.scale(1.0 - t).add(&other.scale(t))
+    }
+}
+
+// per draw state taken from the material
+struct RasterState {
+    side: Side,
+    depth_test: bool,
+    depth_write: bool,
+    blend: bool,
+}
+
+type FragmentShader<'a> = dyn Fn(&Varying, bool) -> [f32; 4] + 'a;
+
+#[derive(Clone, Copy)]
+struct ClipVertex {
+    pos: [f32; 4],
+    varying: Varying,
+}
+
+#[derive(Clone, Copy)]
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    z: f32,
+    inv_w: f32,
+    varying: Varying,
+}
+
+struct Framebuffer {
+    width: usize,
+    height: usize,
+    depth_test: bool,
+    color: Vec<u8>,
+    depth: Vec<f32>,
+}
+
+impl Framebuffer {
+    fn new(
+        width: u32,
+        height: u32,
+        depth_test: bool
+    ) -> Self {
+        let width = width as usize;
+        let height = height as usize;
+
+        Self {
+            width,
+            height,
+            depth_test,
+            color: vec![0; width * height * 4],
+            depth: vec![1.0; width * height],
+        }
+    }
+
+    fn clear(
+        &mut self,
+        color: &[f32; 4]
+    ) {
+        let c = color.map(Self::to_byte);
+        for pixel in self.color.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&c);
+        }
+        self.depth.fill(1.0);
+    }
+
+    fn to_byte(
+        c: f32
+    ) -> u8 {
+        (c.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+
+    fn lerp(
+        a: &ClipVertex,
+        b: &ClipVertex,
+        t: f32
+    ) -> ClipVertex {
+        ClipVertex {
+            pos: [
+                a.pos[0] + (b.pos[0] - a.pos[0]) * t,
+                a.pos[1] + (b.pos[1] - a.pos[1]) * t,
+                a.pos[2] + (b.pos[2] - a.pos[2]) * t,
+                a.pos[3] + (b.pos[3] - a.pos[3]) * t,
+            ],
+            varying: a.varying.lerp(&b.varying, t),
+        }
+    }
+
+    // signed distances to the near (z >= -w) and far (z <= w) clip planes
+    fn clip_distances(
+        v: &ClipVertex
+    ) -> [f32; 2] {
+        [v.pos[2] + v.pos[3], v.pos[3] - v.pos[2]]
+    }
+
+    fn clip_polygon(
+        vertices: &[ClipVertex]
+    ) -> Vec<ClipVertex> {
+        let mut polygon = vertices.to_vec();
+
+        for plane in 0..2 {
+            if polygon.is_empty() {
+                break;
+            }
+
+            let mut clipped = Vec::with_capacity(polygon.len() + 1);
+            for i in 0..polygon.len() {
+                let a = &polygon[i];
+                let b = &polygon[(i + 1) % polygon.len()];
+                let da = Self::clip_distances(a)[plane];
+                let db = Self::clip_distances(b)[plane];
+
+                if da >= 0.0 {
+                    clipped.push(*a);
+                }
+                if (da >= 0.0) != (db >= 0.0) {
+                    clipped.push(Self::lerp(a, b, da / (da - db)));
+                }
+            }
+            polygon = clipped;
+        }
+
+        polygon
+    }
+
+    fn to_screen(
+        &self,
+        v: &ClipVertex
+    ) -> ScreenVertex {
+        let inv_w = 1.0 / v.pos[3];
+        ScreenVertex {
+            x: (v.pos[0] * inv_w + 1.0) * 0.5 * self.width as f32,
+            y: (1.0 - v.pos[1] * inv_w) * 0.5 * self.height as f32,
+            z: v.pos[2] * inv_w * 0.5 + 0.5,
+            inv_w,
+            varying: v.varying.scale(inv_w),
+        }
+    }
+
+    fn edge(
+        a: &ScreenVertex,
+        b: &ScreenVertex,
+        x: f32,
+        y: f32
+    ) -> f32 {
+        (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn write_fragment(
+        &mut self,
+        x: usize,
+        y: usize,
+        z: f32,
+        varying: &Varying,
+        front_facing: bool,
+        state: &RasterState,
+        shader: &FragmentShader
+    ) {
+        let index = y * self.width + x;
+        if !(0.0..=1.0).contains(&z) {
+            return;
+        }
+
+        if self.depth_test && state.depth_test && z >= self.depth[index] {
+            return;
+        }
+
+        let color = shader(varying, front_facing);
+
+        if self.depth_test && state.depth_write {
+            self.depth[index] = z;
+        }
+
+        let pixel = &mut self.color[index * 4..index * 4 + 4];
+        if state.blend {
+            let a = color[3].clamp(0.0, 1.0);
+            for i in 0..3 {
+                let dst = pixel[i] as f32 / 255.0;
+                pixel[i] = Self::to_byte(color[i] * a + dst * (1.0 - a));
+            }
+            let dst = pixel[3] as f32 / 255.0;
+            pixel[3] = Self::to_byte(a + dst * (1.0 - a));
+        }
+        else {
+            pixel[0] = Self::to_byte(color[0]);
+            pixel[1] = Self::to_byte(color[1]);
+            pixel[2] = Self::to_byte(color[2]);
+            pixel[3] = Self::to_byte(color[3]);
+        }
+    }
+
+    fn draw_triangle(
+        &mut self,
+        vertices: [ClipVertex; 3],
+        state: &RasterState,
+        shader: &FragmentShader
+    ) {
+        let polygon = Self::clip_polygon(&vertices);
+        if polygon.len() < 3 {
+            return;
+        }
+
+        let screen = polygon.iter()
+            .map(|v| self.to_screen(v))
+            .collect::<Vec<_>>();
+
+        for i in 1..screen.len() - 1 {
+            self.rasterize_triangle(&screen[0], &screen[i], &screen[i + 1], state, shader);
+        }
+    }
+
+    fn draw_triangle_edges(
+        &mut self,
+        vertices: [ClipVertex; 3],
+        state: &RasterState,
+        shader: &FragmentShader
+    ) {
+        for i in 0..3 {
+            self.draw_line(vertices[i], vertices[(i + 1) % 3], state, shader);
+        }
+    }
+
+    fn rasterize_triangle(
+        &mut self,
+        a: &ScreenVertex,
+        b: &ScreenVertex,
+        c: &ScreenVertex,
+        state: &RasterState,
+        shader: &FragmentShader
+    ) {
+        // the screen y axis points down, so counter-clockwise (front) faces have a negative area
+        let area = Self::edge(a, b, c.x, c.y);
+        let front_facing = area < 0.0;
+        let culled = match state.side {
+            Side::Front => !front_facing,
+            Side::Back => front_facing,
+            Side::Double => false,
+        };
+        if culled || area == 0.0 {
+            return;
+        }
+
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as usize;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as usize;
+        let max_x = (a.x.max(b.x).max(c.x).ceil().max(0.0) as usize).min(self.width);
+        let max_y = (a.y.max(b.y).max(c.y).ceil().max(0.0) as usize).min(self.height);
+
+        for y in min_y..max_y {
+            let py = y as f32 + 0.5;
+            for x in min_x..max_x {
+                let px = x as f32 + 0.5;
+
+                let l0 = Self::edge(b, c, px, py) / area;
+                let l1 = Self::edge(c, a, px, py) / area;
+                let l2 = Self::edge(a, b, px, py) / area;
+                if l0 < 0.0 || l1 < 0.0 || l2 < 0.0 {
+                    continue;
+                }
+
+                let z = l0 * a.z + l1 * b.z + l2 * c.z;
+                let inv_w = l0 * a.inv_w + l1 * b.inv_w + l2 * c.inv_w;
+                let varying = a.varying.scale(l0)
+                    .add(&b.varying.scale(l1))
+                    .add(&c.varying.scale(l2))
+                    .scale(1.0 / inv_w);
+
+                self.write_fragment(x, y, z, &varying, front_facing, state, shader);
+            }
+        }
+    }
+
+    fn draw_line(
+        &mut self,
+        a: ClipVertex,
+        b: ClipVertex,
+        state: &RasterState,
+        shader: &FragmentShader
+    ) {
+        // clip against the near and far planes
+        let (mut t0, mut t1) = (0.0f32, 1.0f32);
+        let da = Self::clip_distances(&a);
+        let db = Self::clip_distances(&b);
+        for plane in 0..2 {
+            if da[plane] < 0.0 && db[plane] < 0.0 {
+                return;
+            }
+            let t = da[plane] / (da[plane] - db[plane]);
+            if da[plane] < 0.0 {
+                t0 = t0.max(t);
+            }
+            else if db[plane] < 0.0 {
+                t1 = t1.min(t);
+            }
+        }
+        if t0 > t1 {
+            return;
+        }
+
+        let sa = self.to_screen(&Self::lerp(&a, &b, t0));
+        let sb = self.to_screen(&Self::lerp(&a, &b, t1));
+        self.rasterize_line(&sa, &sb, state, shader);
+    }
+
+    fn rasterize_line(
+        &mut self,
+        a: &ScreenVertex,
+        b: &ScreenVertex,
+        state: &RasterState,
+        shader: &FragmentShader
+    ) {
+        let dx = b.x - a.x;
+        let dy = b.y - a.y;
+
+        // clip against the viewport
+        let (mut t0, mut t1) = (0.0f32, 1.0f32);
+        let w = self.width as f32;
+        let h = self.height as f32;
+        for (p, q) in [(-dx, a.x), (dx, w - a.x), (-dy, a.y), (dy, h - a.y)] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return;
+                }
+            }
+            else if p < 0.0 {
+                t0 = t0.max(q / p);
+            }
+            else {
+                t1 = t1.min(q / p);
+            }
+        }
+        if t0 > t1 {
+            return;
+        }
+
+        let steps = (dx.abs().max(dy.abs()) * (t1 - t0)).ceil().max(1.0) as usize;
+        for i in 0..=steps {
+            let t = t0 + (t1 - t0) * i as f32 / steps as f32;
+            let x = (a.x + dx * t).floor();
+            let y = (a.y + dy * t).floor();
+            if x < 0.0 || y < 0.0 || x >= w || y >= h {
+                continue;
+            }
+
+            let z = a.z + (b.z - a.z) * t;
+            let inv_w = a.inv_w + (b.inv_w - a.inv_w) * t;
+            let varying = a.varying.lerp(&b.varying, t).scale(1.0 / inv_w);
+
+            self.write_fragment(x as usize, y as usize, z, &varying, true, state, shader);
+        }
+    }
+}
+
+pub struct SoftRenderer {
+    clear_color: [f32; 4],
+    screen: Framebuffer,
+    targets: HashMap<usize, Framebuffer>,
+    target: Option<usize>,
+    lights: Lights,
+}
+
+impl SoftRenderer {
+    pub fn new(
+        w: u32,
+        h: u32
+    ) -> Self {
+        Self {
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            screen: Framebuffer::new(w, h, true),
+            targets: HashMap::new(),
+            target: None,
+            lights: Lights::default(),
+        }
+    }
+
+    fn framebuffer(
+        &self
+    ) -> &Framebuffer {
+        match self.target {
+            Some(id) => &self.targets[&id],
+            None => &self.screen,
+        }
+    }
+
+    fn framebuffer_mut(
+        &mut self
+    ) -> &mut Framebuffer {
+        match self.target {
+            Some(id) => self.targets.get_mut(&id).unwrap(),
+            None => &mut self.screen,
+        }
+    }
+
+    pub fn get_size(
+        &self
+    ) -> (u32, u32) {
+        let fb = self.framebuffer();
+        (fb.width as _, fb.height as _)
+    }
+
+    // RGBA8 of the bound target, rows ordered from top to bottom
+    pub fn get_pixels(
+        &self
+    ) -> &[u8] {
+        &self.framebuffer().color
+    }
+
+    // window-space depth in [0, 1] of the bound target, rows ordered from top to bottom
+    pub fn get_depth(
+        &self
+    ) -> &[f32] {
+        &self.framebuffer().depth
+    }
+
+    pub fn set_clear_color(
+        &mut self,
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32
+    ) {
+        self.clear_color = [r, g, b, a];
+    }
+
+    fn transform(
+        m: &Matrix4,
+        v: &Vector3
+    ) -> [f32; 4] {
+        let e = &m.0;
+        [
+            e[ 0] * v.x + e[ 4] * v.y + e[ 8] * v.z + e[12],
+            e[ 1] * v.x + e[ 5] * v.y + e[ 9] * v.z + e[13],
+            e[ 2] * v.x + e[ 6] * v.y + e[10] * v.z + e[14],
+            e[ 3] * v.x + e[ 7] * v.y + e[11] * v.z + e[15],
+        ]
+    }
+
+    // mirrors the vertex shaders in shaders/*/vertex.glsl
+    fn vertex_varying(
+        material: &dyn Material,
+        geo: &BufferGeometry,
+        index: usize,
+        model_view: &Matrix4,
+        normal_matrix: &Matrix4
+    ) -> Varying {
+        let mut varying = Varying {
+            color: [1.0; 4],
+            ..Default::default()
+        };
+
+        if material.get_data().vertex_colors {
+            if let Some(c) = geo.colors.as_ref().and_then(|colors| colors.get(index)) {
+                varying.color = [c[0], c[1], c[2], 1.0];
+            }
+        }
+
+        // short attributes leave the defaults for the missing vertices
+        if let Some(normal) = geo.normals.as_ref().and_then(|normals| normals.get(index)) {
+            varying.normal = match material.get_kind() {
+                MaterialKind::Normal(_) => *normal,
+                _ => normal.transform_direction(normal_matrix),
+            };
+        }
+
+        if let Some(positions) = &geo.positions {
+            varying.position = positions[index].apply_matrix4(model_view);
+        }
+
+        if let Some(uv) = geo.uvs.as_ref().and_then(|uvs| uvs.get(index)) {
+            varying.uv = *uv;
+        }
+
+        varying.uv2 = match &geo.uvs2 {
+            Some(uvs2) => uvs2.get(index).copied().unwrap_or_default(),
+            None => varying.uv,
+        };
+
+        varying
+    }
+
+    // the gpu derives this from screen space derivatives, here it's constant per triangle
+    fn set_tangents(
+        tri: &mut [ClipVertex; 3]
+    ) {
+        let [a, b, c] = tri.map(|v| v.varying);
+        let e1 = b.position.sub(&a.position);
+        let e2 = c.position.sub(&a.position);
+        let (du1, dv1) = (b.uv[0] - a.uv[0], b.uv[1] - a.uv[1]);
+        let (du2, dv2) = (c.uv[0] - a.uv[0], c.uv[1] - a.uv[1]);
+
+        let det = du1 * dv2 - du2 * dv1;
+        if det == 0.0 {
+            return;
+        }
+        let tangent = e1.mul_scalar(dv2).sub(&e2.mul_scalar(dv1)).div_scalar(det);
+        let bitangent = e2.mul_scalar(du1).sub(&e1.mul_scalar(du2)).div_scalar(det);
+
+        for v in tri {
+            v.varying.tangent = tangent;
+            v.varying.bitangent = bitangent;
+        }
+    }
+
+    fn sample_map(
+        map: &Option<TextureRef>,
+        uv: &UV
+    ) -> [f32; 4] {
+        match map {
+            Some(map) => map.borrow().sample(uv[0], uv[1]),
+            None => [1.0; 4],
+        }
+    }
+
+    // mirrors the fragment shaders in shaders/*/frag.glsl
+    fn fragment_color(
+        material: &dyn Material,
+        lights: &Lights,
+        varying: &Varying,
+        front_facing: bool
+    ) -> [f32; 4] {
+        let opacity = material.get_data().opacity;
+
+        match material.get_kind() {
+            MaterialKind::Basic(basic) => {
+                let texel = Self::sample_map(&basic.map, &varying.uv);
+                [
+                    basic.color[0] * varying.color[0] * texel[0],
+                    basic.color[1] * varying.color[1] * texel[1],
+                    basic.color[2] * varying.color[2] * texel[2],
+                    opacity * varying.color[3] * texel[3],
+                ]
+            },
+            MaterialKind::Normal(_) => {
+                let n = varying.normal.mul_scalar(0.5).add(&Vector3::new(0.5, 0.5, 0.5));
+                let c = n.x * 0.25 + n.y * 0.50 + n.z * 0.25;
+                [c, c, c, opacity]
+            },
+            MaterialKind::Lambert(lambert) => {
+                let texel = Self::sample_map(&lambert.map, &varying.uv);
+                let color = [0, 1, 2].map(|i| lambert.color[i] * texel[i]);
+                let c = Self::shade_lit(
+                    lights, varying, front_facing, &color, None, &lambert.emissive
+                );
+                [c[0], c[1], c[2], opacity * varying.color[3] * texel[3]]
+            },
+            MaterialKind::Phong(phong) => {
+                let texel = Self::sample_map(&phong.map, &varying.uv);
+                let color = [0, 1, 2].map(|i| phong.color[i] * texel[i]);
+                let c = Self::shade_lit(
+                    lights, varying, front_facing, &color, Some((&phong.specular, phong.shininess)), &phong.emissive
+                );
+                [c[0], c[1], c[2], opacity * varying.color[3] * texel[3]]
+            },
+            MaterialKind::Standard(standard) => {
+                let texel = Self::sample_map(&standard.map, &varying.uv);
+                let c = Self::shade_standard(lights, varying, front_facing, standard, &texel);
+                [c[0], c[1], c[2], opacity * varying.color[3] * texel[3]]
+            },
+            MaterialKind::Shader(shader) => {
+                // glsl can't run here, so it's drawn unlit with its color uniform when it has one
+                let color = match shader.get_uniform("color") {
+                    Some(Uniform::Vector3(c)) => c.to_slice(),
+                    _ => [1.0; 3],
+                };
+                [
+                    color[0] * varying.color[0],
+                    color[1] * varying.color[1],
+                    color[2] * varying.color[2],
+                    opacity * varying.color[3],
+                ]
+            },
+        }
+    }
+
+    fn distance_attenuation(
+        distance: f32,
+        cutoff: f32,
+        decay: f32
+    ) -> f32 {
+        let mut f = 1.0 / distance.powf(decay).max(0.01);
+        if cutoff > 0.0 {
+            f *= (1.0 - (distance / cutoff).powi(4)).clamp(0.0, 1.0).powi(2);
+        }
+        f
+    }
+
+    // mirrors shaders/phong/frag.glsl
+    fn shade_lit(
+        lights: &Lights,
+        varying: &Varying,
+        front_facing: bool,
+        color: &RGB,
+        specular: Option<(&RGB, f32)>,
+        emissive: &RGB
+    ) -> RGB {
+        let normal = Self::shading_normal(varying, front_facing);
+        let view_dir = varying.position.neg().normalize();
+
+        let mut direct = [0.0; 3];
+        let mut reflected = [0.0; 3];
+
+        let indirect = Self::gather_lights(lights, &varying.position, &normal, &mut |dir, radiance| {
+            let dot_nl = normal.dot(dir).max(0.0);
+            for i in 0..3 {
+                direct[i] += radiance[i] * dot_nl;
+            }
+
+            if let Some((specular, shininess)) = specular {
+                if dot_nl > 0.0 {
+                    let half = dir.add(&view_dir).normalize();
+                    let s = normal.dot(&half).max(0.0).powf(shininess);
+                    for i in 0..3 {
+                        reflected[i] += radiance[i] * specular[i] * s;
+                    }
+                }
+            }
+        });
+
+        [0, 1, 2].map(|i|
+            color[i] * varying.color[i] * (indirect[i] + direct[i]) + reflected[i] + emissive[i]
+        )
+    }
+
+    // mirrors shaders/standard/frag.glsl
+    fn shade_standard(
+        lights: &Lights,
+        varying: &Varying,
+        front_facing: bool,
+        material: &MeshStandardMaterial,
+        texel: &[f32; 4]
+    ) -> RGB {
+        let mut normal = varying.normal.normalize();
+        if material.normal_map.is_some() {
+            let n = Self::sample_map(&material.normal_map, &varying.uv);
+            let scale = material.normal_scale;
+            normal = varying.tangent.normalize().mul_scalar((n[0] * 2.0 - 1.0) * scale)
+                .add(&varying.bitangent.normalize().mul_scalar((n[1] * 2.0 - 1.0) * scale))
+                .add(&normal.mul_scalar(n[2] * 2.0 - 1.0))
+                .normalize();
+        }
+        if !front_facing {
+            normal = normal.neg();
+        }
+        let view_dir = varying.position.neg().normalize();
+
+        let mr = Self::sample_map(&material.metalness_roughness_map, &varying.uv);
+        let metalness = material.metalness * mr[2];
+        let base = [0, 1, 2].map(|i| material.color[i] * varying.color[i] * texel[i]);
+        let diffuse_color = base.map(|c| c * (1.0 - metalness));
+        let specular_color = base.map(|c| 0.04 + (c - 0.04) * metalness);
+        let roughness = (material.roughness * mr[1]).clamp(0.0525, 1.0);
+        let occlusion = 1.0 + material.occlusion_strength *
+            (Self::sample_map(&material.occlusion_map, &varying.uv2)[0] - 1.0);
+        let emissive = Self::sample_map(&material.emissive_map, &varying.uv);
+        let a2 = roughness.powi(4);
+
+        let mut direct = [0.0; 3];
+
+        let indirect = Self::gather_lights(lights, &varying.position, &normal, &mut |dir, radiance| {
+            let dot_nl = normal.dot(dir).clamp(0.0, 1.0);
+            if dot_nl <= 0.0 {
+                return;
+            }
+            let half = dir.add(&view_dir).normalize();
+            let dot_nv = normal.dot(&view_dir).clamp(1e-4, 1.0);
+            let dot_nh = normal.dot(&half).clamp(0.0, 1.0);
+            let dot_vh = view_dir.dot(&half).clamp(0.0, 1.0);
+
+            let d = dot_nh * dot_nh * (a2 - 1.0) + 1.0;
+            let ggx = a2 / (PI * d * d);
+            let vis_v = dot_nl * (a2 + (1.0 - a2) * dot_nv * dot_nv).sqrt();
+            let vis_l = dot_nv * (a2 + (1.0 - a2) * dot_nl * dot_nl).sqrt();
+            let vis = 0.5 / (vis_v + vis_l).max(1e-6);
+            let schlick = (1.0 - dot_vh).powi(5);
+
+            for i in 0..3 {
+                let fresnel = specular_color[i] + (1.0 - specular_color[i]) * schlick;
+                direct[i] += radiance[i] * dot_nl * (diffuse_color[i] + PI * fresnel * vis * ggx);
+            }
+        });
+
+        [0, 1, 2].map(|i|
+            diffuse_color[i] * indirect[i] * occlusion + direct[i] + material.emissive[i] * emissive[i]
+        )
+    }
+
+    fn shading_normal(
+        varying: &Varying,
+        front_facing: bool
+    ) -> Vector3 {
+        let normal = varying.normal.normalize();
+        if front_facing {
+            normal
+        }
+        else {
+            normal.neg()
+        }
+    }
+
+    // calls direct for every punctual light with its direction and attenuated radiance,
+    // returns the ambient and hemisphere irradiance
+    fn gather_lights(
+        lights: &Lights,
+        position: &Vector3,
+        normal: &Vector3,
+        direct: &mut dyn FnMut(&Vector3, &RGB)
+    ) -> RGB {
+        for light in &lights.directional {
+            direct(&light.direction, &light.color);
+        }
+
+        for light in &lights.point {
+            let to_light = light.position.sub(position);
+            let f = Self::distance_attenuation(to_light.length(), light.distance, light.decay);
+            direct(&to_light.normalize(), &light.color.map(|c| c * f));
+        }
+
+        for light in &lights.spot {
+            let to_light = light.position.sub(position);
+            let dir = to_light.normalize();
+            let angle_cos = dir.dot(&light.direction);
+            let spot = Self::smoothstep(light.cone_cos, light.penumbra_cos, angle_cos);
+            if spot > 0.0 {
+                let f = spot * Self::distance_attenuation(to_light.length(), light.distance, light.decay);
+                direct(&dir, &light.color.map(|c| c * f));
+            }
+        }
+
+        let mut irradiance = lights.ambient;
+        for light in &lights.hemisphere {
+            let w = normal.dot(&light.direction) * 0.5 + 0.5;
+            for (i, e) in irradiance.iter_mut().enumerate() {
+                *e += light.ground_color[i] + (light.sky_color[i] - light.ground_color[i]) * w;
+            }
+        }
+
+        irradiance
+    }
+
+    fn smoothstep(
+        edge0: f32,
+        edge1: f32,
+        x: f32
+    ) -> f32 {
+        let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Renderer for SoftRenderer {
+    fn clear(
+        &mut self
+    ) {
+        let color = self.clear_color;
+        self.framebuffer_mut().clear(&color);
+    }
+
+    fn set_lights(
+        &mut self,
+        lights: &Lights
+    ) {
+        // the same lights the gl shaders would use
+        self.lights = lights.capped();
+    }
+
+    fn create_buffers(
+        &mut self,
+        _geo: &mut BufferGeometry
+    ) {
+    }
+
+    fn delete_buffers(
+        &mut self,
+        _geo: &mut BufferGeometry
+    ) {
+    }
+
+    fn delete_texture(
+        &mut self,
+        _texture: &Texture
+    ) {
+    }
+
+    fn draw(
+        &mut self,
+        object: &mut dyn Renderable,
+        projection: &Matrix4,
+        model_view: &Matrix4
+    ) {
+        let geo = object.get_geometry();
+        let positions = match &geo.positions {
+            Some(positions) => positions,
+            None => return,
+        };
+
+        let material = object.get_material().borrow();
+        let material = &*material;
+        let mat = material.get_data();
+
+        let state = RasterState {
+            side: mat.side,
+            depth_test: mat.depth_test,
+            depth_write: mat.depth_write,
+            blend: mat.is_transparent(),
+        };
+        let lights = &self.lights;
+        let shader = |varying: &Varying, front_facing: bool|
+            Self::fragment_color(material, lights, varying, front_facing);
+
+        let mvp = projection.mul(model_view);
+        let normal_matrix = model_view.invert().transpose();
+        let normal_mapped = geo.uvs.is_some() && matches!(
+            material.get_kind(), MaterialKind::Standard(standard) if standard.normal_map.is_some()
+        );
+
+        // vertex stage
+        let vertices = positions.iter().enumerate()
+            .map(|(i, p)| ClipVertex {
+                pos: Self::transform(&mvp, p),
+                varying: Self::vertex_varying(material, geo, i, model_view, &normal_matrix),
+            })
+            .collect::<Vec<_>>();
+
+        let indices = match &geo.indices {
+            Some(indices) => indices.iter()
+                .map(|i| *i as usize)
+                .collect::<Vec<_>>(),
+            None => (0..vertices.len()).collect(),
+        };
+
+        // primitive assembly, into the bound target picked field by field as the shader holds the lights
+        let fb = match self.target {
+            Some(id) => self.targets.get_mut(&id).unwrap(),
+            None => &mut self.screen,
+        };
+        // primitives indexing past the vertices are skipped
+        match geo.mode {
+            BufferGeometryMode::Triangles => {
+                for tri in indices.chunks_exact(3) {
+                    let (Some(a), Some(b), Some(c)) = (vertices.get(tri[0]), vertices.get(tri[1]), vertices.get(tri[2])) else {
+                        continue;
+                    };
+                    let mut tri = [*a, *b, *c];
+                    if normal_mapped {
+                        Self::set_tangents(&mut tri);
+                    }
+                    if mat.wireframe {
+                        fb.draw_triangle_edges(tri, &state, &shader);
+                    }
+                    else {
+                        fb.draw_triangle(tri, &state, &shader);
+                    }
+                }
+            },
+            BufferGeometryMode::Lines => {
+                for line in indices.chunks_exact(2) {
+                    if let (Some(a), Some(b)) = (vertices.get(line[0]), vertices.get(line[1])) {
+                        fb.draw_line(*a, *b, &state, &shader);
+                    }
+                }
+            },
+            BufferGeometryMode::LineStrip => {
+                for line in indices.windows(2) {
+                    if let (Some(a), Some(b)) = (vertices.get(line[0]), vertices.get(line[1])) {
+                        fb.draw_line(*a, *b, &state, &shader);
+                    }
+                }
+            },
+        }
+    }
+
+    fn present(
+        &mut self
+    ) {
+    }
+
+    fn set_render_target(
+        &mut self,
+        target: Option<&RenderTarget>
+    ) {
+        self.target = target.map(|target| {
+            let fb = self.targets.entry(target.id)
+                .or_insert_with(|| Framebuffer::new(target.width, target.height, target.depth_buffer));
+
+            if fb.width != target.width as usize ||
+                fb.height != target.height as usize ||
+                fb.depth_test != target.depth_buffer {
+                *fb = Framebuffer::new(target.width, target.height, target.depth_buffer);
+            }
+
+            target.id
+        });
+    }
+
+    fn delete_render_target(
+        &mut self,
+        target: &RenderTarget
+    ) {
+        if self.target == Some(target.id) {
+            self.target = None;
+        }
+        self.targets.remove(&target.id);
+    }
+
+    fn read_pixels(
+        &mut self
+    ) -> Image {
+        let fb = self.framebuffer();
+        Image {
+            width: fb.width as _,
+            height: fb.height as _,
+            data: fb.color.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{rc::Rc, cell::RefCell};
+    use crate::{
+        core::{Object3d, Geometrical},
+        math::{Vector3, Quaternion, Matrix4},
+        camera::{Camera, PerspectiveCamera},
+        geometry::Box3,
+        material::MeshBasicMaterial,
+        object::Mesh,
+        scene::Scene,
+    };
+    use super::*;
+
+    fn pixel(
+        img: &Image,
+        x: usize,
+        y: usize
+    ) -> [u8; 4] {
+        let i = (y * img.width as usize + x) * 4;
+        [img.data[i], img.data[i + 1], img.data[i + 2], img.data[i + 3]]
+    }
+
+    // a box of the given size and color, its center at z
+    fn cube(
+        size: f32,
+        color: [f32; 3],
+        z: f32
+    ) -> Rc<RefCell<Mesh>> {
+        let mesh = Mesh::new_ex(&Box3::new(size, size, size), MeshBasicMaterial::new(color));
+        mesh.borrow_mut().get_object_mut().set_position(Vector3::new(0.0, 0.0, z));
+        mesh
+    }
+
+    #[test]
+    fn clears_to_the_clear_color() {
+        let mut renderer = SoftRenderer::new(8, 4);
+        renderer.set_clear_color(0.0, 0.0, 1.0, 1.0);
+        renderer.clear();
+
+        let img = renderer.read_pixels();
+        assert_eq!((img.width, img.height), (8, 4));
+        assert!(img.data.chunks_exact(4).all(|p| p == [0, 0, 255, 255]));
+        assert!(renderer.get_depth().iter().all(|d| *d == 1.0));
+    }
+
+    #[test]
+    fn fills_what_the_camera_sees() {
+        let renderer = Rc::new(RefCell::new(SoftRenderer::new(32, 32)));
+        let mut scene = Scene::new(renderer.clone());
+        scene.add(cube(1.0, [1.0, 0.0, 0.0], -5.0));
+
+        let mut camera = PerspectiveCamera::new(60.0, 1.0, 0.1, 100.0);
+        scene.render(&mut camera);
+
+        let img = renderer.borrow_mut().read_pixels();
+        assert_eq!(pixel(&img, 16, 16), [255, 0, 0, 255]);
+        for (x, y) in [(0, 0), (31, 0), (0, 31), (31, 31)] {
+            assert_eq!(pixel(&img, x, y), [0, 0, 0, 255]);
+        }
+        let filled = img.data.chunks_exact(4).filter(|p| p[0] == 255).count();
+        assert!(filled > 16 && filled < 32 * 32 / 2, "{}", filled);
+    }
+
+    #[test]
+    fn shows_the_vertex_colors_of_default_meshes() {
+        let renderer = Rc::new(RefCell::new(SoftRenderer::new(32, 32)));
+        let mut scene = Scene::new(renderer.clone());
+        // box geometries are shaded red by their colors
+        let mesh = Mesh::new(&Box3::new(1.0, 1.0, 1.0));
+        mesh.borrow_mut().get_object_mut().set_position(Vector3::new(0.0, 0.0, -5.0));
+        scene.add(mesh);
+
+        let mut camera = PerspectiveCamera::new(60.0, 1.0, 0.1, 100.0);
+        scene.render(&mut camera);
+
+        let [r, g, b, _] = pixel(&renderer.borrow_mut().read_pixels(), 16, 16);
+        assert!(r > 0 && g == 0 && b == 0, "{:?}", [r, g, b]);
+    }
+
+    #[test]
+    fn keeps_the_nearest_surface() {
+        let camera = PerspectiveCamera::new(60.0, 1.0, 0.1, 100.0);
+        let projection = camera.get_data().proj_matrix.clone();
+        let model_view = |z: f32| Matrix4::compose(&Vector3::new(0.0, 0.0, z), &Quaternion::identity(), &Vector3::one());
+
+        let near = cube(1.0, [0.0, 1.0, 0.0], -5.0);
+        let far = cube(4.0, [1.0, 0.0, 0.0], -8.0);
+
+        // either order, the near box covers the middle and the far one shows around it
+        for near_first in [true, false] {
+            let mut renderer = SoftRenderer::new(32, 32);
+            renderer.clear();
+            let mut draws = [(&near, -5.0), (&far, -8.0)];
+            if !near_first {
+                draws.reverse();
+            }
+            for (mesh, z) in draws {
+                renderer.draw(&mut *mesh.borrow_mut(), &projection, &model_view(z));
+            }
+
+            let img = renderer.read_pixels();
+            assert_eq!(pixel(&img, 16, 16), [0, 255, 0, 255]);
+            assert_eq!(pixel(&img, 16, 8), [255, 0, 0, 255]);
+            assert_eq!(pixel(&img, 0, 0), [0, 0, 0, 255]);
+        }
+
+        // without the depth test the last draw wins
+        near.borrow().get_material().borrow_mut().get_data_mut().depth_test = false;
+        far.borrow().get_material().borrow_mut().get_data_mut().depth_test = false;
+        let mut renderer = SoftRenderer::new(32, 32);
+        renderer.clear();
+        renderer.draw(&mut *near.borrow_mut(), &projection, &model_view(-5.0));
+        renderer.draw(&mut *far.borrow_mut(), &projection, &model_view(-8.0));
+        assert_eq!(pixel(&renderer.read_pixels(), 16, 16), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn skips_primitives_with_out_of_range_indices() {
+        let camera = PerspectiveCamera::new(60.0, 1.0, 0.1, 100.0);
+        let projection = camera.get_data().proj_matrix.clone();
+        let model_view = Matrix4::compose(&Vector3::new(0.0, 0.0, -5.0), &Quaternion::identity(), &Vector3::one());
+
+        let mesh = cube(1.0, [1.0, 0.0, 0.0], -5.0);
+        {
+            let mut mesh = mesh.borrow_mut();
+            let geo = mesh.get_geometry_mut();
+            let mut indices = geo.get_indices().unwrap().clone();
+            indices.extend([0, 1, 100]);
+            geo.set_indices(Some(indices));
+        }
+
+        let mut renderer = SoftRenderer::new(32, 32);
+        renderer.clear();
+        renderer.draw(&mut *mesh.borrow_mut(), &projection, &model_view);
+        assert_eq!(pixel(&renderer.read_pixels(), 16, 16), [255, 0, 0, 255]);
+    }
+}
@@ -1,70 +1,332 @@
 use std::{rc::Rc, cell::RefCell};
 use crate::{
-    core::GeometricalRenderable, 
-    renderer::GlRenderer, 
+    core::{Object3d, ObjectRef, Renderable, init_object_ref}, 
+    renderer::{Renderer, Lights}, 
     camera::ObjectCamera,
+    math::{Vector3, Frustum},
 };
 
 pub struct Scene {
-    pub renderer: Rc<RefCell<GlRenderer>>,
-    pub objects: Vec<Rc<RefCell<dyn GeometricalRenderable>>>,
+    pub renderer: Rc<RefCell<dyn Renderer>>,
+    pub objects: Vec<ObjectRef>,
 }
 
 impl Drop for Scene {
     fn drop(
         &mut self
     ) {
-        let renderer = &self.renderer.borrow_mut();
-        for obj in &mut self.objects {
-            obj.borrow_mut().drop(renderer);
-        }
+        self.drop_objects();
     }
 }
 
 impl Scene {
-    pub fn new(
-        renderer: Rc<RefCell<GlRenderer>>
-    ) -> Self {
+    pub fn new<R>(
+        renderer: Rc<RefCell<R>>
+    ) -> Self where R: Renderer + 'static {
         Self {
             renderer,
             objects: vec![],
         }
     }
 
+    fn drop_objects(
+        &mut self
+    ) {
+        let renderer = self.renderer.clone();
+        let renderer = &mut *renderer.borrow_mut();
+        self.traverse(&mut |obj| {
+            if let Some(geo) = obj.borrow_mut().as_geometrical_mut() {
+                geo.drop(renderer);
+            }
+        });
+    }
+
     pub fn reset(
         &mut self
     ) {
-        let renderer = &self.renderer.borrow_mut();
-        for obj in &mut self.objects {
-            obj.borrow_mut().drop(renderer);
-        }
+        self.drop_objects();
         self.objects = vec![];
     }
 
+    // objects at the scene level have no parent
     pub fn add<T>(
         &mut self,
         obj: Rc<RefCell<T>>
-    ) where T: GeometricalRenderable + 'static {
+    ) where T: Object3d + 'static {
+        let obj: ObjectRef = obj;
+        init_object_ref(&obj);
+        Self::detach(&obj);
         self.objects.push(obj);
     }
 
+    pub fn remove(
+        &mut self,
+        obj: &ObjectRef
+    ) -> bool {
+        let Some(index) = self.objects.iter().position(|o| Rc::ptr_eq(o, obj)) else {
+            return false;
+        };
+
+        self.objects.remove(index);
+        true
+    }
+
+    // like add(), but keeps the object's world transform
+    pub fn attach<T>(
+        &mut self,
+        obj: Rc<RefCell<T>>
+    ) where T: Object3d + 'static {
+        let obj: ObjectRef = obj;
+        init_object_ref(&obj);
+        let world = obj.borrow().get_object().compute_world_matrix();
+        Self::detach(&obj);
+        obj.borrow_mut().get_object_mut().set_matrix(world);
+        self.objects.push(obj);
+    }
+
+    fn detach(
+        obj: &ObjectRef
+    ) {
+        let parent = obj.borrow().get_object().get_parent();
+        if let Some(parent) = parent {
+            // can't fail, obj was just borrowed
+            let _ = parent.borrow_mut().get_object_mut().remove(obj);
+        }
+    }
+
+    // returns the objects that were borrowed elsewhere, see ObjectData::update_matrix_world()
+    pub fn update_matrix_world(
+        &mut self,
+        force: bool
+    ) -> Vec<ObjectRef> {
+        let mut skipped = vec![];
+        for object in &self.objects {
+            match object.try_borrow_mut() {
+                Ok(mut obj) => {
+                    obj.get_object_mut().update_matrix_world_ex(None, force, &mut skipped);
+                },
+                Err(_) => {
+                    skipped.push(object.clone());
+                }
+            }
+        }
+        skipped
+    }
+
+    // no borrow is held while the callback runs
+    pub fn traverse(
+        &self,
+        cb: &mut dyn FnMut(&ObjectRef)
+    ) {
+        for object in &self.objects {
+            Self::traverse_object(object, false, cb);
+        }
+    }
+
+    // invisible objects are skipped together with their descendants
+    pub fn traverse_visible(
+        &self,
+        cb: &mut dyn FnMut(&ObjectRef)
+    ) {
+        for object in &self.objects {
+            Self::traverse_object(object, true, cb);
+        }
+    }
+
+    // depth first, in insertion order
+    pub fn get_object_by_id(
+        &self,
+        id: usize
+    ) -> Option<ObjectRef> {
+        self.find_object(&|obj| obj.get_id() == id)
+    }
+
+    pub fn get_object_by_name(
+        &self,
+        name: &str
+    ) -> Option<ObjectRef> {
+        self.find_object(&|obj| obj.get_name() == name)
+    }
+
+    pub fn get_objects_by_property(
+        &self,
+        pred: &dyn Fn(&dyn Object3d) -> bool
+    ) -> Vec<ObjectRef> {
+        let mut res = vec![];
+        self.traverse(&mut |obj| {
+            if pred(&*obj.borrow()) {
+                res.push(obj.clone());
+            }
+        });
+        res
+    }
+
+    fn find_object(
+        &self,
+        pred: &dyn Fn(&dyn Object3d) -> bool
+    ) -> Option<ObjectRef> {
+        self.objects.iter()
+            .find_map(|object| Self::find_in_object(object, pred))
+    }
+
+    fn find_in_object(
+        object: &ObjectRef,
+        pred: &dyn Fn(&dyn Object3d) -> bool
+    ) -> Option<ObjectRef> {
+        let obj = object.borrow();
+        if pred(&*obj) {
+            return Some(object.clone());
+        }
+
+        obj.get_object().get_children().iter()
+            .find_map(|child| Self::find_in_object(child, pred))
+    }
+
+    fn traverse_object(
+        object: &ObjectRef,
+        only_visible: bool,
+        cb: &mut dyn FnMut(&ObjectRef)
+    ) {
+        if only_visible && !object.borrow().get_object().visible {
+            return;
+        }
+
+        cb(object);
+
+        let children = object.borrow().get_object().get_children().clone();
+        for child in &children {
+            Self::traverse_object(child, only_visible, cb);
+        }
+    }
+
     pub fn render(
         &mut self,
         camera: &mut dyn ObjectCamera
     ) {
+        // objects borrowed elsewhere are left out of the frame, except the camera which updates itself
+        self.update_matrix_world(false);
         camera.update_matrix();
+
+        let cam = camera.get_data();
+        let frustum = Frustum::from_matrix(&cam.proj_matrix.mul(&cam.world_matrix_inverse));
+
+        let mut list = RenderList::default();
+        for object in &self.objects {
+            Self::collect_object(object, camera, &frustum, &mut list);
+        }
+        list.sort();
         
-        let renderer = &self.renderer.borrow_mut();
+        let renderer = &mut *self.renderer.borrow_mut();
         
         renderer.clear();
+        renderer.set_lights(&list.lights);
+
+        for item in list.opaque.iter().chain(list.transparent.iter()) {
+            if let Some(renderable) = item.object.borrow_mut().as_renderable_mut() {
+                renderable.render(camera, renderer);
+            }
+        }
+
+        renderer.present();
+    }
+
+    fn collect_object(
+        object: &ObjectRef,
+        camera: &dyn ObjectCamera,
+        frustum: &Frustum,
+        list: &mut RenderList
+    ) {
+        let children = match object.try_borrow_mut() {
+            Ok(mut obj) => {
+                if !obj.get_object().visible {
+                    return;
+                }
+                
+                let render_order = obj.get_object().render_order;
+                let frustum_culled = obj.get_object().frustum_culled;
+                let world = obj.get_object().get_world_matrix().to_slice().to_vec();
+                
+                if let Some(light) = obj.as_light() {
+                    list.lights.add(
+                        light, 
+                        obj.get_object().get_world_matrix(), 
+                        &camera.get_data().world_matrix_inverse
+                    );
+                }
+
+                if let Some(renderable) = obj.as_renderable_mut() {
+                    let in_frustum = !frustum_culled || Self::intersects_frustum(renderable, frustum);
+                    let material = renderable.get_material().borrow();
+                    let mat = material.get_data();
+                    if mat.visible && in_frustum {
+                        let item = RenderItem {
+                            object: object.clone(),
+                            render_order,
+                            // view space z of the object's origin
+                            z: Vector3::new(world[12], world[13], world[14])
+                                .apply_matrix4(&camera.get_data().world_matrix_inverse)
+                                .z,
+                        };
+                        if mat.is_transparent() {
+                            list.transparent.push(item);
+                        }
+                        else {
+                            list.opaque.push(item);
+                        }
+                    }
+                }
 
-        for object in &mut self.objects {
-            let obj = &mut object.borrow_mut();
-            if obj.get_object().visible {
-                obj.render(None, camera, renderer);
+                obj.get_object().get_children().clone()
+            },
+            Err(_) => {
+                // the camera is borrowed by the caller when it is part of the scene
+                if !std::ptr::addr_eq(object.as_ptr(), camera) || !camera.get_object().visible {
+                    return;
+                }
+                
+                camera.get_object().get_children().clone()
             }
+        };
+
+        for child in &children {
+            Self::collect_object(child, camera, frustum, list);
         }
+    }
 
-        renderer.swap_window();
+    // the geometry's bounding sphere is computed on first use
+    fn intersects_frustum(
+        renderable: &mut dyn Renderable,
+        frustum: &Frustum
+    ) -> bool {
+        let world = renderable.get_object().get_world_matrix().clone();
+        let sphere = renderable.get_geometry_mut().compute_bounding_sphere();
+        
+        frustum.intersects_sphere(&sphere.apply_matrix4(&world))
+    }
+}
+
+struct RenderItem {
+    object: ObjectRef,
+    render_order: usize,
+    z: f32,
+}
+
+#[derive(Default)]
+struct RenderList {
+    opaque: Vec<RenderItem>,
+    transparent: Vec<RenderItem>,
+    lights: Lights,
+}
+
+impl RenderList {
+    // transparent objects are drawn back to front, so they blend over what is behind them
+    fn sort(
+        &mut self
+    ) {
+        self.opaque.sort_by_key(|item| item.render_order);
+        self.transparent.sort_by(|a, b| 
+            a.render_order.cmp(&b.render_order)
+                .then(a.z.total_cmp(&b.z))
+        );
     }
 }
\ No newline at end of file
@@ -0,0 +1,15 @@
+#version 430
+in vec3 vertex_color;
+in vec2 uv;
+
+uniform vec3 color;
+uniform float opacity;
+uniform bool use_map;
+uniform sampler2D map;
+
+out vec4 out_color;
+
+void main() {
+    vec4 texel = use_map? texture(map, uv): vec4(1.0);
+    out_color = vec4(color * vertex_color * texel.rgb, opacity * texel.a);
+}
\ No newline at end of file
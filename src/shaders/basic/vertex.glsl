@@ -0,0 +1,17 @@
+#version 430
+layout (location = 0) in vec3 in_position;
+layout (location = 2) in vec3 in_color;
+layout (location = 3) in vec2 in_uv;
+
+uniform mat4 projection;
+uniform mat4 model_view;
+uniform bool vertex_colors;
+
+out vec3 vertex_color;
+out vec2 uv;
+
+void main() {
+    gl_Position = projection * model_view * vec4(in_position, 1.0);
+    vertex_color = vertex_colors? in_color: vec3(1.0);
+    uv = in_uv;
+}
\ No newline at end of file
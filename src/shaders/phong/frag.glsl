@@ -0,0 +1,107 @@
+#version 430
+#define MAX_DIR_LIGHTS 4
+#define MAX_POINT_LIGHTS 8
+#define MAX_SPOT_LIGHTS 4
+#define MAX_HEMI_LIGHTS 2
+
+in vec3 view_position;
+in vec3 view_normal;
+in vec3 vertex_color;
+in vec2 uv;
+
+uniform vec3 color;
+uniform vec3 emissive;
+uniform vec3 specular;
+uniform float shininess;
+uniform float opacity;
+uniform bool use_map;
+uniform sampler2D map;
+
+uniform vec3 ambient_light;
+
+uniform int num_dir_lights;
+uniform vec3 dir_light_direction[MAX_DIR_LIGHTS];
+uniform vec3 dir_light_color[MAX_DIR_LIGHTS];
+
+uniform int num_point_lights;
+uniform vec3 point_light_position[MAX_POINT_LIGHTS];
+uniform vec3 point_light_color[MAX_POINT_LIGHTS];
+uniform float point_light_distance[MAX_POINT_LIGHTS];
+uniform float point_light_decay[MAX_POINT_LIGHTS];
+
+uniform int num_spot_lights;
+uniform vec3 spot_light_position[MAX_SPOT_LIGHTS];
+uniform vec3 spot_light_direction[MAX_SPOT_LIGHTS];
+uniform vec3 spot_light_color[MAX_SPOT_LIGHTS];
+uniform float spot_light_distance[MAX_SPOT_LIGHTS];
+uniform float spot_light_decay[MAX_SPOT_LIGHTS];
+uniform float spot_light_cone_cos[MAX_SPOT_LIGHTS];
+uniform float spot_light_penumbra_cos[MAX_SPOT_LIGHTS];
+
+uniform int num_hemi_lights;
+uniform vec3 hemi_light_direction[MAX_HEMI_LIGHTS];
+uniform vec3 hemi_light_sky_color[MAX_HEMI_LIGHTS];
+uniform vec3 hemi_light_ground_color[MAX_HEMI_LIGHTS];
+
+out vec4 out_color;
+
+vec3 normal;
+vec3 view_dir;
+vec3 irradiance;
+vec3 reflected;
+
+float distance_attenuation(float dist, float cutoff, float decay) {
+    float f = 1.0 / max(pow(dist, decay), 0.01);
+    if (cutoff > 0.0) {
+        f *= pow(clamp(1.0 - pow(dist / cutoff, 4.0), 0.0, 1.0), 2.0);
+    }
+    return f;
+}
+
+void add_light(vec3 dir, vec3 radiance) {
+    float dot_nl = max(dot(normal, dir), 0.0);
+    irradiance += radiance * dot_nl;
+    if (dot_nl > 0.0) {
+        vec3 h = normalize(dir + view_dir);
+        reflected += radiance * specular * pow(max(dot(normal, h), 0.0), shininess);
+    }
+}
+
+void main() {
+    normal = normalize(view_normal);
+    if (!gl_FrontFacing) {
+        normal = -normal;
+    }
+    view_dir = normalize(-view_position);
+    irradiance = ambient_light;
+    reflected = vec3(0.0);
+
+    for (int i = 0; i < num_dir_lights; i++) {
+        add_light(dir_light_direction[i], dir_light_color[i]);
+    }
+
+    for (int i = 0; i < num_point_lights; i++) {
+        vec3 to_light = point_light_position[i] - view_position;
+        float f = distance_attenuation(length(to_light), point_light_distance[i], point_light_decay[i]);
+        add_light(normalize(to_light), point_light_color[i] * f);
+    }
+
+    for (int i = 0; i < num_spot_lights; i++) {
+        vec3 to_light = spot_light_position[i] - view_position;
+        vec3 dir = normalize(to_light);
+        float spot = smoothstep(spot_light_cone_cos[i], spot_light_penumbra_cos[i], dot(dir, spot_light_direction[i]));
+        if (spot > 0.0) {
+            float f = spot * distance_attenuation(length(to_light), spot_light_distance[i], spot_light_decay[i]);
+            add_light(dir, spot_light_color[i] * f);
+        }
+    }
+
+    for (int i = 0; i < num_hemi_lights; i++) {
+        float w = dot(normal, hemi_light_direction[i]) * 0.5 + 0.5;
+        irradiance += mix(hemi_light_ground_color[i], hemi_light_sky_color[i], w);
+    }
+
+    vec4 texel = use_map? texture(map, uv): vec4(1.0);
+    vec3 c = color * vertex_color * texel.rgb * irradiance + reflected + emissive;
+    out_color = vec4(c, opacity * texel.a);
+}
\ No newline at end of file
@@ -0,0 +1,26 @@
+#version 430
+layout (location = 0) in vec3 in_position;
+layout (location = 1) in vec3 in_normal;
+layout (location = 2) in vec3 in_color;
+layout (location = 3) in vec2 in_uv;
+layout (location = 4) in vec2 in_uv2;
+
+uniform mat4 projection;
+uniform mat4 model_view;
+uniform bool vertex_colors;
+
+out vec3 view_position;
+out vec3 view_normal;
+out vec3 vertex_color;
+out vec2 uv;
+out vec2 uv2;
+
+void main() {
+    vec4 position = model_view * vec4(in_position, 1.0);
+    gl_Position = projection * position;
+    view_position = position.xyz;
+    view_normal = mat3(transpose(inverse(model_view))) * in_normal;
+    vertex_color = vertex_colors? in_color: vec3(1.0);
+    uv = in_uv;
+    uv2 = in_uv2;
+}
\ No newline at end of file
@@ -1,8 +0,0 @@
-#version 430
-in vec3 color;
-
-out vec4 out_color;
-
-void main() {
-    out_color = vec4(color, 1.0);
-}
\ No newline at end of file
@@ -0,0 +1,170 @@
+#version 430
+#define MAX_DIR_LIGHTS 4
+#define MAX_POINT_LIGHTS 8
+#define MAX_SPOT_LIGHTS 4
+#define MAX_HEMI_LIGHTS 2
+
+in vec3 view_position;
+in vec3 view_normal;
+in vec3 vertex_color;
+in vec2 uv;
+in vec2 uv2;
+
+uniform vec3 color;
+uniform vec3 emissive;
+uniform float metalness;
+uniform float roughness;
+uniform float opacity;
+uniform float normal_scale;
+uniform float occlusion_strength;
+
+uniform bool use_map;
+uniform sampler2D map;
+uniform bool use_metalness_roughness_map;
+uniform sampler2D metalness_roughness_map;
+uniform bool use_normal_map;
+uniform sampler2D normal_map;
+uniform bool use_occlusion_map;
+uniform sampler2D occlusion_map;
+uniform bool use_emissive_map;
+uniform sampler2D emissive_map;
+
+uniform vec3 ambient_light;
+
+uniform int num_dir_lights;
+uniform vec3 dir_light_direction[MAX_DIR_LIGHTS];
+uniform vec3 dir_light_color[MAX_DIR_LIGHTS];
+
+uniform int num_point_lights;
+uniform vec3 point_light_position[MAX_POINT_LIGHTS];
+uniform vec3 point_light_color[MAX_POINT_LIGHTS];
+uniform float point_light_distance[MAX_POINT_LIGHTS];
+uniform float point_light_decay[MAX_POINT_LIGHTS];
+
+uniform int num_spot_lights;
+uniform vec3 spot_light_position[MAX_SPOT_LIGHTS];
+uniform vec3 spot_light_direction[MAX_SPOT_LIGHTS];
+uniform vec3 spot_light_color[MAX_SPOT_LIGHTS];
+uniform float spot_light_distance[MAX_SPOT_LIGHTS];
+uniform float spot_light_decay[MAX_SPOT_LIGHTS];
+uniform float spot_light_cone_cos[MAX_SPOT_LIGHTS];
+uniform float spot_light_penumbra_cos[MAX_SPOT_LIGHTS];
+
+uniform int num_hemi_lights;
+uniform vec3 hemi_light_direction[MAX_HEMI_LIGHTS];
+uniform vec3 hemi_light_sky_color[MAX_HEMI_LIGHTS];
+uniform vec3 hemi_light_ground_color[MAX_HEMI_LIGHTS];
+
+out vec4 out_color;
+
+const float PI = 3.14159265359;
+
+vec3 normal;
+vec3 view_dir;
+vec3 diffuse_color;
+vec3 specular_color;
+float alpha;
+vec3 irradiance;
+vec3 direct;
+
+float distance_attenuation(float dist, float cutoff, float decay) {
+    float f = 1.0 / max(pow(dist, decay), 0.01);
+    if (cutoff > 0.0) {
+        f *= pow(clamp(1.0 - pow(dist / cutoff, 4.0), 0.0, 1.0), 2.0);
+    }
+    return f;
+}
+
+// cook-torrance with a ggx distribution, height correlated smith visibility and schlick fresnel.
+// punctual lights are scaled by PI so a white light of intensity 1 matches the lambert material
+void add_light(vec3 dir, vec3 radiance) {
+    float dot_nl = clamp(dot(normal, dir), 0.0, 1.0);
+    if (dot_nl <= 0.0) {
+        return;
+    }
+    vec3 h = normalize(dir + view_dir);
+    float dot_nv = clamp(dot(normal, view_dir), 1e-4, 1.0);
+    float dot_nh = clamp(dot(normal, h), 0.0, 1.0);
+    float dot_vh = clamp(dot(view_dir, h), 0.0, 1.0);
+
+    float a2 = alpha * alpha;
+    float d = dot_nh * dot_nh * (a2 - 1.0) + 1.0;
+    float ggx = a2 / (PI * d * d);
+    float vis_v = dot_nl * sqrt(a2 + (1.0 - a2) * dot_nv * dot_nv);
+    float vis_l = dot_nv * sqrt(a2 + (1.0 - a2) * dot_nl * dot_nl);
+    float vis = 0.5 / max(vis_v + vis_l, 1e-6);
+    vec3 fresnel = specular_color + (1.0 - specular_color) * pow(1.0 - dot_vh, 5.0);
+
+    direct += radiance * dot_nl * (diffuse_color + PI * fresnel * vis * ggx);
+}
+
+// tangent frame from screen space derivatives, so no tangent attribute is needed
+vec3 perturb_normal(vec3 n) {
+    vec3 q0 = dFdx(view_position);
+    vec3 q1 = dFdy(view_position);
+    vec2 st0 = dFdx(uv);
+    vec2 st1 = dFdy(uv);
+    float det = st0.x * st1.y - st1.x * st0.y;
+    if (det == 0.0) {
+        return n;
+    }
+    vec3 t = normalize((q0 * st1.y - q1 * st0.y) / det);
+    vec3 b = normalize((q1 * st0.x - q0 * st1.x) / det);
+    vec3 m = texture(normal_map, uv).xyz * 2.0 - 1.0;
+    m.xy *= normal_scale;
+    return normalize(t * m.x + b * m.y + n * m.z);
+}
+
+void main() {
+    normal = normalize(view_normal);
+    if (use_normal_map) {
+        normal = perturb_normal(normal);
+    }
+    if (!gl_FrontFacing) {
+        normal = -normal;
+    }
+    view_dir = normalize(-view_position);
+
+    vec4 texel = use_map? texture(map, uv): vec4(1.0);
+    vec4 mr = use_metalness_roughness_map? texture(metalness_roughness_map, uv): vec4(1.0);
+    float occlusion = use_occlusion_map? 1.0 + occlusion_strength * (texture(occlusion_map, uv2).r - 1.0): 1.0;
+    vec3 emissive_texel = use_emissive_map? texture(emissive_map, uv).rgb: vec3(1.0);
+
+    float metal = metalness * mr.b;
+    vec3 base = color * vertex_color * texel.rgb;
+    diffuse_color = base * (1.0 - metal);
+    specular_color = mix(vec3(0.04), base, metal);
+    float r = clamp(roughness * mr.g, 0.0525, 1.0);
+    alpha = r * r;
+
+    irradiance = ambient_light;
+    direct = vec3(0.0);
+
+    for (int i = 0; i < num_dir_lights; i++) {
+        add_light(dir_light_direction[i], dir_light_color[i]);
+    }
+
+    for (int i = 0; i < num_point_lights; i++) {
+        vec3 to_light = point_light_position[i] - view_position;
+        float f = distance_attenuation(length(to_light), point_light_distance[i], point_light_decay[i]);
+        add_light(normalize(to_light), point_light_color[i] * f);
+    }
+
+    for (int i = 0; i < num_spot_lights; i++) {
+        vec3 to_light = spot_light_position[i] - view_position;
+        vec3 dir = normalize(to_light);
+        float spot = smoothstep(spot_light_cone_cos[i], spot_light_penumbra_cos[i], dot(dir, spot_light_direction[i]));
+        if (spot > 0.0) {
+            float f = spot * distance_attenuation(length(to_light), spot_light_distance[i], spot_light_decay[i]);
+            add_light(dir, spot_light_color[i] * f);
+        }
+    }
+
+    for (int i = 0; i < num_hemi_lights; i++) {
+        float w = dot(normal, hemi_light_direction[i]) * 0.5 + 0.5;
+        irradiance += mix(hemi_light_ground_color[i], hemi_light_sky_color[i], w);
+    }
+
+    vec3 c = diffuse_color * irradiance * occlusion + direct + emissive * emissive_texel;
+    out_color = vec4(c, opacity * texel.a);
+}
\ No newline at end of file
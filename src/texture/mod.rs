@@ -0,0 +1,3 @@
+pub mod texture;
+
+pub use texture::*;
@@ -0,0 +1,296 @@
+use std::{
+    rc::Rc, 
+    cell::RefCell, 
+    sync::atomic::{AtomicUsize, Ordering}
+};
+use crate::image::Image;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+pub type TextureRef = Rc<RefCell<Texture>>;
+
+// four channels per texel, rows ordered from top to bottom
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextureData {
+    Rgba8(Vec<u8>),
+    Float(Vec<f32>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wrapping {
+    Repeat,
+    ClampToEdge,
+    MirroredRepeat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+    NearestMipmapNearest,
+    LinearMipmapNearest,
+    NearestMipmapLinear,
+    LinearMipmapLinear,
+}
+
+impl Filter {
+    pub fn uses_mipmaps(
+        &self
+    ) -> bool {
+        !matches!(self, Self::Nearest | Self::Linear)
+    }
+
+    // filtering inside a single level
+    pub fn is_linear(
+        &self
+    ) -> bool {
+        matches!(self, Self::Linear | Self::LinearMipmapNearest | Self::LinearMipmapLinear)
+    }
+}
+
+#[derive(Debug)]
+pub struct Texture {
+    pub(crate) id: usize,
+    pub name: String,
+    width: u32,
+    height: u32,
+    data: TextureData,
+    pub wrap_s: Wrapping,
+    pub wrap_t: Wrapping,
+    pub mag_filter: Filter,
+    pub min_filter: Filter,
+    pub generate_mipmaps: bool,
+    // the first row is the top of the image, so it's flipped to make v = 0 the bottom
+    pub flip_y: bool,
+    pub(crate) version: usize,
+}
+
+impl Clone for Texture {
+    fn clone(
+        &self
+    ) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            name: self.name.clone(),
+            width: self.width,
+            height: self.height,
+            data: self.data.clone(),
+            wrap_s: self.wrap_s,
+            wrap_t: self.wrap_t,
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            generate_mipmaps: self.generate_mipmaps,
+            flip_y: self.flip_y,
+            version: 0,
+        }
+    }
+}
+
+impl Texture {
+    pub fn new(
+        width: u32,
+        height: u32,
+        data: TextureData
+    ) -> Result<Rc<RefCell<Self>>, String> {
+        Self::check_size(width, height, &data)?;
+
+        Ok(Rc::new(RefCell::new(Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            name: String::new(),
+            width,
+            height,
+            data,
+            wrap_s: Wrapping::ClampToEdge,
+            wrap_t: Wrapping::ClampToEdge,
+            mag_filter: Filter::Linear,
+            min_filter: Filter::LinearMipmapLinear,
+            generate_mipmaps: true,
+            flip_y: true,
+            version: 0,
+        })))
+    }
+
+    pub fn from_image(
+        img: &Image
+    ) -> Result<Rc<RefCell<Self>>, String> {
+        Self::new(img.width, img.height, TextureData::Rgba8(img.data.clone()))
+    }
+
+    pub fn load_from_bytes(
+        bytes: &[u8]
+    ) -> Result<Rc<RefCell<Self>>, String> {
+        Self::from_image(&Image::load_from_bytes(bytes)?)
+    }
+
+    pub fn load(
+        path: &str
+    ) -> Result<Rc<RefCell<Self>>, String> {
+        Self::from_image(&Image::load(path)?)
+    }
+
+    fn check_size(
+        width: u32,
+        height: u32,
+        data: &TextureData
+    ) -> Result<(), String> {
+        let expected = (width as usize).checked_mul(height as usize)
+            .and_then(|n| n.checked_mul(4))
+            .ok_or(format!("Texture size {}x{} is too large", width, height))?;
+        let len = match data {
+            TextureData::Rgba8(data) => data.len(),
+            TextureData::Float(data) => data.len(),
+        };
+
+        if len != expected {
+            return Err(format!(
+                "Expected {} values for a {}x{} RGBA texture, got {}", 
+                expected, width, height, len
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_id(
+        &self
+    ) -> usize {
+        self.id
+    }
+
+    pub fn get_width(
+        &self
+    ) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(
+        &self
+    ) -> u32 {
+        self.height
+    }
+
+    pub fn get_data(
+        &self
+    ) -> &TextureData {
+        &self.data
+    }
+
+    pub fn set_data(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: TextureData
+    ) -> Result<(), String> {
+        Self::check_size(width, height, &data)?;
+        
+        self.width = width;
+        self.height = height;
+        self.data = data;
+        self.needs_update();
+        
+        Ok(())
+    }
+
+    // renderers upload the texture again on the next draw;
+    // needed after changing the sampling parameters too
+    pub fn needs_update(
+        &mut self
+    ) {
+        self.version += 1;
+    }
+
+    pub fn get_version(
+        &self
+    ) -> usize {
+        self.version
+    }
+
+    pub fn get_texel(
+        &self,
+        x: u32,
+        y: u32
+    ) -> [f32; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        match &self.data {
+            TextureData::Rgba8(data) => [
+                data[i] as f32 / 255.0,
+                data[i + 1] as f32 / 255.0,
+                data[i + 2] as f32 / 255.0,
+                data[i + 3] as f32 / 255.0,
+            ],
+            TextureData::Float(data) => [
+                data[i],
+                data[i + 1],
+                data[i + 2],
+                data[i + 3],
+            ],
+        }
+    }
+
+    // cpu lookup with the same wrapping and flipping as the gpu; there are no derivatives,
+    // so the base level is always used, with the magnification filter
+    pub fn sample(
+        &self,
+        u: f32,
+        v: f32
+    ) -> [f32; 4] {
+        if self.width == 0 || self.height == 0 {
+            return [0.0, 0.0, 0.0, 1.0];
+        }
+
+        // texel centers are at half-integer coordinates
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+
+        if !self.mag_filter.is_linear() {
+            return self.fetch(x.round() as i64, y.round() as i64);
+        }
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let a = self.fetch(x0, y0);
+        let b = self.fetch(x0 + 1, y0);
+        let c = self.fetch(x0, y0 + 1);
+        let d = self.fetch(x0 + 1, y0 + 1);
+
+        [0, 1, 2, 3].map(|i| {
+            let top = a[i] + (b[i] - a[i]) * fx;
+            let bottom = c[i] + (d[i] - c[i]) * fx;
+            top + (bottom - top) * fy
+        })
+    }
+
+    fn fetch(
+        &self,
+        x: i64,
+        y: i64
+    ) -> [f32; 4] {
+        let x = Self::wrap(x, self.width as i64, self.wrap_s);
+        let mut y = Self::wrap(y, self.height as i64, self.wrap_t);
+        if self.flip_y {
+            y = self.height as i64 - 1 - y;
+        }
+        self.get_texel(x as u32, y as u32)
+    }
+
+    fn wrap(
+        i: i64,
+        size: i64,
+        mode: Wrapping
+    ) -> i64 {
+        match mode {
+            Wrapping::Repeat => {
+                i.rem_euclid(size)
+            },
+            Wrapping::ClampToEdge => {
+                i.clamp(0, size - 1)
+            },
+            Wrapping::MirroredRepeat => {
+                let i = i.rem_euclid(size * 2);
+                if i < size {i} else {size * 2 - 1 - i}
+            },
+        }
+    }
+}